@@ -1,64 +1,41 @@
-use std::borrow::Cow;
 use std::time::Instant;
-use weechat::bar::{BarItem, LightBarItem};
+use weechat::bar::BarItem;
 use weechat::{
-    weechat_plugin, ArgsWeechat, Buffer, CommandDescription, CommandHook,
-    Config, ConfigOption, ConfigSectionInfo, HasHData, NickArgs, StringOption,
-    Weechat, WeechatPlugin, WeechatResult,
+    weechat_plugin, ArgsWeechat, Buffer, BufferBuilder, CommandDescription,
+    CommandHook, Config, ConfigOption, ConfigSectionInfo, HasHData, NickArgs,
+    StringOption, Weechat, WeechatPlugin, WeechatResult,
 };
 
 struct SamplePlugin {
     weechat: Weechat,
-    _rust_hook: CommandHook<String>,
+    _rust_hook: CommandHook,
     _rust_config: Config<String>,
-    _item: BarItem<String>,
+    _item: BarItem,
 }
 
 impl SamplePlugin {
-    fn input_cb(data: &mut String, buffer: Buffer, _input: Cow<str>) {
-        buffer.print(data);
-        if data == "Hello" {
-            data.push_str(" world.");
-        }
-    }
-
-    fn close_cb(_data: &(), buffer: Buffer) {
-        let w = buffer.get_weechat();
-        w.print("Closing buffer")
-    }
-
-    fn rust_command_cb(data: &String, buffer: Buffer, args: ArgsWeechat) {
-        buffer.print(data);
-        for arg in args {
-            buffer.print(&arg)
-        }
-    }
-
     fn option_change_cb(_data: &mut String, option: &StringOption) {
         let weechat = option.get_weechat();
         weechat.print("Changing rust option");
     }
-
-    fn bar_cb(
-        _data: &String,
-        _item: &LightBarItem,
-        _buffer: &Buffer,
-    ) -> String {
-        "rust/sample".to_owned()
-    }
 }
 
 impl WeechatPlugin for SamplePlugin {
     fn init(weechat: Weechat, _args: ArgsWeechat) -> WeechatResult<Self> {
         weechat.print("Hello Rust!");
 
-        let buffer: Buffer = weechat.buffer_new(
-            "Test buffer",
-            Some(SamplePlugin::input_cb),
-            Some("Hello".to_owned()),
-            Some(SamplePlugin::close_cb),
-            None,
-        );
+        let mut state = "Hello".to_owned();
+
+        let buffer: Buffer = BufferBuilder::new("Test buffer")
+            .input_callback(move |_, buffer, _input| {
+                buffer.print(&state);
+                if state == "Hello" {
+                    state.push_str(" world.");
+                }
+            })
+            .close_callback(|weechat, _| weechat.print("Closing buffer"))
+            .build(&weechat)
+            .release();
 
         let buf_name: String =
             buffer.get_hdata("buffer").unwrap().get_var("name").unwrap();
@@ -103,11 +80,20 @@ impl WeechatPlugin for SamplePlugin {
             ..Default::default()
         };
 
-        let command = weechat.hook_command(
+        let greeting = "Hello rust command".to_owned();
+        let mut runs = 0;
+
+        let command = CommandHook::new(
+            &weechat,
             sample_command,
-            SamplePlugin::rust_command_cb,
-            Some("Hello rust command".to_owned()),
-        );
+            move |_: &Weechat, buffer: Buffer, args: ArgsWeechat| {
+                runs += 1;
+                buffer.print(&format!("{} ({})", greeting, runs));
+                for arg in args {
+                    buffer.print(&arg)
+                }
+            },
+        )?;
 
         let mut config =
             weechat.config_new("rust_sample", None, None::<String>);
@@ -129,8 +115,9 @@ impl WeechatPlugin for SamplePlugin {
             None::<String>,
         );
 
-        let item =
-            weechat.new_bar_item("buffer_plugin", SamplePlugin::bar_cb, None);
+        let item = BarItem::new(&weechat, "buffer_plugin", |_, _, _| {
+            "rust/sample".to_owned()
+        });
 
         Ok(SamplePlugin {
             weechat,
@@ -153,5 +140,6 @@ weechat_plugin!(
     author: "poljar",
     description: "",
     version: "0.1.0",
-    license: "MIT"
+    license: "MIT",
+    debug_command: true
 );
@@ -17,6 +17,8 @@ struct WeechatPluginInfo {
     description: (usize, Literal),
     version: (usize, Literal),
     license: (usize, Literal),
+    version_string: LitStr,
+    debug_command: bool,
 }
 
 enum WeechatVariable {
@@ -25,6 +27,7 @@ enum WeechatVariable {
     Description(syn::LitStr),
     Version(syn::LitStr),
     License(syn::LitStr),
+    DebugCommand(syn::LitBool),
 }
 
 impl WeechatVariable {
@@ -42,6 +45,7 @@ impl WeechatVariable {
             WeechatVariable::Description(string) => WeechatVariable::litstr_to_pair(string),
             WeechatVariable::Version(string) => WeechatVariable::litstr_to_pair(string),
             WeechatVariable::License(string) => WeechatVariable::litstr_to_pair(string),
+            WeechatVariable::DebugCommand(_) => unreachable!("not a string variable"),
         }
     }
 }
@@ -50,14 +54,14 @@ impl Parse for WeechatVariable {
     fn parse(input: ParseStream) -> Result<Self> {
         let key: Ident = input.parse()?;
         input.parse::<syn::Token![:]>()?;
-        let value = input.parse()?;
 
         match key.to_string().to_lowercase().as_ref() {
-            "name" => Ok(WeechatVariable::Name(value)),
-            "author" => Ok(WeechatVariable::Author(value)),
-            "description" => Ok(WeechatVariable::Description(value)),
-            "version" => Ok(WeechatVariable::Version(value)),
-            "license" => Ok(WeechatVariable::License(value)),
+            "name" => Ok(WeechatVariable::Name(input.parse()?)),
+            "author" => Ok(WeechatVariable::Author(input.parse()?)),
+            "description" => Ok(WeechatVariable::Description(input.parse()?)),
+            "version" => Ok(WeechatVariable::Version(input.parse()?)),
+            "license" => Ok(WeechatVariable::License(input.parse()?)),
+            "debug_command" => Ok(WeechatVariable::DebugCommand(input.parse()?)),
             _ => Err(Error::new(key.span(), "expected one of bla")),
         }
     }
@@ -80,9 +84,20 @@ impl Parse for WeechatPluginInfo {
                 WeechatVariable::Description(_) => variables.insert("description", *variable),
                 WeechatVariable::Version(_) => variables.insert("version", *variable),
                 WeechatVariable::License(_) => variables.insert("license", *variable),
+                WeechatVariable::DebugCommand(_) => variables.insert("debug_command", *variable),
             };
         }
 
+        let version_string = match variables.get("version") {
+            Some(WeechatVariable::Version(string)) => string.clone(),
+            _ => return Err(input.error("missing the version of the plugin")),
+        };
+
+        let debug_command = match variables.remove("debug_command") {
+            Some(WeechatVariable::DebugCommand(value)) => value.value,
+            _ => false,
+        };
+
         Ok(WeechatPluginInfo {
             plugin,
             name: variables.remove("name").unwrap().as_pair(),
@@ -90,6 +105,8 @@ impl Parse for WeechatPluginInfo {
             description: variables.remove("description").unwrap().as_pair(),
             version: variables.remove("version").unwrap().as_pair(),
             license: variables.remove("license").unwrap().as_pair(),
+            version_string,
+            debug_command,
         })
     }
 }
@@ -110,6 +127,9 @@ impl Parse for WeechatPluginInfo {
 ///     license: "MIT"
 /// );
 /// ```
+///
+/// Adding `debug_command: true` enables the `/<plugin> debug` command, see
+/// the `diagnostics` module of the weechat crate.
 #[proc_macro]
 pub fn weechat_plugin(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let WeechatPluginInfo {
@@ -119,6 +139,8 @@ pub fn weechat_plugin(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         description,
         version,
         license,
+        version_string,
+        debug_command,
     } = parse_macro_input!(input as WeechatPluginInfo);
 
     let (name_len, name) = name;
@@ -127,6 +149,14 @@ pub fn weechat_plugin(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     let (license_len, license) = license;
     let (version_len, version) = version;
 
+    let debug_command = if debug_command {
+        quote! {
+            Weechat::from_ptr(plugin).enable_debug_command(#version_string);
+        }
+    } else {
+        quote! {}
+    };
+
     let result = quote! {
         #[no_mangle]
         pub static weechat_plugin_api_version: [u8; weechat_sys::WEECHAT_PLUGIN_API_VERSION_LENGTH] = *weechat_sys::WEECHAT_PLUGIN_API_VERSION;
@@ -163,6 +193,9 @@ pub fn weechat_plugin(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 Weechat::from_ptr(plugin)
             };
             let args = ArgsWeechat::new(argc, argv);
+            // Enabled before the plugin is initialized so the commands of
+            // the plugin can list the debug subcommand.
+            #debug_command
             match <#plugin as ::weechat::WeechatPlugin>::init(weechat, args) {
                 Ok(p) => {
                     unsafe {
@@ -171,6 +204,9 @@ pub fn weechat_plugin(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                     return weechat_sys::WEECHAT_RC_OK;
                 }
                 Err(_e) => {
+                    // WeeChat doesn't end a plugin that failed to
+                    // initialize.
+                    ::weechat::plugin_end();
                     return weechat_sys::WEECHAT_RC_ERROR;
                 }
             }
@@ -186,6 +222,7 @@ pub fn weechat_plugin(input: proc_macro::TokenStream) -> proc_macro::TokenStream
             unsafe {
                 __PLUGIN = None;
             }
+            ::weechat::plugin_end();
             weechat_sys::WEECHAT_RC_OK
         }
 
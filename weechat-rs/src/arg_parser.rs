@@ -0,0 +1,508 @@
+//! Parse the arguments of a command into a typed struct.
+//!
+//! An `ArgParser` describes the arguments of a command, WeeChat style options
+//! like `-all` or `-port <port>` followed by positional arguments. The parser
+//! generates the `args` and `args_description` help texts of the command and
+//! reports usage errors that are printed to the buffer the command was run
+//! on.
+//!
+//! ```ignore
+//! struct ConnectArgs {
+//!     server: String,
+//!     port: u16,
+//!     ssl: bool,
+//! }
+//!
+//! impl FromArgs for ConnectArgs {
+//!     fn parser() -> ArgParser {
+//!         ArgParser::new()
+//!             .flag("ssl", "connect with SSL")
+//!             .option("port", "port", "port of the server, default 6667")
+//!             .arg("server", "address of the server")
+//!     }
+//!
+//!     fn from_args(args: ParsedArgs) -> Result<Self, UsageError> {
+//!         Ok(ConnectArgs {
+//!             server: args.value("server")?,
+//!             port: args.value_or("port", 6667)?,
+//!             ssl: args.flag("ssl"),
+//!         })
+//!     }
+//! }
+//!
+//! let command = CommandHook::with_args(
+//!     &weechat,
+//!     CommandDescription {
+//!         name: "connect",
+//!         ..Default::default()
+//!     },
+//!     |_, buffer, args: ConnectArgs| connect(&buffer, args),
+//! )?;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ArgsWeechat;
+
+/// A type that can be parsed from the arguments of a command.
+pub trait FromArgs: Sized {
+    /// The parser describing the arguments.
+    fn parser() -> ArgParser;
+
+    /// Convert the parsed arguments into the type.
+    /// * `args` - The arguments parsed by the parser returned by `parser()`.
+    fn from_args(args: ParsedArgs) -> Result<Self, UsageError>;
+}
+
+enum ArgKind {
+    Flag,
+    Option(String),
+    Required,
+    Optional,
+    Rest,
+}
+
+struct Arg {
+    name: String,
+    description: String,
+    kind: ArgKind,
+}
+
+/// A description of the arguments of a command, see the module
+/// documentation.
+#[derive(Default)]
+pub struct ArgParser {
+    args: Vec<Arg>,
+}
+
+/// An error caused by wrong arguments of a command.
+#[derive(Debug, Clone)]
+pub struct UsageError {
+    message: String,
+    usage: String,
+}
+
+/// The arguments of a command that were parsed by an `ArgParser`.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedArgs {
+    values: HashMap<String, String>,
+    flags: Vec<String>,
+    usage: String,
+}
+
+impl ArgParser {
+    /// Create a parser for a command without arguments.
+    pub fn new() -> ArgParser {
+        ArgParser::default()
+    }
+
+    fn push(mut self, name: &str, description: &str, kind: ArgKind) -> Self {
+        self.args.push(Arg {
+            name: name.to_owned(),
+            description: description.to_owned(),
+            kind,
+        });
+        self
+    }
+
+    /// Add a flag, e.g. `-all`.
+    /// * `name` - The name of the flag without the leading `-`.
+    /// * `description` - The description of the flag for the help text.
+    pub fn flag(self, name: &str, description: &str) -> ArgParser {
+        self.push(name, description, ArgKind::Flag)
+    }
+
+    /// Add an option that takes a value, e.g. `-port <port>`.
+    /// * `name` - The name of the option without the leading `-`.
+    /// * `value_name` - The name of the value for the help text.
+    /// * `description` - The description of the option for the help text.
+    pub fn option(
+        self,
+        name: &str,
+        value_name: &str,
+        description: &str,
+    ) -> ArgParser {
+        self.push(name, description, ArgKind::Option(value_name.to_owned()))
+    }
+
+    /// Add a required positional argument.
+    /// * `name` - The name of the argument.
+    /// * `description` - The description of the argument for the help text.
+    pub fn arg(self, name: &str, description: &str) -> ArgParser {
+        self.push(name, description, ArgKind::Required)
+    }
+
+    /// Add an optional positional argument, it has to come after the
+    /// required ones.
+    /// * `name` - The name of the argument.
+    /// * `description` - The description of the argument for the help text.
+    pub fn optional_arg(self, name: &str, description: &str) -> ArgParser {
+        self.push(name, description, ArgKind::Optional)
+    }
+
+    /// Collect the remaining arguments into a single value, e.g. the text of
+    /// a message. It has to be the last argument.
+    /// * `name` - The name of the argument.
+    /// * `description` - The description of the argument for the help text.
+    pub fn rest(self, name: &str, description: &str) -> ArgParser {
+        self.push(name, description, ArgKind::Rest)
+    }
+
+    /// Generate the `args` help text of the command, e.g.
+    /// `[-ssl] [-port <port>] <server>`.
+    pub fn args(&self) -> String {
+        self.args
+            .iter()
+            .map(|arg| match &arg.kind {
+                ArgKind::Flag => format!("[-{}]", arg.name),
+                ArgKind::Option(value) => {
+                    format!("[-{} <{}>]", arg.name, value)
+                }
+                ArgKind::Required => format!("<{}>", arg.name),
+                ArgKind::Optional => format!("[<{}>]", arg.name),
+                ArgKind::Rest => format!("[<{}>...]", arg.name),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Generate the `args_description` help text of the command, the names
+    /// are right aligned like in the help of WeeChat commands.
+    pub fn args_description(&self) -> String {
+        let name = |arg: &Arg| match arg.kind {
+            ArgKind::Flag | ArgKind::Option(_) => format!("-{}", arg.name),
+            _ => arg.name.clone(),
+        };
+
+        let width = self
+            .args
+            .iter()
+            .map(|arg| name(arg).chars().count())
+            .max()
+            .unwrap_or_default();
+
+        self.args
+            .iter()
+            .map(|arg| {
+                format!(
+                    "{:>width$}: {}",
+                    name(arg),
+                    arg.description,
+                    width = width
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Parse the arguments of a command.
+    ///
+    /// Returns an error describing the problem and the usage of the command
+    /// if the arguments don't match the parser, e.g. for an unknown option
+    /// before the first positional argument.
+    /// * `args` - The arguments of the command, including the command itself
+    ///     as the first argument.
+    pub fn parse(
+        &self,
+        mut args: ArgsWeechat,
+    ) -> Result<ParsedArgs, UsageError> {
+        let command = args.next().unwrap_or_default();
+        let usage = format!("{} {}", command, self.args()).trim().to_owned();
+        let error = |message: String| UsageError {
+            message,
+            usage: usage.clone(),
+        };
+
+        let mut parsed = ParsedArgs {
+            usage: usage.clone(),
+            ..Default::default()
+        };
+        let mut positionals = Vec::new();
+
+        while let Some(arg) = args.next() {
+            // Options are only recognized before the first positional
+            // argument, e.g. the text of a message may start with a dash.
+            let option = match arg.strip_prefix('-') {
+                Some(name) if !name.is_empty() && positionals.is_empty() => {
+                    let option = self.args.iter().find(|a| {
+                        a.name == name
+                            && matches!(
+                                a.kind,
+                                ArgKind::Flag | ArgKind::Option(_)
+                            )
+                    });
+
+                    match option {
+                        Some(option) => Some(option),
+                        None => {
+                            return Err(error(format!(
+                                "unknown option {}",
+                                arg
+                            )))
+                        }
+                    }
+                }
+                _ => None,
+            };
+
+            match option.map(|option| (option, &option.kind)) {
+                Some((option, ArgKind::Flag)) => {
+                    parsed.flags.push(option.name.clone());
+                }
+                Some((option, ArgKind::Option(_))) => {
+                    let value = args.next().ok_or_else(|| {
+                        error(format!("missing value for -{}", option.name))
+                    })?;
+                    parsed.values.insert(option.name.clone(), value);
+                }
+                _ => positionals.push(arg),
+            }
+        }
+
+        let mut positionals = positionals.into_iter();
+
+        for arg in &self.args {
+            match arg.kind {
+                ArgKind::Required => {
+                    let value = positionals.next().ok_or_else(|| {
+                        error(format!("missing argument <{}>", arg.name))
+                    })?;
+                    parsed.values.insert(arg.name.clone(), value);
+                }
+                ArgKind::Optional => {
+                    if let Some(value) = positionals.next() {
+                        parsed.values.insert(arg.name.clone(), value);
+                    }
+                }
+                ArgKind::Rest => {
+                    let rest: Vec<String> = positionals.by_ref().collect();
+                    if !rest.is_empty() {
+                        parsed.values.insert(arg.name.clone(), rest.join(" "));
+                    }
+                }
+                ArgKind::Flag | ArgKind::Option(_) => (),
+            }
+        }
+
+        match positionals.next() {
+            Some(extra) => Err(error(format!("unexpected argument {}", extra))),
+            None => Ok(parsed),
+        }
+    }
+}
+
+impl ParsedArgs {
+    /// Was the flag given.
+    /// * `name` - The name of the flag without the leading `-`.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    /// Get the raw value of an argument or option, None if it wasn't given.
+    /// * `name` - The name of the argument or the option.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Parse the value of an argument or option.
+    ///
+    /// Returns an error if the value is missing or can't be parsed.
+    /// * `name` - The name of the argument or the option.
+    pub fn value<T: FromStr>(&self, name: &str) -> Result<T, UsageError> {
+        match self.get(name) {
+            Some(value) => self.parse_value(name, value),
+            None => Err(self.error(format!("missing value for {}", name))),
+        }
+    }
+
+    /// Parse the value of an argument or option, returning the default if it
+    /// wasn't given.
+    ///
+    /// Returns an error if the value can't be parsed.
+    /// * `name` - The name of the argument or the option.
+    /// * `default` - The value that is used if the argument wasn't given.
+    pub fn value_or<T: FromStr>(
+        &self,
+        name: &str,
+        default: T,
+    ) -> Result<T, UsageError> {
+        match self.get(name) {
+            Some(value) => self.parse_value(name, value),
+            None => Ok(default),
+        }
+    }
+
+    /// Parse the value of an argument or option if it was given.
+    ///
+    /// Returns an error if the value can't be parsed.
+    /// * `name` - The name of the argument or the option.
+    pub fn optional_value<T: FromStr>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, UsageError> {
+        self.get(name)
+            .map(|value| self.parse_value(name, value))
+            .transpose()
+    }
+
+    /// Create a usage error, e.g. for arguments that can't be combined.
+    /// * `message` - The description of the problem.
+    pub fn error(&self, message: impl Into<String>) -> UsageError {
+        UsageError {
+            message: message.into(),
+            usage: self.usage.clone(),
+        }
+    }
+
+    fn parse_value<T: FromStr>(
+        &self,
+        name: &str,
+        value: &str,
+    ) -> Result<T, UsageError> {
+        value.parse().map_err(|_| {
+            self.error(format!("invalid value \"{}\" for {}", value, name))
+        })
+    }
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (usage: {})", self.message, self.usage)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::os::raw::c_char;
+
+    fn parse(parser: &ArgParser, line: &str) -> Result<ParsedArgs, UsageError> {
+        let words: Vec<&str> = line.split(' ').collect();
+        let argv: Vec<CString> =
+            words.iter().map(|w| CString::new(*w).unwrap()).collect();
+        let argv_eol: Vec<CString> = (0..words.len())
+            .map(|i| CString::new(words[i..].join(" ")).unwrap())
+            .collect();
+
+        let mut argv_ptrs: Vec<*mut c_char> =
+            argv.iter().map(|a| a.as_ptr() as *mut c_char).collect();
+        let mut argv_eol_ptrs: Vec<*mut c_char> =
+            argv_eol.iter().map(|a| a.as_ptr() as *mut c_char).collect();
+
+        let args = ArgsWeechat::with_eol(
+            words.len() as i32,
+            argv_ptrs.as_mut_ptr(),
+            argv_eol_ptrs.as_mut_ptr(),
+        );
+
+        parser.parse(args)
+    }
+
+    fn connect_parser() -> ArgParser {
+        ArgParser::new()
+            .flag("ssl", "connect with SSL")
+            .option("port", "port", "port of the server")
+            .arg("server", "address of the server")
+            .rest("message", "a message")
+    }
+
+    #[test]
+    fn help_texts() {
+        let parser = connect_parser();
+
+        assert_eq!(
+            parser.args(),
+            "[-ssl] [-port <port>] <server> [<message>...]"
+        );
+        assert_eq!(
+            parser.args_description().lines().collect::<Vec<_>>(),
+            [
+                "   -ssl: connect with SSL",
+                "  -port: port of the server",
+                " server: address of the server",
+                "message: a message",
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_options_and_positionals() {
+        let parsed =
+            parse(&connect_parser(), "/connect -ssl -port 6697 libera hi all")
+                .unwrap();
+
+        assert!(parsed.flag("ssl"));
+        assert_eq!(parsed.value::<u16>("port").unwrap(), 6697);
+        assert_eq!(parsed.get("server"), Some("libera"));
+        assert_eq!(parsed.get("message"), Some("hi all"));
+    }
+
+    #[test]
+    fn defaults_and_missing_values() {
+        let parsed = parse(&connect_parser(), "/connect libera").unwrap();
+
+        assert!(!parsed.flag("ssl"));
+        assert_eq!(parsed.value_or("port", 6667u16).unwrap(), 6667);
+        assert_eq!(parsed.optional_value::<u16>("port").unwrap(), None);
+        assert_eq!(parsed.get("message"), None);
+        assert!(parsed.value::<String>("message").is_err());
+    }
+
+    #[test]
+    fn dashes_after_the_first_positional_are_values() {
+        let parsed =
+            parse(&connect_parser(), "/connect libera -ssl -x").unwrap();
+
+        assert!(!parsed.flag("ssl"));
+        assert_eq!(parsed.get("message"), Some("-ssl -x"));
+    }
+
+    #[test]
+    fn unknown_option_is_an_error() {
+        let error = parse(&connect_parser(), "/connect -tls libera")
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(
+            error,
+            "unknown option -tls (usage: /connect [-ssl] [-port <port>] \
+             <server> [<message>...])"
+        );
+    }
+
+    #[test]
+    fn usage_errors() {
+        let parser = connect_parser();
+
+        assert!(parse(&parser, "/connect")
+            .unwrap_err()
+            .to_string()
+            .starts_with("missing argument <server>"));
+        assert!(parse(&parser, "/connect -port")
+            .unwrap_err()
+            .to_string()
+            .starts_with("missing value for -port"));
+
+        let parsed = parse(&parser, "/connect -port abc libera").unwrap();
+        assert!(parsed
+            .value::<u16>("port")
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid value \"abc\" for port"));
+    }
+
+    #[test]
+    fn unexpected_argument() {
+        let parser = ArgParser::new().arg("name", "a name");
+
+        assert!(parse(&parser, "/cmd a b")
+            .unwrap_err()
+            .to_string()
+            .starts_with("unexpected argument b"));
+    }
+}
@@ -1,32 +1,176 @@
 use core::ptr;
 use libc::c_char;
+use std::cell::RefCell;
+use std::io::{Read, Write};
 use std::os::raw::c_void;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use weechat_sys::{
-    t_gui_bar_item, t_gui_buffer, t_gui_window, t_hashtable, t_weechat_plugin,
+    t_gui_bar, t_gui_bar_item, t_gui_buffer, t_gui_window, t_hashtable,
+    t_weechat_plugin,
 };
 
-use crate::{Buffer, LossyCString, Weechat};
+use crate::hdata::{HData, HasHData};
+use crate::{Buffer, FdHook, FdHookMode, LossyCString, Weechat};
 
-struct BarItemCbData<T> {
-    callback: fn(&T, &LightBarItem, &Buffer) -> String,
-    callback_data: T,
+type BarItemCallback = dyn FnMut(&Weechat, &LightBarItem, &Buffer) -> String;
+
+struct BarItemCbData {
+    callback: RefCell<Box<BarItemCallback>>,
     weechat_ptr: *mut t_weechat_plugin,
 }
 
 /// A handle to a bar item. The bar item is automatically removed when the object is
 /// dropped.
-pub struct BarItem<T> {
+pub struct BarItem {
     item: LightBarItem,
-    _data: Box<BarItemCbData<T>>,
+    _data: Box<BarItemCbData>,
 }
 
 /// A handle to a bar item that is passed to callbacks.
 pub struct LightBarItem {
-    ptr: *mut t_gui_bar_item,
+    pub(crate) ptr: *mut t_gui_bar_item,
+    pub(crate) weechat_ptr: *mut t_weechat_plugin,
+}
+
+/// A bar item that displays a text, the text is stored by the bar item so no
+/// callback is needed.
+///
+/// The bar item is automatically removed when the object is dropped.
+pub struct StaticBarItem {
+    _item: BarItem,
+    text: Rc<RefCell<String>>,
+    name: String,
     weechat_ptr: *mut t_weechat_plugin,
 }
 
-impl<T> Drop for BarItem<T> {
+impl StaticBarItem {
+    /// Get the text of the bar item.
+    pub fn text(&self) -> String {
+        self.text.borrow().clone()
+    }
+
+    /// Set the text of the bar item, the bar item is updated immediately.
+    /// * `text` - The new text of the bar item, may contain color codes.
+    pub fn set_text(&self, text: impl Into<String>) {
+        *self.text.borrow_mut() = text.into();
+        Weechat::from_ptr(self.weechat_ptr).update_bar_item(&self.name);
+    }
+}
+
+/// A bar item whose text can be updated from any thread.
+///
+/// The bar item is automatically removed when the object is dropped.
+pub struct AsyncBarItem {
+    _item: BarItem,
+    _hook: FdHook<BarItemWakeup>,
+    handle: AsyncBarItemHandle,
+}
+
+/// A handle to update the text of an `AsyncBarItem`, the handle can be cloned
+/// and sent to other threads.
+#[derive(Clone)]
+pub struct AsyncBarItemHandle {
+    text: Arc<Mutex<String>>,
+    waker: Arc<UnixStream>,
+}
+
+/// The reading end of the socket that wakes up the main thread when the text
+/// of an `AsyncBarItem` changes.
+struct BarItemWakeup {
+    reader: UnixStream,
+    name: String,
+}
+
+impl AsRawFd for BarItemWakeup {
+    fn as_raw_fd(&self) -> RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl AsyncBarItemHandle {
+    /// Set the text of the bar item.
+    ///
+    /// The bar item is updated the next time the WeeChat main loop runs.
+    /// * `text` - The new text of the bar item, may contain color codes.
+    pub fn set_text(&self, text: impl Into<String>) {
+        *self.text.lock().unwrap() = text.into();
+
+        // If the socket is full a wakeup is already pending, so the error can
+        // be ignored.
+        let _ = (&*self.waker).write(&[0]);
+    }
+}
+
+impl AsyncBarItem {
+    /// Get a handle that can be used to update the text of the bar item from
+    /// other threads.
+    pub fn handle(&self) -> AsyncBarItemHandle {
+        self.handle.clone()
+    }
+
+    /// Set the text of the bar item.
+    /// * `text` - The new text of the bar item, may contain color codes.
+    pub fn set_text(&self, text: impl Into<String>) {
+        self.handle.set_text(text);
+    }
+}
+
+/// A handle to a bar. The bar isn't removed when the object is dropped.
+pub struct Bar {
+    pub(crate) ptr: *mut t_gui_bar,
+    pub(crate) weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl Bar {
+    /// Get the name of the bar.
+    pub fn name(&self) -> Option<String> {
+        self.get_hdata("bar")?.get_var("name")
+    }
+}
+
+impl HasHData for Bar {
+    fn get_hdata(&self, name: &str) -> Option<HData> {
+        let hdata_get =
+            Weechat::from_ptr(self.weechat_ptr).get().hdata_get.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe {
+            let hdata = hdata_get(self.weechat_ptr, name.as_ptr());
+            if hdata.is_null() {
+                None
+            } else {
+                Some(HData {
+                    weechat_ptr: self.weechat_ptr,
+                    object: self.ptr as *mut c_void,
+                    ptr: hdata,
+                })
+            }
+        }
+    }
+}
+
+impl BarItem {
+    /// Create a new bar item that can be added by a user.
+    ///
+    /// The callback returns the content of the bar item, it is called every
+    /// time the bar item is updated, e.g. with `Weechat::update_bar_item`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `name` - The name of the bar item.
+    /// * `callback` - A closure that returns the content of the bar item for
+    ///     the given buffer, it may contain color codes.
+    pub fn new<C>(weechat: &Weechat, name: &str, callback: C) -> BarItem
+    where
+        C: FnMut(&Weechat, &LightBarItem, &Buffer) -> String + 'static,
+    {
+        weechat.new_bar_item_with(name, Box::new(callback))
+    }
+}
+
+impl Drop for BarItem {
     fn drop(&mut self) {
         let weechat = Weechat::from_ptr(self.item.weechat_ptr);
         let bar_item_remove = weechat.get().bar_item_remove.unwrap();
@@ -37,16 +181,33 @@ impl<T> Drop for BarItem<T> {
 impl Weechat {
     /// Create a new bar item that can be added by a user.
     // TODO: Provide window object, the callback should accept a Window object wrapping a t_gui_window
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `BarItem::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
     pub fn new_bar_item<T>(
         &self,
         name: &str,
         callback: fn(data: &T, item: &LightBarItem, buffer: &Buffer) -> String,
         callback_data: Option<T>,
-    ) -> BarItem<T>
+    ) -> BarItem
     where
-        T: Default,
+        T: Default + 'static,
     {
-        unsafe extern "C" fn c_item_cb<T>(
+        let data = callback_data.unwrap_or_default();
+
+        self.new_bar_item_with(
+            name,
+            Box::new(move |_, item, buffer| callback(&data, item, buffer)),
+        )
+    }
+
+    fn new_bar_item_with(
+        &self,
+        name: &str,
+        callback: Box<BarItemCallback>,
+    ) -> BarItem {
+        unsafe extern "C" fn c_item_cb(
             pointer: *const c_void,
             _data: *mut c_void,
             bar_item: *mut t_gui_bar_item,
@@ -54,25 +215,28 @@ impl Weechat {
             buffer: *mut t_gui_buffer,
             _extra_info: *mut t_hashtable,
         ) -> *mut c_char {
-            let data: &mut BarItemCbData<T> =
-                { &mut *(pointer as *mut BarItemCbData<T>) };
-            let callback = data.callback;
-            let callback_data = &data.callback_data;
-            let buffer = Buffer::from_ptr(data.weechat_ptr, buffer);
+            let data: &BarItemCbData = &*(pointer as *const BarItemCbData);
+            let weechat = Weechat::from_ptr(data.weechat_ptr);
+            let buffer = Buffer::wrap(data.weechat_ptr, buffer);
 
             let item = LightBarItem {
                 ptr: bar_item,
                 weechat_ptr: data.weechat_ptr,
             };
 
-            let ret = callback(&callback_data, &item, &buffer);
+            // Updating the bar item from its own callback would run the
+            // callback again, show nothing instead.
+            let ret = match data.callback.try_borrow_mut() {
+                Ok(mut callback) => callback(&weechat, &item, &buffer),
+                Err(_) => String::new(),
+            };
+
             // weechat wants malloc'ed string
             libc::strdup(LossyCString::new(ret).as_ptr())
         }
 
-        let data = Box::new(BarItemCbData::<T> {
-            callback,
-            callback_data: callback_data.unwrap_or_default(),
+        let data = Box::new(BarItemCbData {
+            callback: RefCell::new(callback),
             weechat_ptr: self.ptr,
         });
 
@@ -85,7 +249,7 @@ impl Weechat {
             bar_item_new(
                 self.ptr,
                 bar_item_name.as_ptr(),
-                Some(c_item_cb::<T>),
+                Some(c_item_cb),
                 data_ref as *const _ as *const c_void,
                 ptr::null_mut(),
             )
@@ -102,6 +266,91 @@ impl Weechat {
         }
     }
 
+    /// Create a new bar item that displays a text.
+    /// * `name` - The name of the bar item.
+    /// * `text` - The initial text of the bar item, may contain color codes.
+    pub fn new_static_bar_item(
+        &self,
+        name: &str,
+        text: impl Into<String>,
+    ) -> StaticBarItem {
+        let text = Rc::new(RefCell::new(text.into()));
+        let item_text = text.clone();
+        let item =
+            BarItem::new(self, name, move |_, _, _| item_text.borrow().clone());
+
+        StaticBarItem {
+            _item: item,
+            text,
+            name: name.to_owned(),
+            weechat_ptr: self.ptr,
+        }
+    }
+
+    /// Create a new bar item whose text can be set from any thread.
+    ///
+    /// Returns an error if the socket that is used to wake up the main thread
+    /// can't be created.
+    /// * `name` - The name of the bar item.
+    pub fn new_async_bar_item(
+        &self,
+        name: &str,
+    ) -> std::io::Result<AsyncBarItem> {
+        fn wakeup_cb(weechat: &Weechat, wakeup: &mut BarItemWakeup) {
+            let mut buf = [0; 64];
+            while let Ok(n) = wakeup.reader.read(&mut buf) {
+                if n < buf.len() {
+                    break;
+                }
+            }
+
+            weechat.update_bar_item(&wakeup.name);
+        }
+
+        let (reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+
+        let text = Arc::new(Mutex::new(String::new()));
+        let item_text = text.clone();
+        let item = BarItem::new(self, name, move |_, _, _| {
+            item_text.lock().unwrap().clone()
+        });
+
+        let wakeup = BarItemWakeup {
+            reader,
+            name: name.to_owned(),
+        };
+        let hook = FdHook::new(self, wakeup, FdHookMode::Read, wakeup_cb);
+
+        Ok(AsyncBarItem {
+            _item: item,
+            _hook: hook,
+            handle: AsyncBarItemHandle {
+                text,
+                waker: Arc::new(writer),
+            },
+        })
+    }
+
+    /// Search a bar by name.
+    pub fn bar_search(&self, name: &str) -> Option<Bar> {
+        let bar_search = self.get().bar_search.unwrap();
+
+        let name = LossyCString::new(name);
+
+        let bar_ptr = unsafe { bar_search(name.as_ptr()) };
+
+        if bar_ptr.is_null() {
+            None
+        } else {
+            Some(Bar {
+                ptr: bar_ptr,
+                weechat_ptr: self.ptr,
+            })
+        }
+    }
+
     /// Triggers a bar update to update by calling its callback
     pub fn update_bar_item(&self, name: &str) {
         let bar_item_update = self.get().bar_item_update.unwrap();
@@ -1,12 +1,21 @@
 //! Weechat Buffer module containing Buffer and Nick types.
-use crate::{LossyCString, Weechat};
+use crate::{
+    print_metrics, BufferRef, ConfigOption, HasHData, LossyCString,
+    ModifierHook, NotSupported, OwnedBuffer, ReturnCode, StyledText, Weechat,
+};
+use chrono::{Local, NaiveDate, TimeZone, Utc};
 use libc::{c_char, c_int};
 use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
 use std::ffi::CStr;
+use std::fmt::Write;
 use std::os::raw::c_void;
 use std::ptr;
+use std::rc::Rc;
 use weechat_sys::{
-    t_gui_buffer, t_gui_nick, t_gui_nick_group, t_weechat_plugin, WEECHAT_RC_OK,
+    t_gui_buffer, t_gui_nick, t_gui_nick_group, t_weechat_plugin,
+    WEECHAT_RC_ERROR, WEECHAT_RC_OK,
 };
 
 /// A high level Buffer type encapsulating weechats C buffer pointer.
@@ -23,12 +32,194 @@ impl PartialEq for Buffer {
     }
 }
 
-pub(crate) struct BufferPointers<A, B> {
-    pub(crate) weechat: *mut t_weechat_plugin,
-    pub(crate) input_cb: Option<fn(&mut A, Buffer, Cow<str>)>,
-    pub(crate) input_data: A,
-    pub(crate) close_cb: Option<fn(&B, Buffer)>,
-    pub(crate) close_cb_data: B,
+/// Input that was entered into the input line of a buffer.
+pub enum BufferInput<'a> {
+    /// A command, including the leading command char, e.g. `/foo bar`.
+    ///
+    /// Only commands that are unknown to WeeChat are passed to the input
+    /// callback of a buffer.
+    Command(Cow<'a, str>),
+    /// Plain text, a leading escaped command char (e.g. `//foo`) has already
+    /// been removed.
+    Text(Cow<'a, str>),
+}
+
+/// The way WeeChat treats input that is entered into a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    /// The input is a command, e.g. `/join`.
+    Command,
+    /// The input starts with an escaped command char, e.g. `//join`, the
+    /// first command char is removed and the rest is sent as text.
+    EscapedCommand,
+    /// The input is plain text.
+    Text,
+}
+
+type BufferTextCallback = dyn FnMut(&Weechat, BufferRef, Cow<str>);
+type BufferCommandCallback = dyn FnMut(&Weechat, BufferRef, BufferInput);
+type BufferCloseCallback = dyn FnOnce(&Weechat, BufferRef);
+
+enum BufferInputCallback {
+    Text(Box<BufferTextCallback>),
+    Command(Box<BufferCommandCallback>),
+}
+
+struct BufferCallbacks {
+    weechat: *mut t_weechat_plugin,
+    input: RefCell<Option<BufferInputCallback>>,
+    close: Cell<Option<Box<BufferCloseCallback>>>,
+    /// Set once the buffer is closed, shared with the `OwnedBuffer`.
+    closed: Rc<Cell<bool>>,
+    /// The lines that were entered into the buffer as the user typed them,
+    /// only recorded for a command input callback, see `RawInput`.
+    raw_input: RefCell<Option<RawInput>>,
+}
+
+/// Records the input of a buffer before WeeChat processes it.
+///
+/// WeeChat removes the escape of an escaped command before the text reaches
+/// the input callback, `//foo` arrives as `/foo` just like the unknown
+/// command `/foo`. The raw lines tell the two apart.
+struct RawInput {
+    lines: Rc<RefCell<VecDeque<String>>>,
+    _hook: ModifierHook,
+}
+
+impl RawInput {
+    fn new(buffer: &Buffer) -> RawInput {
+        let weechat = buffer.get_weechat();
+        let lines = Rc::new(RefCell::new(VecDeque::new()));
+        let buffer_ptr = format!("{:p}", buffer.ptr);
+
+        let hook_lines = Rc::clone(&lines);
+        let hook = ModifierHook::new(
+            &weechat,
+            // Run after the modifiers of other plugins, they may change the
+            // input.
+            "0|input_text_for_buffer",
+            move |_, _, modifier_data, string| {
+                if modifier_data == buffer_ptr {
+                    let mut lines = hook_lines.borrow_mut();
+                    lines.clear();
+                    lines.extend(string.split('\n').map(str::to_owned));
+                }
+                None
+            },
+        );
+
+        RawInput { lines, _hook: hook }
+    }
+
+    /// Find out if the input WeeChat passed to the input callback is a
+    /// command or plain text by finding the line it came from.
+    fn classify<'a>(
+        &self,
+        buffer: &Buffer,
+        input: Cow<'a, str>,
+    ) -> BufferInput<'a> {
+        let mut lines = self.lines.borrow_mut();
+
+        // Lines that don't match were known commands, WeeChat ran them
+        // instead of passing them to the input callback.
+        while let Some(line) = lines.pop_front() {
+            match buffer.parse_input(&line) {
+                BufferInput::Command(command) if command == input => {
+                    return BufferInput::Command(input);
+                }
+                BufferInput::Text(text) if text == input => {
+                    return BufferInput::Text(input);
+                }
+                _ => (),
+            }
+        }
+
+        // The input didn't come from the input line, e.g. another plugin
+        // sent it with `input_data`.
+        BufferInput::Text(input)
+    }
+}
+
+/// The name and the callbacks of a new buffer.
+///
+/// ```ignore
+/// let buffer = BufferBuilder::new("my_buffer")
+///     .input_callback(|_, buffer, input| {
+///         buffer.print(&format!("You wrote: {}", input));
+///     })
+///     .close_callback(|weechat, _| {
+///         weechat.print("The buffer was closed");
+///     })
+///     .build(&weechat);
+/// ```
+pub struct BufferBuilder {
+    name: String,
+    input: Option<BufferInputCallback>,
+    close: Option<Box<BufferCloseCallback>>,
+}
+
+impl BufferBuilder {
+    /// Start the creation of a buffer.
+    /// * `name` - The name of the new buffer.
+    pub fn new(name: &str) -> BufferBuilder {
+        BufferBuilder {
+            name: name.to_owned(),
+            input: None,
+            close: None,
+        }
+    }
+
+    /// Set the callback that is called when text is entered into the input
+    /// line of the buffer.
+    ///
+    /// Replaces a callback that was set with `command_input_callback`.
+    /// * `callback` - The closure that receives the input.
+    pub fn input_callback(
+        mut self,
+        callback: impl FnMut(&Weechat, BufferRef, Cow<str>) + 'static,
+    ) -> BufferBuilder {
+        self.input = Some(BufferInputCallback::Text(Box::new(callback)));
+        self
+    }
+
+    /// Set the callback that is called when text or an unknown command is
+    /// entered into the input line of the buffer.
+    ///
+    /// Commands that WeeChat doesn't know about are passed to the callback
+    /// instead of printing an error, the callback can distinguish such
+    /// commands from plain text. Replaces a callback that was set with
+    /// `input_callback`.
+    /// * `callback` - The closure that receives the input.
+    pub fn command_input_callback(
+        mut self,
+        callback: impl FnMut(&Weechat, BufferRef, BufferInput) + 'static,
+    ) -> BufferBuilder {
+        self.input = Some(BufferInputCallback::Command(Box::new(callback)));
+        self
+    }
+
+    /// Set the callback that is called when the buffer is closed.
+    ///
+    /// The closure is dropped together with the input callback once the
+    /// buffer is closed, so data that needs to be persisted or cleaned up
+    /// can be moved into it.
+    /// * `callback` - The closure that is called with the closing buffer.
+    pub fn close_callback(
+        mut self,
+        callback: impl FnOnce(&Weechat, BufferRef) + 'static,
+    ) -> BufferBuilder {
+        self.close = Some(Box::new(callback));
+        self
+    }
+
+    /// Create the buffer.
+    ///
+    /// The buffer is closed when the returned handle is dropped, see
+    /// `OwnedBuffer::release` to keep it open.
+    /// * `weechat` - The Weechat object of the plugin.
+    pub fn build(self, weechat: &Weechat) -> OwnedBuffer {
+        weechat.new_buffer(self)
+    }
 }
 
 impl Weechat {
@@ -56,8 +247,53 @@ impl Weechat {
         if buf_ptr.is_null() {
             None
         } else {
-            Some(Buffer::from_ptr(self.ptr, buf_ptr))
+            Some(Buffer::wrap(self.ptr, buf_ptr))
+        }
+    }
+
+    /// Find out how input for a buffer will be treated by WeeChat.
+    /// * `text` - The input that should be checked.
+    pub fn input_for_buffer(&self, text: &str) -> InputKind {
+        match self.input_text_offset(text) {
+            None => InputKind::Command,
+            Some(0) => InputKind::Text,
+            Some(_) => InputKind::EscapedCommand,
+        }
+    }
+
+    /// Get the offset in bytes where the text that should be sent to a
+    /// buffer starts, None if the input is a command.
+    fn input_text_offset(&self, text: &str) -> Option<usize> {
+        let string_input_for_buffer =
+            self.get().string_input_for_buffer.unwrap();
+
+        // Strip the nul bytes the same way LossyCString does so the offset
+        // matches the Rust string.
+        let text = text.replace('\0', "");
+        let c_text = LossyCString::new(&text);
+
+        unsafe {
+            let result = string_input_for_buffer(c_text.as_ptr());
+
+            if result.is_null() {
+                None
+            } else {
+                Some(result.offset_from(c_text.as_ptr()) as usize)
+            }
+        }
+    }
+
+    /// Search a buffer of any plugin by its full name.
+    /// * `full_name` - The full name of the buffer, e.g. `irc.libera.#rust`.
+    ///
+    /// Returns a Buffer if one is found, otherwise None. An empty name
+    /// returns None, WeeChat would return the current buffer for it.
+    pub fn buffer_search_full_name(&self, full_name: &str) -> Option<Buffer> {
+        if full_name.is_empty() {
+            return None;
         }
+
+        self.buffer_search("==", full_name)
     }
 
     /// Get the currently open buffer
@@ -69,7 +305,7 @@ impl Weechat {
         if buf_ptr.is_null() {
             None
         } else {
-            Some(Buffer::from_ptr(self.ptr, buf_ptr))
+            Some(Buffer::wrap(self.ptr, buf_ptr))
         }
     }
 
@@ -78,76 +314,204 @@ impl Weechat {
     /// * `input_cb` - Callback that will be called when something is entered
     ///     into the input bar of the buffer
     /// * `input_data` - Data that will be taken over by weechat and passed to
-    ///     the input callback, this data will be freed when the buffer closes
+    ///     the input callback, this data will be handed to the close callback
+    ///     or freed when the buffer closes
     /// * `close_cb` - Callback that will be called when the buffer is closed.
+    ///     The callback takes back ownership of the `input_data` so it can be
+    ///     persisted or cleaned up.
     /// * `close_cb_data` - Reference to some data that will be passed to the
     ///     close callback.
-    pub fn buffer_new<A: Default, B: Default>(
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `BufferBuilder` with closures instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn buffer_new<A: Default + 'static, B: Default + 'static>(
         &self,
         name: &str,
         input_cb: Option<fn(&mut A, Buffer, Cow<str>)>,
         input_data: Option<A>,
-        close_cb: Option<fn(&B, Buffer)>,
+        close_cb: Option<fn(&B, Buffer, A)>,
+        close_cb_data: Option<B>,
+    ) -> Buffer {
+        let input_data = Rc::new(RefCell::new(input_data.unwrap_or_default()));
+        let mut builder = BufferBuilder::new(name);
+
+        if let Some(input_cb) = input_cb {
+            let input_data = input_data.clone();
+            builder = builder.input_callback(move |_, buffer, input| {
+                let buffer = Buffer::wrap(buffer.weechat, buffer.ptr);
+                input_cb(&mut input_data.borrow_mut(), buffer, input)
+            });
+        }
+
+        builder
+            .close_callback(Self::legacy_close_cb(
+                close_cb,
+                close_cb_data,
+                input_data,
+            ))
+            .build(self)
+            .release()
+    }
+
+    /// Create a new Weechat buffer that implements its own commands.
+    ///
+    /// Commands that WeeChat doesn't know about are passed to the input
+    /// callback of the buffer instead of printing an error, the callback can
+    /// distinguish such commands from plain text.
+    /// * `name` - Name of the new buffer
+    /// * `input_cb` - Callback that will be called when text or an unknown
+    ///     command is entered into the input bar of the buffer
+    /// * `input_data` - Data that will be taken over by weechat and passed to
+    ///     the input callback, this data will be handed to the close callback
+    ///     or freed when the buffer closes
+    /// * `close_cb` - Callback that will be called when the buffer is closed.
+    ///     The callback takes back ownership of the `input_data` so it can be
+    ///     persisted or cleaned up.
+    /// * `close_cb_data` - Reference to some data that will be passed to the
+    ///     close callback.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `BufferBuilder` with closures instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn buffer_new_with_commands<
+        A: Default + 'static,
+        B: Default + 'static,
+    >(
+        &self,
+        name: &str,
+        input_cb: fn(&mut A, Buffer, BufferInput),
+        input_data: Option<A>,
+        close_cb: Option<fn(&B, Buffer, A)>,
         close_cb_data: Option<B>,
     ) -> Buffer {
-        unsafe extern "C" fn c_input_cb<A, B>(
+        let input_data = Rc::new(RefCell::new(input_data.unwrap_or_default()));
+        let callback_data = input_data.clone();
+
+        BufferBuilder::new(name)
+            .command_input_callback(move |_, buffer, input| {
+                let buffer = Buffer::wrap(buffer.weechat, buffer.ptr);
+                input_cb(&mut callback_data.borrow_mut(), buffer, input)
+            })
+            .close_callback(Self::legacy_close_cb(
+                close_cb,
+                close_cb_data,
+                input_data,
+            ))
+            .build(self)
+            .release()
+    }
+
+    /// Wrap the close callback of the deprecated buffer constructors, the
+    /// input data is handed to the callback once the buffer closes.
+    fn legacy_close_cb<A: Default, B: Default>(
+        close_cb: Option<fn(&B, Buffer, A)>,
+        close_cb_data: Option<B>,
+        input_data: Rc<RefCell<A>>,
+    ) -> impl FnOnce(&Weechat, BufferRef) {
+        let close_cb_data = close_cb_data.unwrap_or_default();
+
+        move |_, buffer| {
+            if let Some(close_cb) = close_cb {
+                let buffer = Buffer::wrap(buffer.weechat, buffer.ptr);
+                let input_data = input_data
+                    .try_borrow_mut()
+                    .map(|mut data| std::mem::take(&mut *data))
+                    .unwrap_or_default();
+                close_cb(&close_cb_data, buffer, input_data)
+            }
+        }
+    }
+
+    fn new_buffer(&self, builder: BufferBuilder) -> OwnedBuffer {
+        unsafe extern "C" fn c_input_cb(
             pointer: *const c_void,
             _data: *mut c_void,
             buffer: *mut t_gui_buffer,
             input_data: *const c_char,
         ) -> c_int {
             let input_data = CStr::from_ptr(input_data).to_string_lossy();
-
-            let pointers: &mut BufferPointers<A, B> =
-                { &mut *(pointer as *mut BufferPointers<A, B>) };
-
-            let buffer = Buffer::from_ptr(pointers.weechat, buffer);
-            let data = &mut pointers.input_data;
-
-            if let Some(callback) = pointers.input_cb {
-                callback(data, buffer, input_data)
+            let callbacks = &*(pointer as *const BufferCallbacks);
+
+            let weechat = Weechat::from_ptr(callbacks.weechat);
+            let buffer = Buffer::wrap(callbacks.weechat, buffer);
+
+            let mut input = match callbacks.input.try_borrow_mut() {
+                Ok(input) => input,
+                Err(_) => {
+                    weechat.record_error(
+                        "the input callback of a buffer was called while it \
+                         was running, the call was ignored",
+                    );
+                    return WEECHAT_RC_ERROR;
+                }
+            };
+
+            match &mut *input {
+                Some(BufferInputCallback::Command(callback)) => {
+                    let input = match &*callbacks.raw_input.borrow() {
+                        Some(raw_input) => {
+                            raw_input.classify(&buffer, input_data)
+                        }
+                        None => BufferInput::Text(input_data),
+                    };
+                    callback(&weechat, BufferRef::new(&buffer), input)
+                }
+                Some(BufferInputCallback::Text(callback)) => {
+                    callback(&weechat, BufferRef::new(&buffer), input_data)
+                }
+                None => (),
             }
 
             WEECHAT_RC_OK
         }
 
-        unsafe extern "C" fn c_close_cb<A, B>(
+        unsafe extern "C" fn c_close_cb(
             pointer: *const c_void,
             _data: *mut c_void,
             buffer: *mut t_gui_buffer,
         ) -> c_int {
-            // We use from_raw() here so that the box get's freed at the end
-            // of this scope.
-            let pointers = Box::from_raw(pointer as *mut BufferPointers<A, B>);
-            let buffer = Buffer::from_ptr(pointers.weechat, buffer);
-            let data = &pointers.close_cb_data;
-
-            if let Some(callback) = pointers.close_cb {
-                callback(data, buffer)
+            let callbacks = &*(pointer as *const BufferCallbacks);
+            let weechat = Weechat::from_ptr(callbacks.weechat);
+            let buffer = Buffer::wrap(callbacks.weechat, buffer);
+
+            callbacks.closed.set(true);
+
+            if let Some(callback) = callbacks.close.take() {
+                callback(&weechat, BufferRef::new(&buffer))
+            }
+
+            // If the buffer is closed from its own input callback the input
+            // callback is still running further up the stack, leak the
+            // callbacks in that case instead of freeing them under it.
+            if callbacks.input.try_borrow_mut().is_ok() {
+                drop(Box::from_raw(pointer as *mut BufferCallbacks));
             }
+
             WEECHAT_RC_OK
         }
 
+        let unknown_commands =
+            matches!(builder.input, Some(BufferInputCallback::Command(_)));
+        let has_input_cb = builder.input.is_some();
+
         // We create a box and use leak to stop rust from freeing our data,
         // we are giving weechat ownership over the data and will free it in
         // the buffer close callback.
-        let buffer_pointers = Box::new(BufferPointers::<A, B> {
+        let callbacks = Box::new(BufferCallbacks {
             weechat: self.ptr,
-            input_cb,
-            input_data: input_data.unwrap_or_default(),
-            close_cb,
-            close_cb_data: close_cb_data.unwrap_or_default(),
+            input: RefCell::new(builder.input),
+            close: Cell::new(builder.close),
+            closed: Rc::new(Cell::new(false)),
+            raw_input: RefCell::new(None),
         });
-        let buffer_pointers_ref: &BufferPointers<A, B> =
-            Box::leak(buffer_pointers);
+        let callbacks_ref: &BufferCallbacks = Box::leak(callbacks);
 
         let buf_new = self.get().buffer_new.unwrap();
-        let c_name = LossyCString::new(name);
+        let c_name = LossyCString::new(&builder.name);
 
-        let c_input_cb: Option<WeechatInputCbT> = match input_cb {
-            Some(_) => Some(c_input_cb::<A, B>),
-            None => None,
-        };
+        let c_input_cb: Option<WeechatInputCbT> =
+            if has_input_cb { Some(c_input_cb) } else { None };
 
         // TODO this can fail, return a Option type
         let buf_ptr = unsafe {
@@ -155,18 +519,62 @@ impl Weechat {
                 self.ptr,
                 c_name.as_ptr(),
                 c_input_cb,
-                buffer_pointers_ref as *const _ as *const c_void,
+                callbacks_ref as *const _ as *const c_void,
                 ptr::null_mut(),
-                Some(c_close_cb::<A, B>),
-                buffer_pointers_ref as *const _ as *const c_void,
+                Some(c_close_cb),
+                callbacks_ref as *const _ as *const c_void,
                 ptr::null_mut(),
             )
         };
 
-        Buffer {
+        let buffer = Buffer {
             weechat: self.ptr,
             ptr: buf_ptr,
+        };
+
+        if unknown_commands {
+            buffer.set_input_get_unknown_commands(true);
+            *callbacks_ref.raw_input.borrow_mut() =
+                Some(RawInput::new(&buffer));
+        }
+
+        OwnedBuffer::new(buffer, Rc::clone(&callbacks_ref.closed))
+    }
+
+    /// Get all the buffers that belong to the plugin.
+    pub(crate) fn plugin_buffers(&self) -> Vec<Buffer> {
+        let hdata_get = self.get().hdata_get.unwrap();
+        let hdata_get_list = self.get().hdata_get_list.unwrap();
+        let hdata_move = self.get().hdata_move.unwrap();
+
+        let hdata_name = LossyCString::new("buffer");
+        let list = LossyCString::new("gui_buffers");
+        let plugin_name = self.plugin_name();
+
+        let mut buffers = Vec::new();
+
+        unsafe {
+            let hdata = hdata_get(self.ptr, hdata_name.as_ptr());
+
+            if hdata.is_null() {
+                return buffers;
+            }
+
+            let mut pointer = hdata_get_list(hdata, list.as_ptr());
+
+            while !pointer.is_null() {
+                let buffer =
+                    Buffer::wrap(self.ptr, pointer as *mut t_gui_buffer);
+
+                if buffer.plugin_name() == plugin_name {
+                    buffers.push(buffer);
+                }
+
+                pointer = hdata_move(hdata, pointer, 1);
+            }
         }
+
+        buffers
     }
 }
 
@@ -271,7 +679,7 @@ impl<'a> Default for NickArgs<'a> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum HotlistPriority {
     Low,
     Message,
@@ -289,12 +697,23 @@ impl HotlistPriority {
             Highlight => "3",
         }
     }
+
+    pub(crate) fn from_int(priority: i32) -> Option<HotlistPriority> {
+        use HotlistPriority::*;
+        match priority {
+            0 => Some(Low),
+            1 => Some(Message),
+            2 => Some(Private),
+            3 => Some(Highlight),
+            _ => None,
+        }
+    }
 }
 
 impl Buffer {
     /// Create a high level Buffer object from a C plugin pointer and the
-    /// buffer pointer.
-    pub fn from_ptr(
+    /// buffer pointer, the pointers are trusted to be valid.
+    pub(crate) fn wrap(
         weechat_ptr: *mut t_weechat_plugin,
         buffer_ptr: *mut t_gui_buffer,
     ) -> Buffer {
@@ -304,6 +723,46 @@ impl Buffer {
         }
     }
 
+    /// Create a high level Buffer object from a C plugin pointer and the
+    /// buffer pointer.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use the unsafe `Buffer::from_raw` instead, the pointers aren't checked"
+    )]
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn from_ptr(
+        weechat_ptr: *mut t_weechat_plugin,
+        buffer_ptr: *mut t_gui_buffer,
+    ) -> Buffer {
+        unsafe { Buffer::from_raw(weechat_ptr, buffer_ptr) }
+    }
+
+    /// Create a Buffer object from raw pointers, e.g. a buffer pointer
+    /// returned by a function of `weechat-sys` that isn't wrapped yet.
+    /// * `weechat_ptr` - The pointer of the plugin, see `Weechat::as_ptr`.
+    /// * `buffer_ptr` - The pointer of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// Both pointers have to be valid, the methods of the buffer dereference
+    /// them. The buffer pointer has to point to an existing buffer as long as
+    /// the Buffer object is used, the buffer must not be closed in the
+    /// meantime.
+    pub unsafe fn from_raw(
+        weechat_ptr: *mut t_weechat_plugin,
+        buffer_ptr: *mut t_gui_buffer,
+    ) -> Buffer {
+        Buffer::wrap(weechat_ptr, buffer_ptr)
+    }
+
+    /// Get the raw pointer of the buffer, e.g. to call functions of
+    /// `weechat-sys` that aren't wrapped yet.
+    ///
+    /// The pointer becomes invalid once the buffer is closed.
+    pub fn as_ptr(&self) -> *mut t_gui_buffer {
+        self.ptr
+    }
+
     /// Create a sealed buffer.
     ///
     /// This prevents access to the buffer but allows it to be passed
@@ -337,6 +796,8 @@ impl Buffer {
                 c_message.as_ptr(),
             )
         }
+
+        print_metrics::record(|| self.get_full_name().into_owned(), message);
     }
 
     /// Display a message on the buffer with attached date and tags
@@ -346,7 +807,7 @@ impl Buffer {
 
         let fmt_str = LossyCString::new("%s");
         let tags = LossyCString::new(tags);
-        let message = LossyCString::new(message);
+        let c_message = LossyCString::new(message);
 
         unsafe {
             printf_datetime_tags(
@@ -354,9 +815,39 @@ impl Buffer {
                 date,
                 0,
                 tags.as_ptr(),
-                message.as_ptr(),
+                c_message.as_ptr(),
+            )
+        }
+
+        print_metrics::record(|| self.get_full_name().into_owned(), message);
+    }
+
+    /// Display a message on a specific line of a buffer with free content.
+    /// * `y` - The line number, starting with 0. A negative value displays
+    ///     the message after the last line.
+    /// * `message` - The message that will be displayed, an empty message
+    ///     removes the line.
+    pub fn print_y(&self, y: i32, message: &str) {
+        let weechat = Weechat::from_ptr(self.weechat);
+        let printf_y_datetime_tags =
+            weechat.get().printf_y_datetime_tags.unwrap();
+
+        let fmt_str = LossyCString::new("%s");
+        let c_message = LossyCString::new(message);
+
+        unsafe {
+            printf_y_datetime_tags(
+                self.ptr,
+                y,
+                0,
+                0,
+                ptr::null(),
+                fmt_str.as_ptr(),
+                c_message.as_ptr(),
             )
         }
+
+        print_metrics::record(|| self.get_full_name().into_owned(), message);
     }
 
     /// Search for a nicklist group by name
@@ -487,7 +978,7 @@ impl Buffer {
         }
     }
 
-    fn set(&self, property: &str, value: &str) {
+    pub(crate) fn set(&self, property: &str, value: &str) {
         let weechat = Weechat::from_ptr(self.weechat);
 
         let buffer_set = weechat.get().buffer_set.unwrap();
@@ -497,6 +988,15 @@ impl Buffer {
         unsafe { buffer_set(self.ptr, option.as_ptr(), value.as_ptr()) };
     }
 
+    pub(crate) fn get_integer(&self, property: &str) -> i32 {
+        let weechat = Weechat::from_ptr(self.weechat);
+
+        let buffer_get = weechat.get().buffer_get_integer.unwrap();
+        let property = LossyCString::new(property);
+
+        unsafe { buffer_get(self.ptr, property.as_ptr()) }
+    }
+
     fn get_string(&self, property: &str) -> Option<Cow<str>> {
         let weechat = Weechat::from_ptr(self.weechat);
 
@@ -523,6 +1023,13 @@ impl Buffer {
         self.set(&format!("localvar_set_{}", property), value)
     }
 
+    /// Get all the localvars of the buffer and their values.
+    pub fn localvars(&self) -> HashMap<String, String> {
+        self.get_hdata("buffer")
+            .and_then(|hdata| hdata.get_var("local_variables"))
+            .unwrap_or_default()
+    }
+
     /// Get the full name of the buffer.
     pub fn get_full_name(&self) -> Cow<str> {
         self.get_string("full_name").unwrap()
@@ -563,6 +1070,95 @@ impl Buffer {
         self.set("time_for_each_line", "0");
     }
 
+    /// Is the time displayed for each line of the buffer.
+    pub fn time_for_each_line(&self) -> bool {
+        self.get_integer("time_for_each_line") != 0
+    }
+
+    /// Show or hide the time for all lines in the buffer.
+    /// * `enable` - Should the time be displayed.
+    pub fn set_time_for_each_line(&self, enable: bool) {
+        self.set("time_for_each_line", if enable { "1" } else { "0" });
+    }
+
+    /// Are unknown commands passed to the input callback of the buffer.
+    pub fn input_get_unknown_commands(&self) -> bool {
+        self.get_integer("input_get_unknown_commands") != 0
+    }
+
+    /// Pass commands that WeeChat doesn't know about to the input callback of
+    /// the buffer instead of printing an error.
+    /// * `enable` - Should unknown commands be passed to the input callback.
+    pub fn set_input_get_unknown_commands(&self, enable: bool) {
+        self.set("input_get_unknown_commands", if enable { "1" } else { "0" });
+    }
+
+    /// Find out if input for this buffer is a command or plain text.
+    /// * `input` - The input that was entered into the input line.
+    pub fn parse_input<'a>(&self, input: &'a str) -> BufferInput<'a> {
+        let offset = match self.get_weechat().input_text_offset(input) {
+            Some(o) => o,
+            None => return BufferInput::Command(Cow::Borrowed(input)),
+        };
+
+        if input.contains('\0') {
+            let input = input.replace('\0', "");
+            BufferInput::Text(Cow::Owned(input[offset..].to_owned()))
+        } else {
+            BufferInput::Text(Cow::Borrowed(&input[offset..]))
+        }
+    }
+
+    /// Are day change messages displayed in the buffer.
+    pub fn day_change(&self) -> bool {
+        self.get_integer("day_change") != 0
+    }
+
+    /// Show or hide the day change messages in the buffer.
+    /// * `enable` - Should the day change messages be displayed.
+    pub fn set_day_change(&self, enable: bool) {
+        self.set("day_change", if enable { "1" } else { "0" });
+    }
+
+    /// Display a date separator line, formatted the same way as the day
+    /// change messages of WeeChat.
+    ///
+    /// WeeChat only displays day change messages between lines that are in
+    /// chronological order, this is useful to mark days when lines are
+    /// printed out of order, e.g. when backfilling history.
+    /// * `date` - The day that the separator announces.
+    pub fn print_date_separator(&self, date: NaiveDate) {
+        let weechat = self.get_weechat();
+
+        const DEFAULT_FORMAT: &str = "-- %a, %d %b %Y --";
+
+        let format = weechat
+            .get_string_option("weechat.look.day_change_message_1date")
+            .map(|o| o.value().into_owned())
+            .unwrap_or_else(|| DEFAULT_FORMAT.to_owned());
+
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+
+        // The format comes from the user, `to_string` would panic if it
+        // contains an invalid specifier.
+        let mut message = String::new();
+        if write!(message, "{}", midnight.format(&format)).is_err() {
+            message = midnight.format(DEFAULT_FORMAT).to_string();
+        }
+        let message = weechat
+            .eval_string_expression(&message)
+            .map(Cow::into_owned)
+            .unwrap_or(message);
+
+        let timestamp = Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|d| d.timestamp())
+            .unwrap_or_else(|| Utc.from_utc_datetime(&midnight).timestamp());
+
+        self.print_tags_dated(timestamp, "notify_none,no_log", &message);
+    }
+
     /// Disable the nicklist for this buffer.
     pub fn disable_nicklist(&self) {
         self.set("nicklist", "0")
@@ -573,10 +1169,62 @@ impl Buffer {
         self.set("nicklist", "1")
     }
 
+    /// Get the title of the buffer, color codes in the title are kept.
+    pub fn title(&self) -> Option<Cow<str>> {
+        self.get_string("title")
+    }
+
     /// Set the title of the buffer.
-    /// * `title` - The new title that will be set.
-    pub fn set_title(&self, title: &str) {
-        self.set("title", title);
+    /// * `title` - The new title that will be set, either a plain string or a
+    ///     `StyledText` containing colors.
+    pub fn set_title(&self, title: impl Into<StyledText>) {
+        let title = title.into().render(&self.get_weechat());
+        self.set("title", &title);
+    }
+
+    /// Get the input prompt of the buffer.
+    ///
+    /// Returns None if the buffer doesn't have a prompt set.
+    pub fn input_prompt(&self) -> Option<Cow<str>> {
+        self.get_string("input_prompt")
+            .filter(|prompt| !prompt.is_empty())
+    }
+
+    /// Set the input prompt of the buffer, the prompt is displayed before the
+    /// input line by the `input_prompt` bar item.
+    /// * `prompt` - The new prompt, may contain color codes. An empty string
+    ///     removes the prompt.
+    ///
+    /// Returns an error if the running WeeChat is older than 4.3.0 and
+    /// doesn't support input prompts.
+    pub fn set_input_prompt(&self, prompt: &str) -> Result<(), NotSupported> {
+        self.get_weechat()
+            .require_version(4, 3, 0, "input_prompt")?;
+        self.set("input_prompt", prompt);
+        Ok(())
+    }
+
+    /// Set the input prompt of the buffer displayed in a single color.
+    /// * `prompt` - The new prompt.
+    /// * `color` - The name of the color that the prompt should be displayed
+    ///     with.
+    ///
+    /// Returns an error if the running WeeChat doesn't support input prompts.
+    pub fn set_input_prompt_colored(
+        &self,
+        prompt: &str,
+        color: &str,
+    ) -> Result<(), NotSupported> {
+        let weechat = self.get_weechat();
+
+        let prompt = format!(
+            "{}{}{}",
+            weechat.color(color),
+            prompt,
+            weechat.color("reset")
+        );
+
+        self.set_input_prompt(&prompt)
     }
 
     /// Disable logging for this buffer.
@@ -623,8 +1271,55 @@ impl Buffer {
         self.get_string("input").unwrap()
     }
 
+    /// Execute a command on the buffer, as if the user typed it.
+    /// * `command` - The command that should be executed, it needs to start
+    ///     with a command char, otherwise the text is sent to the buffer.
+    pub fn run_command(&self, command: &str) -> ReturnCode {
+        let weechat = Weechat::from_ptr(self.weechat);
+
+        let run_command = weechat.get().command.unwrap();
+        let command = LossyCString::new(command);
+
+        let ret =
+            unsafe { run_command(self.weechat, self.ptr, command.as_ptr()) };
+
+        ReturnCode::from_int(ret)
+    }
+
     /// Switch to the buffer
     pub fn switch_to(&self) {
         self.set("display", "1");
     }
+
+    /// Turn the buffer into a buffer with free content, lines of such a
+    /// buffer are written with `print_y`.
+    pub fn set_type_free(&self) {
+        self.set("type", "free");
+    }
+
+    /// Bind a key to a command for this buffer only, the binding overrides
+    /// the global key bindings while the buffer is displayed.
+    /// * `key` - The key that should be bound, e.g. `meta-n` or `ctrl-j`.
+    /// * `command` - The command that runs when the key is pressed, e.g.
+    ///     `/myplugin next`.
+    pub fn bind_key(&self, key: &str, command: &str) {
+        self.set(&format!("key_bind_{}", key), command);
+    }
+
+    /// Remove a key binding that was added with `bind_key()`.
+    pub fn unbind_key(&self, key: &str) {
+        self.set(&format!("key_unbind_{}", key), "");
+    }
+
+    /// Close the buffer.
+    ///
+    /// Buffers that the plugin created with a `BufferBuilder` are closed with
+    /// `OwnedBuffer::close` instead, the handles that the `OwnedBuffer` and
+    /// `BufferRef` lend out can't close the buffer.
+    pub fn close(self) {
+        let weechat = Weechat::from_ptr(self.weechat);
+
+        let buffer_close = weechat.get().buffer_close.unwrap();
+        unsafe { buffer_close(self.ptr) }
+    }
 }
@@ -0,0 +1,390 @@
+//! Convert text containing WeeChat color codes for use outside of WeeChat.
+//!
+//! Plugins that forward lines to logs, relays or webhooks can convert the
+//! prefix and message of a printed line with `Weechat::strip_and_convert`:
+//!
+//! ```ignore
+//! let html = weechat.strip_and_convert(&prefix, &message, TextFormat::Html);
+//! ```
+//!
+//! The colors are converted to ANSI escape sequences by WeeChat, the HTML
+//! output is generated from those sequences.
+
+use std::ffi::CStr;
+use std::fmt::Write;
+
+use libc::c_void;
+
+use crate::{LossyCString, Weechat};
+
+/// The format a text with WeeChat color codes is converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    /// Text without any colors or attributes.
+    Plain,
+    /// Text with ANSI escape sequences, e.g. for terminals.
+    Ansi,
+    /// HTML with the colors and attributes as inline styles, special
+    /// characters are escaped.
+    Html,
+}
+
+/// The style of the text that is currently set by the ANSI sequences.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    foreground: Option<String>,
+    background: Option<String>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    reverse: bool,
+}
+
+impl Style {
+    fn css(&self) -> String {
+        let (foreground, background) = if self.reverse {
+            (&self.background, &self.foreground)
+        } else {
+            (&self.foreground, &self.background)
+        };
+
+        let mut css = String::new();
+
+        if let Some(color) = foreground {
+            let _ = write!(css, "color:{};", color);
+        }
+        if let Some(color) = background {
+            let _ = write!(css, "background-color:{};", color);
+        }
+        if self.bold {
+            css.push_str("font-weight:bold;");
+        }
+        if self.italic {
+            css.push_str("font-style:italic;");
+        }
+        if self.underline {
+            css.push_str("text-decoration:underline;");
+        }
+
+        css
+    }
+
+    /// Apply the parameters of an SGR sequence, e.g. `1;38;5;196`.
+    fn apply(&mut self, params: &str) {
+        let mut params = params
+            .split(';')
+            .map(|param| param.parse::<u8>().unwrap_or_default());
+
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.reverse = true,
+                21 | 22 => self.bold = false,
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.reverse = false,
+                30..=37 => self.foreground = Some(palette_color(param - 30)),
+                90..=97 => {
+                    self.foreground = Some(palette_color(param - 90 + 8))
+                }
+                40..=47 => self.background = Some(palette_color(param - 40)),
+                100..=107 => {
+                    self.background = Some(palette_color(param - 100 + 8))
+                }
+                38 => self.foreground = extended_color(&mut params),
+                48 => self.background = extended_color(&mut params),
+                39 => self.foreground = None,
+                49 => self.background = None,
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Parse the color of a `38;5;<n>` or `38;2;<r>;<g>;<b>` sequence, the
+/// parameters following the 38 or 48 are consumed.
+fn extended_color(params: &mut impl Iterator<Item = u8>) -> Option<String> {
+    match params.next()? {
+        5 => Some(palette_color(params.next()?)),
+        2 => Some(format!(
+            "#{:02x}{:02x}{:02x}",
+            params.next()?,
+            params.next()?,
+            params.next()?
+        )),
+        _ => None,
+    }
+}
+
+/// Get the CSS color of an entry of the xterm 256 color palette.
+fn palette_color(index: u8) -> String {
+    const BASIC: [&str; 16] = [
+        "#000000", "#800000", "#008000", "#808000", "#000080", "#800080",
+        "#008080", "#c0c0c0", "#808080", "#ff0000", "#00ff00", "#ffff00",
+        "#0000ff", "#ff00ff", "#00ffff", "#ffffff",
+    ];
+
+    match index {
+        0..=15 => BASIC[index as usize].to_owned(),
+        16..=231 => {
+            let level =
+                |value: u8| if value == 0 { 0 } else { value * 40 + 55 };
+            let index = index - 16;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(index / 36),
+                level(index / 6 % 6),
+                level(index % 6)
+            )
+        }
+        _ => {
+            let gray = (index - 232) * 10 + 8;
+            format!("#{:02x}{:02x}{:02x}", gray, gray, gray)
+        }
+    }
+}
+
+fn escape_html(text: &str, html: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+/// Convert a text with ANSI escape sequences into HTML, every run of text
+/// with a style is wrapped in a `span`.
+fn ansi_to_html(ansi: &str) -> String {
+    let mut html = String::new();
+    let mut style = Style::default();
+    let mut rest = ansi;
+
+    while !rest.is_empty() {
+        let (text, sequence) = match rest.find('\x1b') {
+            Some(start) => rest.split_at(start),
+            None => (rest, ""),
+        };
+
+        if !text.is_empty() {
+            let css = style.css();
+            if css.is_empty() {
+                escape_html(text, &mut html);
+            } else {
+                let _ = write!(html, "<span style=\"{}\">", css);
+                escape_html(text, &mut html);
+                html.push_str("</span>");
+            }
+        }
+
+        if sequence.is_empty() {
+            break;
+        }
+
+        // Skip the escape sequence, only SGR sequences change the style.
+        let sequence = &sequence[1..];
+        rest = match sequence.strip_prefix('[') {
+            Some(csi) => {
+                match csi.find(|c: char| ('\x40'..='\x7e').contains(&c)) {
+                    Some(end) => {
+                        if csi[end..].starts_with('m') {
+                            style.apply(&csi[..end]);
+                        }
+                        &csi[end + 1..]
+                    }
+                    None => "",
+                }
+            }
+            None => sequence,
+        };
+    }
+
+    html
+}
+
+impl Weechat {
+    /// Remove the WeeChat color codes from a string.
+    /// * `string` - The string containing color codes.
+    pub fn remove_color(&self, string: &str) -> String {
+        let string_remove_color = self.get().string_remove_color.unwrap();
+
+        let string = LossyCString::new(string);
+
+        unsafe {
+            let ret = string_remove_color(string.as_ptr(), std::ptr::null());
+
+            if ret.is_null() {
+                String::new()
+            } else {
+                let stripped =
+                    CStr::from_ptr(ret).to_string_lossy().into_owned();
+                libc::free(ret as *mut c_void);
+                stripped
+            }
+        }
+    }
+
+    /// Convert a printed line for use outside of WeeChat, e.g. for logs,
+    /// relays or webhooks.
+    ///
+    /// The prefix and the message are joined with a space, the prefix is
+    /// left out if it's empty. If WeeChat can't convert the colors to ANSI
+    /// sequences, the colors are removed instead.
+    /// * `prefix` - The prefix of the line, it can contain color codes.
+    /// * `message` - The message of the line, it can contain color codes.
+    /// * `format` - The format the line is converted to.
+    pub fn strip_and_convert(
+        &self,
+        prefix: &str,
+        message: &str,
+        format: TextFormat,
+    ) -> String {
+        let line = if prefix.is_empty() {
+            message.to_owned()
+        } else {
+            format!("{} {}", prefix, message)
+        };
+
+        let ansi = || {
+            self.modifier_exec("color_encode_ansi", "", &line)
+                .unwrap_or_else(|| self.remove_color(&line))
+        };
+
+        match format {
+            TextFormat::Plain => self.remove_color(&line),
+            TextFormat::Ansi => ansi(),
+            TextFormat::Html => ansi_to_html(&ansi()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_sets_and_resets_attributes() {
+        let mut style = Style::default();
+
+        style.apply("1;3;4;7");
+        assert!(style.bold && style.italic && style.underline && style.reverse);
+
+        style.apply("22;23;24;27");
+        assert_eq!(style, Style::default());
+
+        style.apply("1;31");
+        style.apply("0");
+        assert_eq!(style, Style::default());
+
+        // An empty parameter list, e.g. `\x1b[m`, is a reset.
+        style.apply("1");
+        style.apply("");
+        assert_eq!(style, Style::default());
+    }
+
+    #[test]
+    fn apply_basic_colors() {
+        let mut style = Style::default();
+
+        style.apply("31;42");
+        assert_eq!(style.foreground.as_deref(), Some("#800000"));
+        assert_eq!(style.background.as_deref(), Some("#008000"));
+
+        style.apply("91;102");
+        assert_eq!(style.foreground.as_deref(), Some("#ff0000"));
+        assert_eq!(style.background.as_deref(), Some("#00ff00"));
+
+        style.apply("39;49");
+        assert_eq!(style.foreground, None);
+        assert_eq!(style.background, None);
+    }
+
+    #[test]
+    fn apply_extended_colors() {
+        let mut style = Style::default();
+
+        style.apply("38;5;196;48;2;1;2;3;1");
+        assert_eq!(style.foreground.as_deref(), Some("#ff0000"));
+        assert_eq!(style.background.as_deref(), Some("#010203"));
+        assert!(style.bold);
+    }
+
+    #[test]
+    fn extended_color_consumes_its_parameters() {
+        let mut params = vec![5, 21, 1].into_iter();
+        assert_eq!(extended_color(&mut params).as_deref(), Some("#0000ff"));
+        assert_eq!(params.next(), Some(1));
+
+        let mut params = vec![2, 255, 128, 0].into_iter();
+        assert_eq!(extended_color(&mut params).as_deref(), Some("#ff8000"));
+        assert_eq!(params.next(), None);
+    }
+
+    #[test]
+    fn extended_color_incomplete_or_unknown() {
+        assert_eq!(extended_color(&mut vec![].into_iter()), None);
+        assert_eq!(extended_color(&mut vec![5].into_iter()), None);
+        assert_eq!(extended_color(&mut vec![2, 1, 2].into_iter()), None);
+        assert_eq!(extended_color(&mut vec![7, 1].into_iter()), None);
+    }
+
+    #[test]
+    fn palette_colors() {
+        assert_eq!(palette_color(0), "#000000");
+        assert_eq!(palette_color(15), "#ffffff");
+        assert_eq!(palette_color(16), "#000000");
+        assert_eq!(palette_color(17), "#00005f");
+        assert_eq!(palette_color(196), "#ff0000");
+        assert_eq!(palette_color(231), "#ffffff");
+        assert_eq!(palette_color(232), "#080808");
+        assert_eq!(palette_color(255), "#eeeeee");
+    }
+
+    #[test]
+    fn css_swaps_colors_when_reversed() {
+        let mut style = Style::default();
+        style.apply("31;42;1");
+        assert_eq!(
+            style.css(),
+            "color:#800000;background-color:#008000;font-weight:bold;"
+        );
+
+        style.apply("7");
+        assert_eq!(
+            style.css(),
+            "color:#008000;background-color:#800000;font-weight:bold;"
+        );
+    }
+
+    #[test]
+    fn html_special_characters_are_escaped() {
+        let mut html = String::new();
+        escape_html("<a href=\"x\">'&'</a>", &mut html);
+        assert_eq!(
+            html,
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn ansi_to_html_wraps_styled_text() {
+        assert_eq!(ansi_to_html("plain <text>"), "plain &lt;text&gt;");
+        assert_eq!(
+            ansi_to_html("\x1b[1mbold\x1b[0m & \x1b[31mred"),
+            "<span style=\"font-weight:bold;\">bold</span> &amp; \
+             <span style=\"color:#800000;\">red</span>"
+        );
+    }
+
+    #[test]
+    fn ansi_to_html_skips_other_sequences() {
+        assert_eq!(ansi_to_html("a\x1b[2Kb\x1b[1;2Hc\x1b["), "abc");
+    }
+}
@@ -0,0 +1,200 @@
+//! Dispatch the subcommands of a command to separate handlers.
+//!
+//! ```ignore
+//! let command = CommandRouter::new()
+//!     .subcommand("connect", "<server>", "connect to a server", "%(irc_servers)",
+//!         |_, buffer, mut args| connect(&buffer, args.next()))
+//!     .subcommand("list", "", "list the connections", "",
+//!         |_, buffer, _| list(&buffer))
+//!     .hook(&weechat, CommandDescription {
+//!         name: "myplugin",
+//!         description: "manage the connections of myplugin",
+//!         ..Default::default()
+//!     })?;
+//! ```
+//!
+//! The help text and the completion template of the command are generated
+//! from the subcommands, see `ArgSpec`.
+
+use crate::diagnostics;
+use crate::{
+    ArgSpec, ArgsWeechat, Buffer, CommandDescription, CommandExists,
+    CommandHook, CommandResult, ReturnCode, Weechat,
+};
+
+type Handler = Box<dyn FnMut(&Weechat, Buffer, ArgsWeechat) -> ReturnCode>;
+
+struct Route {
+    name: String,
+    args: String,
+    description: String,
+    completion: String,
+    handler: Handler,
+}
+
+/// A command made of subcommands, see the module documentation.
+#[derive(Default)]
+pub struct CommandRouter {
+    routes: Vec<Route>,
+    fallback: Option<Handler>,
+}
+
+fn boxed_handler<R: CommandResult>(
+    mut handler: impl FnMut(&Weechat, Buffer, ArgsWeechat) -> R + 'static,
+) -> Handler {
+    Box::new(move |weechat, buffer, args| {
+        let result_buffer = Buffer::wrap(buffer.weechat, buffer.ptr);
+        handler(weechat, buffer, args).into_return_code(&result_buffer)
+    })
+}
+
+impl CommandRouter {
+    /// Create a router without subcommands.
+    pub fn new() -> CommandRouter {
+        CommandRouter::default()
+    }
+
+    /// Add a subcommand.
+    /// * `name` - The name of the subcommand, e.g. `connect`.
+    /// * `args` - The arguments of the subcommand for the help text, e.g.
+    ///     `<server> [<port>]`.
+    /// * `description` - The description of the subcommand for the help
+    ///     text.
+    /// * `completion` - The completion template for the arguments of the
+    ///     subcommand, e.g. `%(irc_servers)`.
+    /// * `handler` - A closure that is called when the subcommand is run, it
+    ///     receives the arguments following the name of the subcommand. It
+    ///     can return a `Result` to report a failure, see `CommandResult`.
+    pub fn subcommand<R: CommandResult>(
+        mut self,
+        name: &str,
+        args: &str,
+        description: &str,
+        completion: &str,
+        handler: impl FnMut(&Weechat, Buffer, ArgsWeechat) -> R + 'static,
+    ) -> CommandRouter {
+        self.routes.push(Route {
+            name: name.to_owned(),
+            args: args.to_owned(),
+            description: description.to_owned(),
+            completion: completion.to_owned(),
+            handler: boxed_handler(handler),
+        });
+        self
+    }
+
+    /// Set the handler that is called if the command is run without a
+    /// subcommand. Without a fallback an error is printed.
+    /// * `handler` - A closure that is called when the command is run
+    ///     without arguments.
+    pub fn fallback<R: CommandResult>(
+        mut self,
+        handler: impl FnMut(&Weechat, Buffer, ArgsWeechat) -> R + 'static,
+    ) -> CommandRouter {
+        self.fallback = Some(boxed_handler(handler));
+        self
+    }
+
+    fn dispatch(
+        &mut self,
+        weechat: &Weechat,
+        buffer: Buffer,
+        mut args: ArgsWeechat,
+    ) -> Result<ReturnCode, String> {
+        let command = args.next().unwrap_or_default();
+
+        let subcommand = match args.next() {
+            Some(subcommand) => subcommand,
+            None => {
+                return match &mut self.fallback {
+                    Some(handler) => Ok(handler(weechat, buffer, args)),
+                    None => Err(format!(
+                        "missing subcommand for \"{}\" (help on command: \
+                         /help {})",
+                        command,
+                        command.trim_start_matches('/')
+                    )),
+                };
+            }
+        };
+
+        match self.routes.iter_mut().find(|r| r.name == subcommand) {
+            Some(route) => Ok((route.handler)(weechat, buffer, args)),
+            None => Err(format!(
+                "unknown subcommand \"{}\" for \"{}\"",
+                subcommand, command
+            )),
+        }
+    }
+
+    /// Hook the command.
+    ///
+    /// The `args` and `args_description` help texts of the command are
+    /// generated from the subcommands, the `arg_specs` of the description
+    /// are replaced. The completion template is generated as well unless the
+    /// description contains one.
+    ///
+    /// If the debug command is enabled and the command has the name of the
+    /// plugin a `debug` subcommand is added, see
+    /// `Weechat::enable_debug_command`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `command_info` - The description of the command.
+    ///
+    /// Returns an error if a command with the same name already exists.
+    pub fn hook(
+        mut self,
+        weechat: &Weechat,
+        command_info: CommandDescription,
+    ) -> Result<CommandHook, CommandExists> {
+        if let Some(plugin_version) = diagnostics::debug_command_version() {
+            let has_debug = self.routes.iter().any(|r| r.name == "debug");
+
+            if command_info.name == weechat.plugin_name() && !has_debug {
+                self = self.subcommand(
+                    "debug",
+                    "",
+                    "print support information for bug reports",
+                    "",
+                    move |weechat, buffer, _| {
+                        diagnostics::print_debug_info(
+                            weechat,
+                            &buffer,
+                            &plugin_version,
+                        );
+                        ReturnCode::Ok
+                    },
+                );
+            }
+        }
+
+        let routes: Vec<(String, String, String, String)> = self
+            .routes
+            .iter()
+            .map(|route| {
+                (
+                    route.name.clone(),
+                    route.args.clone(),
+                    route.description.clone(),
+                    route.completion.clone(),
+                )
+            })
+            .collect();
+
+        let command_info = CommandDescription {
+            arg_specs: routes
+                .iter()
+                .map(|(name, args, description, completion)| ArgSpec {
+                    name,
+                    args,
+                    description,
+                    completion,
+                })
+                .collect(),
+            ..command_info
+        };
+
+        CommandHook::new(weechat, command_info, move |weechat, buffer, args| {
+            self.dispatch(weechat, buffer, args)
+        })
+    }
+}
@@ -1,18 +1,22 @@
 use libc::{c_char, c_int};
 use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashSet;
 use std::ffi::CStr;
 use std::os::raw::c_void;
 use std::ptr;
 
 use weechat_sys::{t_gui_buffer, t_gui_completion, t_weechat_plugin};
 
-use crate::hooks::Hook;
-use crate::{Buffer, LossyCString, ReturnCode, Weechat};
+use crate::hooks::{sealed, Hook, HookCallback, HookSettings, ToggleHook};
+use crate::profiling::profile;
+use crate::{Buffer, HookKind, LossyCString, ReturnCode, Weechat};
 
 /// A handle to a completion item.
 pub struct Completion {
     weechat_ptr: *mut t_weechat_plugin,
     ptr: *mut t_gui_completion,
+    buffer_ptr: *mut t_gui_buffer,
 }
 
 /// The positions an entry can be added to a completion list.
@@ -36,22 +40,139 @@ impl CompletionPosition {
     }
 }
 
+/// Options for adding multiple words to a completion list at once.
+#[derive(Clone, Copy)]
+pub struct CompletionOptions {
+    /// Only add the first occurrence of every word.
+    pub dedupe: bool,
+    /// Ignore the case when deduplicating and sorting the words.
+    pub case_insensitive: bool,
+    /// Are the words nick names.
+    pub is_nick: bool,
+    /// The position the words should be added to the list.
+    pub position: CompletionPosition,
+}
+
+impl Default for CompletionOptions {
+    fn default() -> Self {
+        CompletionOptions {
+            dedupe: true,
+            case_insensitive: false,
+            is_nick: false,
+            position: CompletionPosition::Sorted,
+        }
+    }
+}
+
 impl Completion {
     pub(crate) fn from_raw(
         weechat: *mut t_weechat_plugin,
         completion: *mut t_gui_completion,
+        buffer: *mut t_gui_buffer,
     ) -> Completion {
         Completion {
             weechat_ptr: weechat,
             ptr: completion,
+            buffer_ptr: buffer,
         }
     }
 
+    /// Get the buffer for which the completion is done.
+    pub fn buffer(&self) -> Buffer {
+        Buffer::wrap(self.weechat_ptr, self.buffer_ptr)
+    }
+
+    fn get_string(&self, property: &str) -> Option<Cow<str>> {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+
+        let hook_completion_get_string =
+            weechat.get().hook_completion_get_string.unwrap();
+
+        let property = LossyCString::new(property);
+
+        unsafe {
+            let value = hook_completion_get_string(self.ptr, property.as_ptr());
+
+            if value.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(value).to_string_lossy())
+            }
+        }
+    }
+
+    /// Get the command that is being completed.
+    pub fn base_command(&self) -> Option<Cow<str>> {
+        self.get_string("base_command")
+    }
+
+    /// Get the word that is being completed.
+    pub fn base_word(&self) -> Option<Cow<str>> {
+        self.get_string("base_word")
+    }
+
+    /// Get the arguments of the command that is being completed, including
+    /// the base word.
+    pub fn args(&self) -> Option<Cow<str>> {
+        self.get_string("args")
+    }
+
     /// Add a word for completion, keeping the list sorted.
     pub fn add(&self, word: &str) {
         self.add_with_options(word, false, CompletionPosition::Sorted)
     }
 
+    /// Add a word for completion in a specific position.
+    pub fn add_with_position(&self, word: &str, position: CompletionPosition) {
+        self.add_with_options(word, false, position)
+    }
+
+    /// Add a nick name for completion, keeping the list sorted.
+    ///
+    /// WeeChat adds the nick completion suffix, e.g. `:`, when a nick is
+    /// completed at the beginning of the input.
+    pub fn add_nick(&self, nick: &str) {
+        self.add_with_options(nick, true, CompletionPosition::Sorted)
+    }
+
+    /// Add multiple words for completion.
+    ///
+    /// The words are deduplicated and sorted before they are added, this
+    /// allows ignoring the case which WeeChat doesn't do when sorting.
+    /// * `words` - The words that should be added.
+    /// * `options` - Options controlling how the words are added.
+    pub fn add_all<I, S>(&self, words: I, options: &CompletionOptions)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let fold = |word: &str| {
+            if options.case_insensitive {
+                word.to_lowercase()
+            } else {
+                word.to_owned()
+            }
+        };
+
+        let mut seen = HashSet::new();
+        let mut words: Vec<S> = words
+            .into_iter()
+            .filter(|word| !options.dedupe || seen.insert(fold(word.as_ref())))
+            .collect();
+
+        let position = match options.position {
+            CompletionPosition::Sorted if options.case_insensitive => {
+                words.sort_by_cached_key(|word| fold(word.as_ref()));
+                CompletionPosition::End
+            }
+            position => position,
+        };
+
+        for word in words {
+            self.add_with_options(word.as_ref(), options.is_nick, position);
+        }
+    }
+
     /// Add a word for completion in a specific position specific if the word is a nick name
     pub fn add_with_options(
         &self,
@@ -79,17 +200,65 @@ impl Completion {
 }
 
 /// Hook for a completion item, the hook is removed when the object is dropped.
-pub struct CompletionHook<T> {
-    _hook: Hook,
-    _hook_data: Box<CompletionHookData<T>>,
+///
+/// The completion item doesn't complete to anything while the hook is
+/// disabled.
+pub struct CompletionHook {
+    hook: Hook,
+    hook_data: Box<CompletionHookData>,
 }
 
-struct CompletionHookData<T> {
-    callback: fn(&T, Buffer, Cow<str>, Completion) -> ReturnCode,
-    callback_data: T,
+struct CompletionHookData {
+    callback: HookCallback<
+        dyn FnMut(&Weechat, Buffer, Cow<str>, Completion) -> ReturnCode,
+    >,
+    enabled: Cell<bool>,
     weechat_ptr: *mut t_weechat_plugin,
 }
 
+impl sealed::RawHook for CompletionHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for CompletionHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for CompletionHook {}
+
+impl ToggleHook for CompletionHook {}
+
+impl CompletionHook {
+    /// Hook a completion item.
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `completion_item` - The name of the completion item
+    /// * `description` - The description of the completion item
+    /// * `callback` - A closure that will be called when the completion is
+    ///     used, the callback must populate the words for the completion
+    pub fn new(
+        weechat: &Weechat,
+        completion_item: &str,
+        description: &str,
+        callback: impl FnMut(&Weechat, Buffer, Cow<str>, Completion) -> ReturnCode
+            + 'static,
+    ) -> CompletionHook {
+        weechat.new_completion_hook(
+            completion_item,
+            description,
+            Box::new(callback),
+        )
+    }
+}
+
 impl Weechat {
     /// Hook a completion.
     ///
@@ -99,6 +268,10 @@ impl Weechat {
     ///     populate the words for the completion
     /// * `callback_data` - Data that will be passed to the callback every time
     ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `CompletionHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
     pub fn hook_completion<T>(
         &self,
         completion_item: &str,
@@ -110,37 +283,73 @@ impl Weechat {
             completion: Completion,
         ) -> ReturnCode,
         callback_data: Option<T>,
-    ) -> CompletionHook<T>
+    ) -> CompletionHook
     where
-        T: Default,
+        T: Default + 'static,
     {
-        unsafe extern "C" fn c_hook_cb<T>(
+        let callback_data = callback_data.unwrap_or_default();
+
+        self.new_completion_hook(
+            completion_item,
+            description,
+            Box::new(move |_, buffer, item, completion| {
+                callback(&callback_data, buffer, item, completion)
+            }),
+        )
+    }
+
+    fn new_completion_hook(
+        &self,
+        completion_item: &str,
+        description: &str,
+        callback: Box<
+            dyn FnMut(&Weechat, Buffer, Cow<str>, Completion) -> ReturnCode,
+        >,
+    ) -> CompletionHook {
+        unsafe extern "C" fn c_hook_cb(
             pointer: *const c_void,
             _data: *mut c_void,
             completion_item: *const c_char,
             buffer: *mut t_gui_buffer,
             completion: *mut t_gui_completion,
         ) -> c_int {
-            let hook_data: &mut CompletionHookData<T> =
-                { &mut *(pointer as *mut CompletionHookData<T>) };
-            let callback = hook_data.callback;
-            let callback_data = &hook_data.callback_data;
-            let buffer = Buffer::from_ptr(hook_data.weechat_ptr, buffer);
+            let hook_data: &CompletionHookData =
+                { &*(pointer as *const CompletionHookData) };
+
+            if !hook_data.enabled.get() {
+                return ReturnCode::Ok as i32;
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+            let completion =
+                Completion::from_raw(hook_data.weechat_ptr, completion, buffer);
+            let buffer = Buffer::wrap(hook_data.weechat_ptr, buffer);
 
             let completion_item =
                 CStr::from_ptr(completion_item).to_string_lossy();
 
-            callback(
-                callback_data,
-                buffer,
-                completion_item,
-                Completion::from_raw(hook_data.weechat_ptr, completion),
-            ) as i32
+            hook_data
+                .callback
+                .call(&weechat, HookKind::Completion, |callback| {
+                    profile(
+                        HookKind::Completion,
+                        || completion_item.clone().into_owned(),
+                        || {
+                            callback(
+                                &weechat,
+                                buffer,
+                                completion_item.clone(),
+                                completion,
+                            )
+                        },
+                    )
+                })
+                .unwrap_or(ReturnCode::Error) as i32
         }
 
         let data = Box::new(CompletionHookData {
-            callback,
-            callback_data: callback_data.unwrap_or_default(),
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
             weechat_ptr: self.ptr,
         });
 
@@ -155,7 +364,7 @@ impl Weechat {
                 self.ptr,
                 completion_item.as_ptr(),
                 description.as_ptr(),
-                Some(c_hook_cb::<T>),
+                Some(c_hook_cb),
                 data_ref as *const _ as *const c_void,
                 ptr::null_mut(),
             )
@@ -166,9 +375,252 @@ impl Weechat {
             weechat_ptr: self.ptr,
         };
 
-        CompletionHook::<T> {
-            _hook: hook,
-            _hook_data: hook_data,
+        CompletionHook { hook, hook_data }
+    }
+}
+
+/// Completion items listing the local variables of the buffer the completion
+/// is done for. The items are removed when the object is dropped.
+pub struct LocalvarCompletion {
+    _names_hook: CompletionHook,
+    _values_hook: CompletionHook,
+    names_item: String,
+    values_item: String,
+}
+
+impl LocalvarCompletion {
+    /// The completion template for the names of the local variables, e.g.
+    /// `%(myplugin_localvar_names)`.
+    pub fn names_template(&self) -> String {
+        format!("%({})", self.names_item)
+    }
+
+    /// The completion template for the values of the local variables, e.g.
+    /// `%(myplugin_localvar_values)`.
+    pub fn values_template(&self) -> String {
+        format!("%({})", self.values_item)
+    }
+}
+
+impl Weechat {
+    /// Hook completion items for the local variables of a buffer.
+    ///
+    /// Two items are hooked, one completing the names and one completing the
+    /// values of the local variables. The items are prefixed with the plugin
+    /// name, use the templates of the returned object to reference them in a
+    /// command completion.
+    pub fn hook_localvar_completion(&self) -> LocalvarCompletion {
+        fn names_cb(
+            _weechat: &Weechat,
+            buffer: Buffer,
+            _item: Cow<str>,
+            completion: Completion,
+        ) -> ReturnCode {
+            for name in buffer.localvars().keys() {
+                completion.add(name);
+            }
+            ReturnCode::Ok
+        }
+
+        fn values_cb(
+            _weechat: &Weechat,
+            buffer: Buffer,
+            _item: Cow<str>,
+            completion: Completion,
+        ) -> ReturnCode {
+            for value in buffer.localvars().values() {
+                completion.add(value);
+            }
+            ReturnCode::Ok
+        }
+
+        let names_item = format!("{}_localvar_names", self.plugin_name());
+        let values_item = format!("{}_localvar_values", self.plugin_name());
+
+        LocalvarCompletion {
+            _names_hook: CompletionHook::new(
+                self,
+                &names_item,
+                "names of local variables of the current buffer",
+                names_cb,
+            ),
+            _values_hook: CompletionHook::new(
+                self,
+                &values_item,
+                "values of local variables of the current buffer",
+                values_cb,
+            ),
+            names_item,
+            values_item,
+        }
+    }
+}
+
+/// A completion item listing the full names of the buffers that were created
+/// by the plugin. The item is removed when the object is dropped.
+pub struct PluginBufferCompletion {
+    _hook: CompletionHook,
+    item: String,
+}
+
+impl PluginBufferCompletion {
+    /// The completion template for the buffers, e.g. `%(myplugin_buffers)`.
+    pub fn template(&self) -> String {
+        format!("%({})", self.item)
+    }
+}
+
+impl Weechat {
+    /// Hook a completion item for the buffers created by the plugin.
+    ///
+    /// The item completes the full names of the buffers, e.g.
+    /// `myplugin.dashboard`. It is prefixed with the plugin name, use the
+    /// template of the returned object to reference it in a command
+    /// completion.
+    pub fn hook_plugin_buffer_completion(&self) -> PluginBufferCompletion {
+        fn buffers_cb(
+            weechat: &Weechat,
+            _buffer: Buffer,
+            _item: Cow<str>,
+            completion: Completion,
+        ) -> ReturnCode {
+            for buffer in weechat.plugin_buffers() {
+                completion.add(&buffer.get_full_name());
+            }
+
+            ReturnCode::Ok
+        }
+
+        let item = format!("{}_buffers", self.plugin_name());
+
+        PluginBufferCompletion {
+            _hook: CompletionHook::new(
+                self,
+                &item,
+                "buffers created by the plugin",
+                buffers_cb,
+            ),
+            item,
+        }
+    }
+}
+
+/// The names of the basic colors WeeChat knows about.
+const COLOR_NAMES: &[&str] = &[
+    "default",
+    "black",
+    "darkgray",
+    "red",
+    "lightred",
+    "green",
+    "lightgreen",
+    "brown",
+    "yellow",
+    "blue",
+    "lightblue",
+    "magenta",
+    "lightmagenta",
+    "cyan",
+    "lightcyan",
+    "gray",
+    "white",
+];
+
+/// A completion item listing the valid values of an option. The option is
+/// the argument in front of the word that is being completed, e.g. for the
+/// command `/myplugin set <option> <value>`. The item is removed when the
+/// object is dropped.
+pub struct OptionValueCompletion {
+    _hook: CompletionHook,
+    item: String,
+}
+
+impl OptionValueCompletion {
+    /// The completion template for the values, e.g.
+    /// `%(myplugin_option_values)`.
+    pub fn template(&self) -> String {
+        format!("%({})", self.item)
+    }
+}
+
+impl Weechat {
+    /// Hook a completion item for the values of an option.
+    ///
+    /// Boolean options complete to `on`, `off` and `toggle`, options with a
+    /// fixed list of values complete to those values and color options
+    /// complete to the names of the basic colors. Other options don't
+    /// complete to anything.
+    ///
+    /// ```ignore
+    /// let values = weechat.hook_option_value_completion();
+    ///
+    /// let command = CommandDescription {
+    ///     name: "myplugin",
+    ///     args: "set <option> <value>",
+    ///     completion: &format!("set %(config_options) {}", values.template()),
+    ///     ..Default::default()
+    /// };
+    /// ```
+    pub fn hook_option_value_completion(&self) -> OptionValueCompletion {
+        fn values_cb(
+            weechat: &Weechat,
+            _buffer: Buffer,
+            _item: Cow<str>,
+            completion: Completion,
+        ) -> ReturnCode {
+            let args = completion.args().unwrap_or_default();
+
+            // The last argument is the word that is being completed, the
+            // option is the one in front of it.
+            let option = match args.rsplit(' ').nth(1) {
+                Some(option) if !option.is_empty() => option,
+                _ => return ReturnCode::Ok,
+            };
+
+            for value in weechat.option_values(option) {
+                completion.add(&value);
+            }
+
+            ReturnCode::Ok
+        }
+
+        let item = format!("{}_option_values", self.plugin_name());
+
+        OptionValueCompletion {
+            _hook: CompletionHook::new(
+                self,
+                &item,
+                "values of the option in front of the completed word",
+                values_cb,
+            ),
+            item,
+        }
+    }
+
+    /// Get the valid values of an option.
+    /// * `option` - The full name of the option, e.g. `myplugin.look.mode`.
+    fn option_values(&self, option: &str) -> Vec<String> {
+        let infolist = match self.infolist_get("option", option) {
+            Some(infolist) if infolist.next() => infolist,
+            _ => return Vec::new(),
+        };
+
+        let option_type = infolist.get_string("type").unwrap_or_default();
+        let string_values =
+            infolist.get_string("string_values").unwrap_or_default();
+
+        if !string_values.is_empty() {
+            return string_values.split('|').map(str::to_owned).collect();
+        }
+
+        match option_type.as_ref() {
+            "boolean" => {
+                vec!["on".to_owned(), "off".to_owned(), "toggle".to_owned()]
+            }
+            "color" => {
+                COLOR_NAMES.iter().map(|&name| name.to_owned()).collect()
+            }
+            _ => Vec::new(),
         }
     }
 }
@@ -0,0 +1,296 @@
+//! Build completion templates for commands.
+//!
+//! The completion template of a command lists the completion of every
+//! argument, e.g. `add %(nicks)|%* || del %(irc_channels)`. A
+//! `CompletionTemplate` builds such a template out of `CompletionArg`s:
+//!
+//! ```ignore
+//! let completion = CompletionTemplate::new()
+//!     .arg("add")
+//!     .arg(CompletionArg::NICKS.repeated())
+//!     .or()
+//!     .arg("del")
+//!     .arg(CompletionArg::IRC_CHANNELS | CompletionArg::BUFFERS_NAMES)
+//!     .render();
+//!
+//! let command = CommandHook::new(
+//!     &weechat,
+//!     CommandDescription {
+//!         name: "myplugin",
+//!         completion: &completion,
+//!         ..Default::default()
+//!     },
+//!     callback,
+//! )?;
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::BitOr;
+
+/// The completion of a single argument of a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionArg(Cow<'static, str>);
+
+/// A completion template for a command, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionTemplate {
+    alternatives: Vec<Vec<CompletionArg>>,
+}
+
+impl CompletionArg {
+    /// The nicks of the current buffer.
+    pub const NICKS: CompletionArg = CompletionArg::builtin("%(nicks)");
+    /// The names of the buffers.
+    pub const BUFFERS_NAMES: CompletionArg =
+        CompletionArg::builtin("%(buffers_names)");
+    /// The full names of the buffers, including the plugin name.
+    pub const BUFFERS_PLUGINS_NAMES: CompletionArg =
+        CompletionArg::builtin("%(buffers_plugins_names)");
+    /// The names of the loaded plugins.
+    pub const PLUGINS_NAMES: CompletionArg =
+        CompletionArg::builtin("%(plugins_names)");
+    /// The commands of WeeChat and all plugins.
+    pub const COMMANDS: CompletionArg = CompletionArg::builtin("%(commands)");
+    /// The names of the config options.
+    pub const CONFIG_OPTIONS: CompletionArg =
+        CompletionArg::builtin("%(config_options)");
+    /// The names of the proxies.
+    pub const PROXIES_NAMES: CompletionArg =
+        CompletionArg::builtin("%(proxies_names)");
+    /// The names of the bars.
+    pub const BARS_NAMES: CompletionArg =
+        CompletionArg::builtin("%(bars_names)");
+    /// The names of the filters.
+    pub const FILTERS_NAMES: CompletionArg =
+        CompletionArg::builtin("%(filters_names)");
+    /// File names.
+    pub const FILENAME: CompletionArg = CompletionArg::builtin("%(filename)");
+    /// The names of the IRC servers.
+    pub const IRC_SERVERS: CompletionArg =
+        CompletionArg::builtin("%(irc_servers)");
+    /// The IRC channels of all servers.
+    pub const IRC_CHANNELS: CompletionArg =
+        CompletionArg::builtin("%(irc_channels)");
+    /// The IRC channels of the current server.
+    pub const IRC_SERVER_CHANNELS: CompletionArg =
+        CompletionArg::builtin("%(irc_server_channels)");
+    /// The nicks of the current IRC channel.
+    pub const IRC_CHANNEL_NICKS: CompletionArg =
+        CompletionArg::builtin("%(irc_channel_nicks)");
+    /// Stop the completion, no arguments follow.
+    pub const STOP: CompletionArg = CompletionArg::builtin("%-");
+
+    const fn builtin(template: &'static str) -> CompletionArg {
+        CompletionArg(Cow::Borrowed(template))
+    }
+
+    /// Complete a literal word, e.g. the name of a subcommand.
+    /// * `word` - The word, it must not contain spaces or `|`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the word is empty or contains whitespace or `|`, WeeChat
+    /// would split it into several arguments or alternatives.
+    pub fn word(word: &str) -> CompletionArg {
+        assert!(
+            !word.is_empty()
+                && !word.contains(|c: char| c.is_whitespace() || c == '|'),
+            "invalid completion word \"{}\"",
+            word
+        );
+
+        CompletionArg(Cow::Owned(word.to_owned()))
+    }
+
+    /// Complete with a completion item, e.g. one hooked with
+    /// `CompletionHook::new`.
+    /// * `name` - The name of the completion item, without `%(` and `)`.
+    pub fn item(name: &str) -> CompletionArg {
+        CompletionArg(Cow::Owned(format!("%({})", name)))
+    }
+
+    /// Use an already rendered template for the argument, e.g. the template
+    /// of a `LocalvarCompletion`.
+    /// * `template` - The template of the argument.
+    pub fn raw(template: &str) -> CompletionArg {
+        CompletionArg(Cow::Owned(template.to_owned()))
+    }
+
+    /// Complete with one of several alternatives.
+    /// * `alternatives` - The completions that are offered together.
+    pub fn any_of<I>(alternatives: I) -> CompletionArg
+    where
+        I: IntoIterator<Item = CompletionArg>,
+    {
+        let alternatives: Vec<String> = alternatives
+            .into_iter()
+            .map(|arg| arg.0.into_owned())
+            .collect();
+
+        CompletionArg(Cow::Owned(alternatives.join("|")))
+    }
+
+    /// Use the completion for this and all following arguments, e.g. for a
+    /// list of nicks.
+    pub fn repeated(self) -> CompletionArg {
+        self | CompletionArg::builtin("%*")
+    }
+
+    /// Get the template of the argument.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for CompletionArg {
+    fn from(word: &str) -> CompletionArg {
+        CompletionArg::word(word)
+    }
+}
+
+impl BitOr for CompletionArg {
+    type Output = CompletionArg;
+
+    fn bitor(self, other: CompletionArg) -> CompletionArg {
+        CompletionArg::any_of(vec![self, other])
+    }
+}
+
+impl fmt::Display for CompletionArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Default for CompletionTemplate {
+    fn default() -> CompletionTemplate {
+        CompletionTemplate {
+            alternatives: vec![Vec::new()],
+        }
+    }
+}
+
+impl CompletionTemplate {
+    /// Create an empty template.
+    pub fn new() -> CompletionTemplate {
+        CompletionTemplate::default()
+    }
+
+    /// Add the completion of the next argument.
+    /// * `arg` - The completion of the argument, a string is completed as a
+    ///     literal word.
+    pub fn arg(mut self, arg: impl Into<CompletionArg>) -> CompletionTemplate {
+        if let Some(args) = self.alternatives.last_mut() {
+            args.push(arg.into());
+        }
+        self
+    }
+
+    /// Stop the completion after the arguments that were added.
+    pub fn stop(self) -> CompletionTemplate {
+        self.arg(CompletionArg::STOP)
+    }
+
+    /// Start an alternative template, e.g. for the next subcommand.
+    pub fn or(mut self) -> CompletionTemplate {
+        self.alternatives.push(Vec::new());
+        self
+    }
+
+    /// Render the template for the `completion` field of a
+    /// `CommandDescription`.
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for CompletionTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alternatives: Vec<String> = self
+            .alternatives
+            .iter()
+            .filter(|args| !args.is_empty())
+            .map(|args| {
+                args.iter()
+                    .map(CompletionArg::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+
+        f.write_str(&alternatives.join(" || "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_arguments_and_alternatives() {
+        let template = CompletionTemplate::new()
+            .arg("add")
+            .arg(CompletionArg::NICKS.repeated())
+            .or()
+            .arg("del")
+            .arg(CompletionArg::IRC_CHANNELS | CompletionArg::BUFFERS_NAMES)
+            .stop();
+
+        assert_eq!(
+            template.render(),
+            "add %(nicks)|%* || del %(irc_channels)|%(buffers_names) %-"
+        );
+    }
+
+    #[test]
+    fn empty_alternatives_are_skipped() {
+        assert_eq!(CompletionTemplate::new().render(), "");
+        assert_eq!(
+            CompletionTemplate::new().or().arg("list").or().render(),
+            "list"
+        );
+    }
+
+    #[test]
+    fn items_and_raw_templates() {
+        let template = CompletionTemplate::new()
+            .arg(CompletionArg::item("myplugin_servers"))
+            .arg(CompletionArg::raw("%(buffer_localvar:topic)"));
+
+        assert_eq!(
+            template.render(),
+            "%(myplugin_servers) %(buffer_localvar:topic)"
+        );
+    }
+
+    #[test]
+    fn any_of_joins_alternatives() {
+        let arg = CompletionArg::any_of(vec![
+            "on".into(),
+            "off".into(),
+            CompletionArg::item("toggle"),
+        ]);
+
+        assert_eq!(arg.as_str(), "on|off|%(toggle)");
+        assert_eq!(arg.to_string(), "on|off|%(toggle)");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid completion word")]
+    fn word_with_space_panics() {
+        CompletionArg::word("two words");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid completion word")]
+    fn word_with_pipe_panics() {
+        CompletionTemplate::new().arg("on|off");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid completion word")]
+    fn empty_word_panics() {
+        CompletionArg::word("");
+    }
+}
@@ -1,33 +1,66 @@
 //! Weechat Configuration module
 
 use libc::{c_char, c_int};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::ffi::CStr;
 use std::os::raw::c_void;
+#[cfg(feature = "async")]
+use std::pin::Pin;
 use std::ptr;
+use std::rc::Rc;
+use std::rc::Weak;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use futures::Stream;
 
 use crate::config_options::{
-    BooleanOption, ColorOption, ConfigOption, IntegerOption, OptionDescription,
-    OptionPointers, OptionType, StringOption,
+    display_value, BooleanOption, ColorOption, ConfigOption, IntegerOption,
+    OptionDescription, OptionOwner, OptionPointers, OptionType, StringOption,
 };
-use crate::{LossyCString, Weechat};
+use crate::hdata::HData;
+use crate::hooks::{catch_panic, Hook, HookCallback};
+use crate::{HookKind, LossyCString, TimerHook, Weechat};
 use std::borrow::Cow;
 use weechat_sys::{
     t_config_file, t_config_option, t_config_section, t_weechat_plugin,
-    WEECHAT_RC_OK,
+    WEECHAT_RC_ERROR, WEECHAT_RC_OK,
 };
 
 /// Weechat configuration file
 pub struct Config<T> {
     ptr: *mut t_config_file,
     weechat_ptr: *mut t_weechat_plugin,
+    name: String,
     _config_data: Box<ConfigPointers<T>>,
     sections: HashMap<String, ConfigSection>,
+    autosave: Option<ConfigAutosave>,
+    /// Options created from the config file hold a weak reference to this,
+    /// so they notice when the file is freed.
+    alive: Rc<()>,
+}
+
+/// Writes a configuration file periodically if any of its options changed.
+struct ConfigAutosave {
+    _hook: Hook,
+    _state: Rc<AutosaveState>,
+    _timer: TimerHook,
+}
+
+struct AutosaveState {
+    dirty: Cell<bool>,
+    config_ptr: *mut t_config_file,
 }
 
 struct ConfigPointers<T> {
     reload_cb: Option<fn(&mut T)>,
     reload_data: T,
+    weechat_ptr: *mut t_weechat_plugin,
+    #[cfg(feature = "async")]
+    reload_subscribers: RefCell<Vec<Weak<RefCell<ReloadSubscriber>>>>,
 }
 
 /// Weechat Configuration section
@@ -35,6 +68,7 @@ pub struct ConfigSection {
     ptr: *mut t_config_section,
     config_ptr: *mut t_config_file,
     weechat_ptr: *mut t_weechat_plugin,
+    config_alive: Weak<()>,
 }
 
 /// Represents the options when creating a new config section.
@@ -79,9 +113,14 @@ impl<T> Drop for Config<T> {
         let weechat = Weechat::from_ptr(self.weechat_ptr);
         let config_free = weechat.get().config_free.unwrap();
 
-        // Drop the sections first.
+        // Drop the autosave hooks and the sections first.
+        self.autosave = None;
         self.sections.clear();
 
+        // End the reload streams.
+        #[cfg(feature = "async")]
+        self._config_data.notify_reload_subscribers(true);
+
         unsafe {
             // Now drop the config.
             config_free(self.ptr)
@@ -104,6 +143,15 @@ impl Drop for ConfigSection {
 }
 
 impl<T> Config<T> {
+    /// Get the raw pointer of the configuration file, e.g. to call functions
+    /// of `weechat-sys` that aren't wrapped yet.
+    ///
+    /// The pointer is only valid as long as the Config object exists, the
+    /// configuration file must not be freed manually.
+    pub fn as_ptr(&self) -> *mut t_config_file {
+        self.ptr
+    }
+
     /// Create a new section in the configuration file.
     pub fn new_section<S: Default>(
         &mut self,
@@ -142,6 +190,7 @@ impl<T> Config<T> {
             ptr,
             config_ptr: self.ptr,
             weechat_ptr: weechat.ptr,
+            config_alive: Rc::downgrade(&self.alive),
         };
         self.sections.insert(section_info.name.to_string(), section);
         &self.sections[section_info.name]
@@ -168,6 +217,157 @@ impl<T> Config<T> {
             config_write(self.ptr);
         }
     }
+
+    /// Get a stream that yields an item every time the config file is
+    /// reloaded, e.g. when the user runs `/reload`.
+    ///
+    /// This allows long lived tasks to re-read their settings. The stream
+    /// ends when the config is dropped.
+    #[cfg(feature = "async")]
+    pub fn reloads(&self) -> ConfigReloads {
+        let subscriber = Rc::new(RefCell::new(ReloadSubscriber::default()));
+
+        self._config_data
+            .reload_subscribers
+            .borrow_mut()
+            .push(Rc::downgrade(&subscriber));
+
+        ConfigReloads { subscriber }
+    }
+
+    /// Periodically save the config file to the disk if any of its options
+    /// changed.
+    ///
+    /// This is useful for plugins that change their options
+    /// programmatically, the changes won't be lost if WeeChat crashes.
+    /// Calling this again replaces the previous interval.
+    /// * `interval` - How often the config file should be checked for
+    ///     changes.
+    pub fn autosave(&mut self, interval: Duration) {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            _option: *const c_char,
+            _value: *const c_char,
+        ) -> c_int {
+            let state: &AutosaveState = { &*(pointer as *const AutosaveState) };
+            state.dirty.set(true);
+
+            WEECHAT_RC_OK
+        }
+
+        fn timer_cb(state: &AutosaveState, weechat: &Weechat, _remaining: i32) {
+            if state.dirty.replace(false) {
+                let config_write = weechat.get().config_write.unwrap();
+                unsafe { config_write(state.config_ptr) };
+            }
+        }
+
+        self.autosave = None;
+
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+
+        let state = Rc::new(AutosaveState {
+            dirty: Cell::new(false),
+            config_ptr: self.ptr,
+        });
+
+        let hook_config = weechat.get().hook_config.unwrap();
+
+        let option = LossyCString::new(format!("{}.*", self.name));
+
+        let hook_ptr = unsafe {
+            hook_config(
+                self.weechat_ptr,
+                option.as_ptr(),
+                Some(c_hook_cb),
+                Rc::as_ptr(&state) as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.weechat_ptr,
+        };
+
+        let timer_state = state.clone();
+        let timer = TimerHook::new(
+            &weechat,
+            interval,
+            0,
+            0,
+            move |weechat, remaining| {
+                timer_cb(&timer_state, weechat, remaining)
+            },
+        );
+
+        self.autosave = Some(ConfigAutosave {
+            _hook: hook,
+            _state: state,
+            _timer: timer,
+        });
+    }
+
+    /// Stop saving the config file periodically.
+    pub fn stop_autosave(&mut self) {
+        self.autosave = None;
+    }
+
+    /// List the options of the config file whose names match a mask, together
+    /// with their values formatted the same way the `/set` command displays
+    /// them.
+    /// * `mask` - The mask the option names are matched against, relative
+    ///     to the config file, e.g. `look.*`. The wildcard `*` is allowed.
+    pub fn fset_filter(&self, mask: &str) -> Vec<(String, String)> {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+
+        let infolist = match weechat
+            .infolist_get("option", &format!("{}.{}", self.name, mask))
+        {
+            Some(i) => i,
+            None => return Vec::new(),
+        };
+
+        let mut options = Vec::new();
+
+        while infolist.next() {
+            let full_name = match infolist.get_string("full_name") {
+                Some(n) => n.into_owned(),
+                None => continue,
+            };
+            let option_type = infolist.get_string("type").unwrap_or_default();
+            let value = infolist.get_string("value");
+
+            options.push((
+                full_name,
+                display_value(&option_type, value.as_deref()),
+            ));
+        }
+
+        options
+    }
+
+    /// Keep the config file registered until WeeChat shuts down.
+    ///
+    /// The config file is never freed, this is useful if options need to
+    /// stay alive for the whole lifetime of WeeChat, e.g. because other
+    /// plugins or the user hold on to them. The returned reference can still
+    /// be used to manage the config file.
+    pub fn persist(self) -> &'static mut Config<T>
+    where
+        T: 'static,
+    {
+        Box::leak(Box::new(self))
+    }
+
+    /// Free the config file and all of its sections and options now.
+    ///
+    /// This is the same as dropping the config file. Option objects that were
+    /// created from the config file are invalidated, `is_valid` returns false
+    /// for them and using them panics.
+    pub fn free(self) {
+        drop(self)
+    }
 }
 
 type WeechatOptChangeCbT = unsafe extern "C" fn(
@@ -184,6 +384,15 @@ type WeechatOptCheckCbT = unsafe extern "C" fn(
 ) -> c_int;
 
 impl ConfigSection {
+    /// Get the raw pointer of the section, e.g. to call functions of
+    /// `weechat-sys` that aren't wrapped yet.
+    ///
+    /// The pointer is only valid as long as the configuration file exists,
+    /// the section must not be freed manually.
+    pub fn as_ptr(&self) -> *mut t_config_section {
+        self.ptr
+    }
+
     /// Create a new string Weechat configuration option.
     pub fn new_string_option<D>(
         &self,
@@ -218,6 +427,7 @@ impl ConfigSection {
         StringOption {
             ptr,
             weechat_ptr: self.weechat_ptr,
+            owner: OptionOwner::new(self.config_alive.clone()),
         }
     }
 
@@ -257,6 +467,7 @@ impl ConfigSection {
         BooleanOption {
             ptr,
             weechat_ptr: self.weechat_ptr,
+            owner: OptionOwner::new(self.config_alive.clone()),
         }
     }
 
@@ -299,6 +510,7 @@ impl ConfigSection {
         IntegerOption {
             ptr,
             weechat_ptr: self.weechat_ptr,
+            owner: OptionOwner::new(self.config_alive.clone()),
         }
     }
 
@@ -336,6 +548,7 @@ impl ConfigSection {
         ColorOption {
             ptr,
             weechat_ptr: self.weechat_ptr,
+            owner: OptionOwner::new(self.config_alive.clone()),
         }
     }
 
@@ -368,12 +581,16 @@ impl ConfigSection {
             let pointers: &mut OptionPointers<T, A, B, C> =
                 { &mut *(pointer as *mut OptionPointers<T, A, B, C>) };
 
-            let option = T::from_ptrs(option_pointer, pointers.weechat_ptr);
+            let option =
+                unsafe { T::from_raw(option_pointer, pointers.weechat_ptr) };
 
             let data = &mut pointers.check_cb_data;
 
             if let Some(callback) = pointers.check_cb {
-                callback(data, &option, value)
+                let weechat = Weechat::from_ptr(pointers.weechat_ptr);
+                catch_panic(&weechat, HookKind::Config, || {
+                    callback(data, &option, value)
+                });
             };
 
             WEECHAT_RC_OK
@@ -389,12 +606,16 @@ impl ConfigSection {
             let pointers: &mut OptionPointers<T, A, B, C> =
                 { &mut *(pointer as *mut OptionPointers<T, A, B, C>) };
 
-            let option = T::from_ptrs(option_pointer, pointers.weechat_ptr);
+            let option =
+                unsafe { T::from_raw(option_pointer, pointers.weechat_ptr) };
 
             let data = &mut pointers.change_cb_data;
 
             if let Some(callback) = pointers.change_cb {
-                callback(data, &option)
+                let weechat = Weechat::from_ptr(pointers.weechat_ptr);
+                catch_panic(&weechat, HookKind::Config, || {
+                    callback(data, &option)
+                });
             };
         }
 
@@ -408,12 +629,16 @@ impl ConfigSection {
             let pointers: &mut OptionPointers<T, A, B, C> =
                 { &mut *(pointer as *mut OptionPointers<T, A, B, C>) };
 
-            let option = T::from_ptrs(option_pointer, pointers.weechat_ptr);
+            let option =
+                unsafe { T::from_raw(option_pointer, pointers.weechat_ptr) };
 
             let data = &mut pointers.delete_cb_data;
 
             if let Some(callback) = pointers.delete_cb {
-                callback(data, &option)
+                let weechat = Weechat::from_ptr(pointers.weechat_ptr);
+                catch_panic(&weechat, HookKind::Config, || {
+                    callback(data, &option)
+                });
             };
         }
 
@@ -509,18 +734,33 @@ impl Weechat {
         unsafe extern "C" fn c_reload_cb<T>(
             pointer: *const c_void,
             _data: *mut c_void,
-            _config_pointer: *mut t_config_file,
+            config_pointer: *mut t_config_file,
         ) -> c_int {
             let pointers: &mut ConfigPointers<T> =
                 { &mut *(pointer as *mut ConfigPointers<T>) };
 
             let data = &mut pointers.reload_data;
 
-            if let Some(callback) = pointers.reload_cb {
-                callback(data)
-            }
+            let ret = if let Some(callback) = pointers.reload_cb {
+                let weechat = Weechat::from_ptr(pointers.weechat_ptr);
+                match catch_panic(&weechat, HookKind::Config, || callback(data))
+                {
+                    Some(()) => WEECHAT_RC_OK,
+                    None => WEECHAT_RC_ERROR,
+                }
+            } else {
+                // Without a reload callback WeeChat would reload the file
+                // itself, since our callback is always registered this needs
+                // to be done here.
+                let weechat = Weechat::from_ptr(pointers.weechat_ptr);
+                let config_reload = weechat.get().config_reload.unwrap();
+                config_reload(config_pointer)
+            };
 
-            WEECHAT_RC_OK
+            #[cfg(feature = "async")]
+            pointers.notify_reload_subscribers(false);
+
+            ret
         }
 
         let c_name = LossyCString::new(name);
@@ -528,13 +768,13 @@ impl Weechat {
         let config_pointers = Box::new(ConfigPointers::<T> {
             reload_cb: reload_callback,
             reload_data: reload_data.unwrap_or_default(),
+            weechat_ptr: self.ptr,
+            #[cfg(feature = "async")]
+            reload_subscribers: RefCell::new(Vec::new()),
         });
         let config_pointers_ref = Box::leak(config_pointers);
 
-        let c_reload_cb: Option<WeechatReloadT> = match reload_callback {
-            Some(_) => Some(c_reload_cb::<T>),
-            None => None,
-        };
+        let c_reload_cb: Option<WeechatReloadT> = Some(c_reload_cb::<T>);
 
         let config_new = self.get().config_new.unwrap();
         let config_ptr = unsafe {
@@ -551,8 +791,263 @@ impl Weechat {
         Config {
             ptr: config_ptr,
             weechat_ptr: self.ptr,
+            name: name.to_owned(),
             _config_data: config_data,
             sections: HashMap::new(),
+            autosave: None,
+            alive: Rc::new(()),
+        }
+    }
+}
+
+impl<T: Default> Config<T> {
+    /// Take ownership of a configuration file that was created through
+    /// `weechat-sys`, e.g. by a function that isn't wrapped yet.
+    ///
+    /// The file is freed when the Config object is dropped. Sections and
+    /// options that already exist aren't known to the object, they are freed
+    /// together with the file.
+    /// The reload callback that was given when the file was created stays in
+    /// place, so `reloads` never yields an item for such a file.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `ptr` - The pointer of the configuration file.
+    ///
+    /// # Safety
+    ///
+    /// The pointer has to point to a valid configuration file of this
+    /// plugin. Nothing else may free the file, and the callback data of the
+    /// file must not refer to the Config object.
+    pub unsafe fn from_raw(
+        weechat: &Weechat,
+        ptr: *mut t_config_file,
+    ) -> Config<T> {
+        let hdata_get = weechat.get().hdata_get.unwrap();
+        let hdata_name = LossyCString::new("config_file");
+
+        let hdata = hdata_get(weechat.ptr, hdata_name.as_ptr());
+        let name = if hdata.is_null() {
+            None
+        } else {
+            HData {
+                weechat_ptr: weechat.ptr,
+                object: ptr as *mut c_void,
+                ptr: hdata,
+            }
+            .get_var::<Cow<str>>("name")
+            .map(Cow::into_owned)
+        };
+
+        Config {
+            ptr,
+            weechat_ptr: weechat.ptr,
+            name: name.unwrap_or_default(),
+            _config_data: Box::new(ConfigPointers {
+                reload_cb: None,
+                reload_data: T::default(),
+                weechat_ptr: weechat.ptr,
+                #[cfg(feature = "async")]
+                reload_subscribers: RefCell::new(Vec::new()),
+            }),
+            sections: HashMap::new(),
+            autosave: None,
+            alive: Rc::new(()),
+        }
+    }
+}
+
+/// A stream that yields an item every time a config file is reloaded, see
+/// `Config::reloads`.
+#[cfg(feature = "async")]
+pub struct ConfigReloads {
+    subscriber: Rc<RefCell<ReloadSubscriber>>,
+}
+
+#[cfg(feature = "async")]
+#[derive(Default)]
+struct ReloadSubscriber {
+    pending: usize,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+#[cfg(feature = "async")]
+impl<T> ConfigPointers<T> {
+    fn notify_reload_subscribers(&self, close: bool) {
+        let mut subscribers = self.reload_subscribers.borrow_mut();
+
+        // Forget about streams that were dropped.
+        subscribers.retain(|subscriber| subscriber.strong_count() > 0);
+
+        for subscriber in subscribers.iter().filter_map(Weak::upgrade) {
+            let mut subscriber = subscriber.borrow_mut();
+
+            if close {
+                subscriber.closed = true;
+            } else {
+                subscriber.pending += 1;
+            }
+
+            if let Some(waker) = subscriber.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Stream for ConfigReloads {
+    type Item = ();
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<()>> {
+        let mut subscriber = self.subscriber.borrow_mut();
+
+        if subscriber.pending > 0 {
+            subscriber.pending -= 1;
+            Poll::Ready(Some(()))
+        } else if subscriber.closed {
+            Poll::Ready(None)
+        } else {
+            subscriber.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// Dispatches change notifications for the options of a configuration file
+/// to subscribers registered by option name.
+///
+/// A single config hook is used for the whole configuration file, so
+/// subscribing to an option doesn't allocate a callback per option. The hook
+/// is removed when the object is dropped.
+pub struct ConfigWatcher {
+    _hook: Hook,
+    hook_data: Box<ConfigWatcherData>,
+}
+
+type WatcherCallback = HookCallback<dyn FnMut(&Weechat, &str, &str)>;
+
+struct ConfigWatcherData {
+    prefix: String,
+    subscribers: RefCell<HashMap<String, Vec<Rc<WatcherCallback>>>>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl ConfigWatcherData {
+    fn dispatch(&self, option_name: &str, value: &str) {
+        let name = match option_name.strip_prefix(&self.prefix) {
+            Some(n) => n,
+            None => return,
+        };
+
+        let section = name.split('.').next().unwrap_or_default();
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+
+        // The subscribers aren't borrowed while they run, a subscriber may
+        // subscribe to other options.
+        let callbacks: Vec<Rc<WatcherCallback>> = {
+            let subscribers = self.subscribers.borrow();
+            subscribers
+                .get(name)
+                .into_iter()
+                .chain(subscribers.get(section))
+                .flatten()
+                .cloned()
+                .collect()
+        };
+
+        for callback in callbacks {
+            callback.call(&weechat, HookKind::Config, |callback| {
+                callback(&weechat, name, value)
+            });
+        }
+    }
+}
+
+impl ConfigWatcher {
+    /// Subscribe to changes of an option.
+    /// * `option` - The name of the option including the section, e.g.
+    ///     `look.color`. If only a section name is given the callback is
+    ///     called for changes of any option in the section.
+    /// * `callback` - A closure that will be called when the option
+    ///     changes, it receives the name of the option including the section
+    ///     and the new value of the option.
+    pub fn subscribe(
+        &mut self,
+        option: &str,
+        callback: impl FnMut(&Weechat, &str, &str) + 'static,
+    ) {
+        self.hook_data
+            .subscribers
+            .borrow_mut()
+            .entry(option.to_owned())
+            .or_default()
+            .push(Rc::new(HookCallback::new(Box::new(callback))));
+    }
+
+    /// Remove all subscribers of an option or section.
+    pub fn unsubscribe(&mut self, option: &str) {
+        self.hook_data.subscribers.borrow_mut().remove(option);
+    }
+}
+
+impl Weechat {
+    /// Create a watcher for the options of a configuration file.
+    /// * `config_name` - The name of the configuration file that should be
+    ///     watched.
+    pub fn config_watcher(&self, config_name: &str) -> ConfigWatcher {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            option: *const c_char,
+            value: *const c_char,
+        ) -> c_int {
+            let hook_data: &ConfigWatcherData =
+                { &*(pointer as *const ConfigWatcherData) };
+
+            let option = CStr::from_ptr(option).to_string_lossy();
+            let value = if value.is_null() {
+                Cow::from("")
+            } else {
+                CStr::from_ptr(value).to_string_lossy()
+            };
+
+            hook_data.dispatch(&option, &value);
+
+            WEECHAT_RC_OK
+        }
+
+        let data = Box::new(ConfigWatcherData {
+            prefix: format!("{}.", config_name),
+            subscribers: RefCell::new(HashMap::new()),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_config = self.get().hook_config.unwrap();
+
+        let option = LossyCString::new(format!("{}.*", config_name));
+
+        let hook_ptr = unsafe {
+            hook_config(
+                self.ptr,
+                option.as_ptr(),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        ConfigWatcher {
+            _hook: hook,
+            hook_data,
         }
     }
 }
@@ -3,6 +3,7 @@
 use crate::{LossyCString, Weechat};
 use std::borrow::Cow;
 use std::ffi::CStr;
+use std::rc::Weak;
 use weechat_sys::{t_config_option, t_weechat_plugin};
 
 #[derive(Default)]
@@ -49,15 +50,58 @@ pub trait ConfigOption<'a> {
     /// Returns the weechat object that this config option was created with.
     fn get_weechat(&self) -> Weechat;
     /// Returns the raw pointer to the config option.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the config file of the option was freed, see `is_valid`.
     fn get_ptr(&self) -> *mut t_config_option;
 
+    /// Does the option still exist.
+    ///
+    /// An option that was created by a `ConfigSection` is freed together
+    /// with its config file, e.g. by `Config::free`. Using it afterwards
+    /// panics instead of accessing the freed option.
+    fn is_valid(&self) -> bool;
+
+    /// Constructs a ConfigOption from its raw pointer and a weechat pointer,
+    /// e.g. an option pointer returned by `config_get` of `weechat-sys`.
+    /// * `ptr` - The pointer of the option.
+    /// * `weechat_ptr` - The pointer of the plugin, see `Weechat::as_ptr`.
+    ///
+    /// # Safety
+    ///
+    /// Both pointers have to be valid, the methods of the option dereference
+    /// them. The option has to be of the matching type, e.g. a boolean
+    /// option for a `BooleanOption`, and it must not be freed as long as the
+    /// object is used.
+    unsafe fn from_raw(
+        ptr: *mut t_config_option,
+        weechat_ptr: *mut t_weechat_plugin,
+    ) -> Self
+    where
+        Self: Sized;
+
     /// Constructs a ConfigOption from its raw pointer and a weechat pointer.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use the unsafe `ConfigOption::from_raw` instead, the pointers aren't checked"
+    )]
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     fn from_ptrs(
         ptr: *mut t_config_option,
         weechat_ptr: *mut t_weechat_plugin,
-    ) -> Self;
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        unsafe { Self::from_raw(ptr, weechat_ptr) }
+    }
 
     /// Get the value of the option.
+    ///
+    /// Options whose value is null aren't supported, string and color
+    /// options have no value to return for them. Check `is_null` first or
+    /// use `value_with_fallback` for options that can be null.
     fn value(&'a self) -> Self::R;
 
     /// Set the value of the option
@@ -72,6 +116,166 @@ pub trait ConfigOption<'a> {
 
         crate::OptionChanged::from_int(ret)
     }
+
+    /// Get the full name of the option, including the name of the config
+    /// file and the section, e.g. `plugin.look.color`.
+    fn full_name(&self) -> String {
+        let property =
+            |name: &str| option_property(self, name).unwrap_or_default();
+
+        format!(
+            "{}.{}.{}",
+            property("config_name"),
+            property("section_name"),
+            property("name")
+        )
+    }
+
+    /// Get the full name of the parent option, the value of the parent is
+    /// used if the value of this option is null.
+    ///
+    /// A parent is set by creating the option with a name that contains the
+    /// full name of the parent, see `inherit_from`.
+    fn parent_name(&self) -> Option<String> {
+        option_property(self, "parent_name").filter(|name| !name.is_empty())
+    }
+
+    /// Get the parent option of this option.
+    ///
+    /// Returns None if the option has no parent or if the parent doesn't
+    /// exist.
+    fn parent(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let weechat = self.get_weechat();
+        let config_get = weechat.get().config_get.unwrap();
+
+        let name = LossyCString::new(self.parent_name()?);
+
+        let ptr = unsafe { config_get(name.as_ptr()) };
+
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Self::from_raw(ptr, weechat.ptr) })
+        }
+    }
+
+    /// Get the option whose value is in effect for this option, this is the
+    /// option itself if its value isn't null, otherwise the first parent with
+    /// a value that isn't null.
+    ///
+    /// Returns None if the value of the option and of all its parents is
+    /// null.
+    fn inherited(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let mut option =
+            unsafe { Self::from_raw(self.get_ptr(), self.get_weechat().ptr) };
+
+        // Limit the depth in case the parents form a cycle.
+        for _ in 0..MAX_PARENT_DEPTH {
+            if !option.is_null() {
+                return Some(option);
+            }
+
+            option = option.parent()?;
+        }
+
+        None
+    }
+
+    /// Is the value of the option null.
+    fn is_null(&self) -> bool {
+        let weechat = self.get_weechat();
+        let is_null = weechat.get().config_option_is_null.unwrap();
+
+        unsafe { is_null(self.get_ptr()) != 0 }
+    }
+
+    /// Get the value of the option formatted the same way the `/set` command
+    /// displays it, e.g. strings are quoted and color options show the name
+    /// of the color.
+    fn display_value(&self) -> String {
+        let weechat = self.get_weechat();
+
+        let infolist = weechat.infolist_get("option", &self.full_name());
+        let (option_type, value) = match infolist {
+            Some(i) if i.next() => (
+                i.get_string("type")
+                    .map(Cow::into_owned)
+                    .unwrap_or_default(),
+                i.get_string("value").map(Cow::into_owned),
+            ),
+            _ => (String::new(), None),
+        };
+
+        if self.is_null() {
+            display_value(&option_type, None)
+        } else {
+            display_value(&option_type, value.as_deref())
+        }
+    }
+}
+
+/// The maximal number of parents that are followed to find the value of an
+/// option.
+const MAX_PARENT_DEPTH: usize = 16;
+
+/// Build the name for a new option that inherits its value from a parent
+/// option if its own value is null, e.g. a per-server option falling back to
+/// a default option.
+/// * `name` - The name of the new option.
+/// * `parent` - The full name of the parent option, e.g.
+///     `myplugin.server_default.nick`.
+///
+/// ```ignore
+/// let nick = section.new_string_option(
+///     &inherit_from("libera.nick", "myplugin.server_default.nick"),
+///     "nick on the server",
+///     "",
+///     "",
+///     true,
+///     None,
+///     None::<()>,
+/// );
+/// ```
+pub fn inherit_from(name: &str, parent: &str) -> String {
+    format!("{} << {}", name, parent)
+}
+
+/// Get a string property of an option, e.g. `name` or `parent_name`.
+pub(crate) fn option_property<'a>(
+    option: &(impl ConfigOption<'a> + ?Sized),
+    property: &str,
+) -> Option<String> {
+    let weechat = option.get_weechat();
+    let get_string = weechat.get().config_option_get_string.unwrap();
+
+    let property = LossyCString::new(property);
+
+    unsafe {
+        let value = get_string(option.get_ptr(), property.as_ptr());
+        if value.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(value).to_string_lossy().into_owned())
+        }
+    }
+}
+
+/// Format an option value the same way the `/set` command displays it.
+/// * `option_type` - The type of the option, e.g. `string`.
+/// * `value` - The value of the option as a string, None if the value is
+///     null.
+pub(crate) fn display_value(option_type: &str, value: Option<&str>) -> String {
+    match value {
+        Some(v) if option_type == "string" => format!("\"{}\"", v),
+        Some(v) => v.to_owned(),
+        None => "null".to_owned(),
+    }
 }
 
 pub(crate) struct OptionPointers<T, A, B, C> {
@@ -84,28 +288,60 @@ pub(crate) struct OptionPointers<T, A, B, C> {
     pub(crate) delete_cb_data: C,
 }
 
+/// Tracks if the config file an option was created from still exists.
+///
+/// Options that weren't created by a `ConfigSection`, e.g. the ones passed
+/// to callbacks or created with `from_raw`, have no owner and are assumed to
+/// be valid.
+#[derive(Clone, Default)]
+pub(crate) struct OptionOwner(Option<Weak<()>>);
+
+impl OptionOwner {
+    pub(crate) fn new(config: Weak<()>) -> OptionOwner {
+        OptionOwner(Some(config))
+    }
+
+    fn is_alive(&self) -> bool {
+        self.0
+            .as_ref()
+            .is_none_or(|config| config.strong_count() > 0)
+    }
+
+    fn check(&self) {
+        assert!(
+            self.is_alive(),
+            "the config file of the option was freed, the option can't be \
+             used anymore"
+        );
+    }
+}
+
 /// A config option with a string value.
 pub struct StringOption {
     pub(crate) ptr: *mut t_config_option,
     pub(crate) weechat_ptr: *mut t_weechat_plugin,
+    pub(crate) owner: OptionOwner,
 }
 
 /// A config option with a boolean value.
 pub struct BooleanOption {
     pub(crate) ptr: *mut t_config_option,
     pub(crate) weechat_ptr: *mut t_weechat_plugin,
+    pub(crate) owner: OptionOwner,
 }
 
 /// A config option with a integer value.
 pub struct IntegerOption {
     pub(crate) ptr: *mut t_config_option,
     pub(crate) weechat_ptr: *mut t_weechat_plugin,
+    pub(crate) owner: OptionOwner,
 }
 
 /// A config option with a color value.
 pub struct ColorOption {
     pub(crate) ptr: *mut t_config_option,
     pub(crate) weechat_ptr: *mut t_weechat_plugin,
+    pub(crate) owner: OptionOwner,
 }
 
 impl<'a> ConfigOption<'a> for StringOption {
@@ -115,13 +351,23 @@ impl<'a> ConfigOption<'a> for StringOption {
         Weechat::from_ptr(self.weechat_ptr)
     }
     fn get_ptr(&self) -> *mut t_config_option {
+        self.owner.check();
         self.ptr
     }
-    fn from_ptrs(
+
+    fn is_valid(&self) -> bool {
+        self.owner.is_alive()
+    }
+
+    unsafe fn from_raw(
         ptr: *mut t_config_option,
         weechat_ptr: *mut t_weechat_plugin,
     ) -> StringOption {
-        StringOption { ptr, weechat_ptr }
+        StringOption {
+            ptr,
+            weechat_ptr,
+            owner: OptionOwner::default(),
+        }
     }
 
     fn value(&self) -> Self::R {
@@ -145,13 +391,23 @@ impl<'a> ConfigOption<'a> for BooleanOption {
         Weechat::from_ptr(self.weechat_ptr)
     }
     fn get_ptr(&self) -> *mut t_config_option {
+        self.owner.check();
         self.ptr
     }
-    fn from_ptrs(
+
+    fn is_valid(&self) -> bool {
+        self.owner.is_alive()
+    }
+
+    unsafe fn from_raw(
         ptr: *mut t_config_option,
         weechat_ptr: *mut t_weechat_plugin,
     ) -> BooleanOption {
-        BooleanOption { ptr, weechat_ptr }
+        BooleanOption {
+            ptr,
+            weechat_ptr,
+            owner: OptionOwner::default(),
+        }
     }
 
     fn value(&self) -> Self::R {
@@ -173,13 +429,23 @@ impl<'a> ConfigOption<'a> for IntegerOption {
         Weechat::from_ptr(self.weechat_ptr)
     }
     fn get_ptr(&self) -> *mut t_config_option {
+        self.owner.check();
         self.ptr
     }
-    fn from_ptrs(
+
+    fn is_valid(&self) -> bool {
+        self.owner.is_alive()
+    }
+
+    unsafe fn from_raw(
         ptr: *mut t_config_option,
         weechat_ptr: *mut t_weechat_plugin,
     ) -> IntegerOption {
-        IntegerOption { ptr, weechat_ptr }
+        IntegerOption {
+            ptr,
+            weechat_ptr,
+            owner: OptionOwner::default(),
+        }
     }
 
     fn value(&self) -> Self::R {
@@ -200,13 +466,23 @@ impl<'a> ConfigOption<'a> for ColorOption {
         Weechat::from_ptr(self.weechat_ptr)
     }
     fn get_ptr(&self) -> *mut t_config_option {
+        self.owner.check();
         self.ptr
     }
-    fn from_ptrs(
+
+    fn is_valid(&self) -> bool {
+        self.owner.is_alive()
+    }
+
+    unsafe fn from_raw(
         ptr: *mut t_config_option,
         weechat_ptr: *mut t_weechat_plugin,
     ) -> ColorOption {
-        ColorOption { ptr, weechat_ptr }
+        ColorOption {
+            ptr,
+            weechat_ptr,
+            owner: OptionOwner::default(),
+        }
     }
 
     fn value(&'a self) -> Self::R {
@@ -234,6 +510,15 @@ impl StringOption {
     pub fn set(&self, value: &str) -> crate::OptionChanged {
         set_str_option(self, value)
     }
+
+    /// Get the value of the option, falling back to the value of the parent
+    /// option if the value is null.
+    ///
+    /// Returns None if the value of the option and of all its parents is
+    /// null.
+    pub fn value_with_fallback(&self) -> Option<String> {
+        self.inherited().map(|option| option.value().into_owned())
+    }
 }
 
 impl ColorOption {
@@ -241,6 +526,15 @@ impl ColorOption {
     pub fn set(&self, value: &str) -> crate::OptionChanged {
         set_str_option(self, value)
     }
+
+    /// Get the value of the option, falling back to the value of the parent
+    /// option if the value is null.
+    ///
+    /// Returns None if the value of the option and of all its parents is
+    /// null.
+    pub fn value_with_fallback(&self) -> Option<String> {
+        self.inherited().map(|option| option.value().into_owned())
+    }
 }
 
 impl IntegerOption {
@@ -248,6 +542,26 @@ impl IntegerOption {
     pub fn set(&self, value: &str) -> crate::OptionChanged {
         set_str_option(self, value)
     }
+
+    /// Get the value of the option, falling back to the value of the parent
+    /// option if the value is null.
+    ///
+    /// Returns None if the value of the option and of all its parents is
+    /// null.
+    pub fn value_with_fallback(&self) -> Option<i32> {
+        self.inherited().map(|option| option.value())
+    }
+}
+
+impl BooleanOption {
+    /// Get the value of the option, falling back to the value of the parent
+    /// option if the value is null.
+    ///
+    /// Returns None if the value of the option and of all its parents is
+    /// null.
+    pub fn value_with_fallback(&self) -> Option<bool> {
+        self.inherited().map(|option| option.value())
+    }
 }
 
 fn set_str_option<'a>(
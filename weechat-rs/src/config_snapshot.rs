@@ -0,0 +1,178 @@
+//! Copies of option values that can be read outside of the main thread.
+//!
+//! The WeeChat API may only be used on the main thread, async tasks and
+//! worker threads that need configuration values can get a `ConfigSnapshot`
+//! instead. The snapshot is taken on the main thread and doesn't change when
+//! the options change, a new snapshot needs to be taken for that, e.g. in
+//! the change callback of the options.
+//!
+//! ```ignore
+//! let snapshot = weechat.config_snapshot(&[
+//!     "weechat.look.buffer_time_format",
+//!     "myplugin.network.timeout",
+//! ]);
+//!
+//! std::thread::spawn(move || {
+//!     let timeout = snapshot.integer("myplugin.network.timeout");
+//! });
+//! ```
+
+use std::collections::HashMap;
+
+use weechat_sys::t_config_option;
+
+use crate::config_options::option_property;
+use crate::{
+    BooleanOption, ColorOption, ConfigOption, IntegerOption, LossyCString,
+    StringOption, Weechat,
+};
+
+/// The value of an option at the time the snapshot was taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    /// The value of a boolean option.
+    Boolean(bool),
+    /// The value of an integer option.
+    Integer(i32),
+    /// The value of a string or enum option.
+    String(String),
+    /// The name of the color of a color option.
+    Color(String),
+    /// The option has a null value.
+    Null,
+}
+
+/// The values of a set of options, see the module documentation.
+///
+/// Options that didn't exist when the snapshot was taken are missing from the
+/// snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigSnapshot {
+    values: HashMap<String, OptionValue>,
+}
+
+impl OptionValue {
+    fn from_ptr(weechat: &Weechat, ptr: *mut t_config_option) -> OptionValue {
+        let option = unsafe { StringOption::from_raw(ptr, weechat.ptr) };
+
+        if option.is_null() {
+            return OptionValue::Null;
+        }
+
+        match option_property(&option, "type").as_deref() {
+            Some("boolean") => OptionValue::Boolean(
+                unsafe { BooleanOption::from_raw(ptr, weechat.ptr) }.value(),
+            ),
+            Some("integer") => OptionValue::Integer(
+                unsafe { IntegerOption::from_raw(ptr, weechat.ptr) }.value(),
+            ),
+            Some("color") => OptionValue::Color(
+                unsafe { ColorOption::from_raw(ptr, weechat.ptr) }
+                    .value()
+                    .into_owned(),
+            ),
+            _ => OptionValue::String(option.value().into_owned()),
+        }
+    }
+}
+
+impl ConfigSnapshot {
+    /// Add the current value of an option to the snapshot, replacing a
+    /// previous value of the option.
+    /// * `option` - The option whose value should be copied.
+    pub fn capture<'a>(&mut self, option: &impl ConfigOption<'a>) {
+        let weechat = option.get_weechat();
+        let value = OptionValue::from_ptr(&weechat, option.get_ptr());
+
+        self.values.insert(option.full_name(), value);
+    }
+
+    /// Get the value of an option.
+    /// * `name` - The full name of the option, e.g. `weechat.look.mouse`.
+    pub fn get(&self, name: &str) -> Option<&OptionValue> {
+        self.values.get(name)
+    }
+
+    /// Get the value of a boolean option.
+    ///
+    /// Returns None if the option isn't part of the snapshot, isn't a boolean
+    /// option or is null.
+    /// * `name` - The full name of the option.
+    pub fn boolean(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            OptionValue::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get the value of an integer option.
+    ///
+    /// Returns None if the option isn't part of the snapshot, isn't an
+    /// integer option or is null.
+    /// * `name` - The full name of the option.
+    pub fn integer(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            OptionValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a string, enum or color option.
+    ///
+    /// Returns None if the option isn't part of the snapshot, is of another
+    /// type or is null.
+    /// * `name` - The full name of the option.
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            OptionValue::String(value) | OptionValue::Color(value) => {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the full names of the options in the snapshot.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// Get the number of options in the snapshot.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Is the snapshot empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl Weechat {
+    /// Copy the current values of options into a snapshot that can be sent
+    /// to other threads.
+    /// * `names` - The full names of the options, e.g.
+    ///     `weechat.look.buffer_time_format`. Options that don't exist are
+    ///     skipped.
+    pub fn config_snapshot<I, S>(&self, names: I) -> ConfigSnapshot
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let config_get = self.get().config_get.unwrap();
+        let mut snapshot = ConfigSnapshot::default();
+
+        for name in names {
+            let name = name.as_ref();
+            let c_name = LossyCString::new(name);
+            let ptr = unsafe { config_get(c_name.as_ptr()) };
+
+            if !ptr.is_null() {
+                snapshot
+                    .values
+                    .insert(name.to_owned(), OptionValue::from_ptr(self, ptr));
+            }
+        }
+
+        snapshot
+    }
+}
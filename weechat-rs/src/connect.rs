@@ -0,0 +1,415 @@
+//! Connect to remote hosts with sockets managed by WeeChat, the connection is
+//! made in the background and can go through the proxies that are configured
+//! in WeeChat.
+
+use libc::{c_char, c_int};
+use std::cell::Cell;
+use std::error::Error;
+use std::ffi::CStr;
+use std::fmt;
+use std::mem;
+use std::net::TcpStream;
+use std::os::raw::c_void;
+use std::os::unix::io::FromRawFd;
+use std::ptr;
+
+use weechat_sys::{t_weechat_plugin, WEECHAT_RC_OK};
+
+use crate::hooks::{catch_panic, sealed, Hook, HookSettings};
+use crate::profiling::profile;
+use crate::{HookKind, LossyCString, ReturnCode, Weechat};
+
+/// The address and settings of a connection made with `ConnectHook::new`.
+///
+/// ```ignore
+/// let options = ConnectOptions::new("irc.libera.chat", 6667)
+///     .proxy("tor")
+///     .ipv6(true)
+///     .retry(3);
+///
+/// let hook = ConnectHook::new(&weechat, &options, |_, connection| {
+///     // ...
+///     ReturnCode::Ok
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConnectOptions {
+    address: String,
+    port: u16,
+    proxy: Option<String>,
+    ipv6: bool,
+    retry: u32,
+    local_hostname: Option<String>,
+    tls: Option<TlsOptions>,
+}
+
+#[derive(Debug, Clone)]
+struct TlsOptions {
+    session: *mut c_void,
+    callback: *mut c_void,
+    dhkey_size: i32,
+    priorities: String,
+}
+
+impl ConnectOptions {
+    /// Create the settings for a connection to an address.
+    /// * `address` - The name or IP address of the host.
+    /// * `port` - The port that should be connected to.
+    pub fn new(address: &str, port: u16) -> ConnectOptions {
+        ConnectOptions {
+            address: address.to_owned(),
+            port,
+            proxy: None,
+            ipv6: false,
+            retry: 0,
+            local_hostname: None,
+            tls: None,
+        }
+    }
+
+    /// Connect through a proxy that is configured in WeeChat, the proxies
+    /// can be listed with `Weechat::proxies`.
+    /// * `proxy` - The name of the proxy, see the `/proxy` command.
+    pub fn proxy(mut self, proxy: &str) -> ConnectOptions {
+        self.proxy = Some(proxy.to_owned());
+        self
+    }
+
+    /// Should IPv6 be used, IPv4 is used as a fallback.
+    pub fn ipv6(mut self, ipv6: bool) -> ConnectOptions {
+        self.ipv6 = ipv6;
+        self
+    }
+
+    /// Set how many times the connection should be retried, this is used
+    /// if a host resolves to multiple IP addresses.
+    pub fn retry(mut self, retry: u32) -> ConnectOptions {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the local hostname that should be used for the connection.
+    pub fn local_hostname(mut self, hostname: &str) -> ConnectOptions {
+        self.local_hostname = Some(hostname.to_owned());
+        self
+    }
+
+    /// Make a TLS handshake with GnuTLS once the connection is established.
+    ///
+    /// # Safety
+    ///
+    /// `session` has to point to a `gnutls_session_t` and `callback` to a
+    /// GnuTLS certificate callback or be null. Both have to stay valid until
+    /// the callback of the connection was called.
+    /// * `session` - The GnuTLS session that should be used.
+    /// * `callback` - The GnuTLS callback for certificate verification.
+    /// * `dhkey_size` - The size of the key used for the Diffie-Hellman key
+    ///     exchange.
+    /// * `priorities` - The GnuTLS priorities, e.g. `NORMAL`.
+    pub unsafe fn tls(
+        mut self,
+        session: *mut c_void,
+        callback: *mut c_void,
+        dhkey_size: i32,
+        priorities: &str,
+    ) -> ConnectOptions {
+        self.tls = Some(TlsOptions {
+            session,
+            callback,
+            dhkey_size,
+            priorities: priorities.to_owned(),
+        });
+        self
+    }
+}
+
+/// A connection that was established by a `ConnectHook`.
+#[derive(Debug)]
+pub struct Connection {
+    /// The connected socket, the socket is closed when the stream is
+    /// dropped.
+    pub stream: TcpStream,
+    /// The IP address that was connected to.
+    pub ip_address: String,
+}
+
+/// Error returned if a connection couldn't be established.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectError {
+    /// The address couldn't be resolved.
+    AddressNotFound,
+    /// No IP address was found for the address.
+    IpAddressNotFound,
+    /// The connection was refused.
+    ConnectionRefused,
+    /// The proxy returned an error.
+    ProxyError,
+    /// The local hostname couldn't be used.
+    LocalHostnameError,
+    /// The TLS session couldn't be initialized, contains the GnuTLS error
+    /// code.
+    TlsInitError(i32),
+    /// The TLS handshake failed, contains the GnuTLS error code.
+    TlsHandshakeError(i32),
+    /// WeeChat ran out of memory.
+    MemoryError,
+    /// The connection timed out.
+    Timeout,
+    /// The socket couldn't be created.
+    SocketError,
+    /// An unknown status was returned by WeeChat.
+    Other(i32),
+}
+
+impl ConnectError {
+    fn from_status(status: c_int, tls_rc: c_int) -> ConnectError {
+        match status {
+            1 => ConnectError::AddressNotFound,
+            2 => ConnectError::IpAddressNotFound,
+            3 => ConnectError::ConnectionRefused,
+            4 => ConnectError::ProxyError,
+            5 => ConnectError::LocalHostnameError,
+            6 => ConnectError::TlsInitError(tls_rc),
+            7 => ConnectError::TlsHandshakeError(tls_rc),
+            8 => ConnectError::MemoryError,
+            9 => ConnectError::Timeout,
+            10 => ConnectError::SocketError,
+            status => ConnectError::Other(status),
+        }
+    }
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConnectError::AddressNotFound => write!(f, "address not found"),
+            ConnectError::IpAddressNotFound => {
+                write!(f, "IP address not found")
+            }
+            ConnectError::ConnectionRefused => write!(f, "connection refused"),
+            ConnectError::ProxyError => write!(f, "proxy error"),
+            ConnectError::LocalHostnameError => {
+                write!(f, "unable to set local hostname")
+            }
+            ConnectError::TlsInitError(rc) => {
+                write!(f, "TLS init error ({})", rc)
+            }
+            ConnectError::TlsHandshakeError(rc) => {
+                write!(f, "TLS handshake failed ({})", rc)
+            }
+            ConnectError::MemoryError => write!(f, "not enough memory"),
+            ConnectError::Timeout => write!(f, "timeout"),
+            ConnectError::SocketError => write!(f, "unable to create socket"),
+            ConnectError::Other(status) => {
+                write!(f, "unknown error ({})", status)
+            }
+        }
+    }
+}
+
+impl Error for ConnectError {}
+
+/// Hook for a connection, the connection attempt is cancelled if the object
+/// is dropped before the callback was called.
+pub struct ConnectHook {
+    hook: Option<Hook>,
+    hook_data: Box<ConnectHookData>,
+}
+
+type ConnectCallback =
+    dyn FnOnce(&Weechat, Result<Connection, ConnectError>) -> ReturnCode;
+
+struct ConnectHookData {
+    callback: Cell<Option<Box<ConnectCallback>>>,
+    address: String,
+    finished: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for ConnectHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        self.hook
+            .as_ref()
+            .filter(|_| !self.hook_data.finished.get())
+    }
+}
+
+impl HookSettings for ConnectHook {}
+
+impl ConnectHook {
+    /// Connect to a remote host in the background.
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `options` - The address and settings of the connection.
+    /// * `callback` - A closure that will be called once the connection is
+    ///     established or failed. The socket of an established connection is
+    ///     owned by the callback, it can be watched with an `FdHook`.
+    pub fn new<C>(
+        weechat: &Weechat,
+        options: &ConnectOptions,
+        callback: C,
+    ) -> ConnectHook
+    where
+        C: FnOnce(&Weechat, Result<Connection, ConnectError>) -> ReturnCode
+            + 'static,
+    {
+        weechat.new_connect_hook(options, Box::new(callback))
+    }
+
+    /// Has the callback of the connection been called.
+    pub fn is_finished(&self) -> bool {
+        self.hook_data.finished.get()
+    }
+}
+
+impl Drop for ConnectHook {
+    fn drop(&mut self) {
+        // WeeChat removes the hook itself after the callback was called.
+        if self.hook_data.finished.get() {
+            if let Some(hook) = self.hook.take() {
+                mem::forget(hook);
+            }
+        }
+    }
+}
+
+impl Weechat {
+    /// Connect to a remote host in the background.
+    ///
+    /// * `options` - The address and settings of the connection.
+    /// * `callback` - A function that will be called once the connection is
+    ///     established or failed. The socket of an established connection is
+    ///     owned by the callback, it can be watched with `hook_fd`.
+    /// * `callback_data` - Data that will be passed to the callback. This data
+    ///     will be freed when the hook is dropped.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `ConnectHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_connect<T>(
+        &self,
+        options: &ConnectOptions,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            connection: Result<Connection, ConnectError>,
+        ) -> ReturnCode,
+        callback_data: Option<T>,
+    ) -> ConnectHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_connect_hook(
+            options,
+            Box::new(move |weechat, connection| {
+                callback(&data, weechat, connection)
+            }),
+        )
+    }
+
+    fn new_connect_hook(
+        &self,
+        options: &ConnectOptions,
+        callback: Box<ConnectCallback>,
+    ) -> ConnectHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            status: c_int,
+            gnutls_rc: c_int,
+            sock: c_int,
+            _error: *const c_char,
+            ip_address: *const c_char,
+        ) -> c_int {
+            let hook_data: &ConnectHookData =
+                { &*(pointer as *const ConnectHookData) };
+            hook_data.finished.set(true);
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let connection = if status == 0 && sock >= 0 {
+                let ip_address = if ip_address.is_null() {
+                    String::new()
+                } else {
+                    CStr::from_ptr(ip_address).to_string_lossy().into_owned()
+                };
+
+                Ok(Connection {
+                    stream: TcpStream::from_raw_fd(sock),
+                    ip_address,
+                })
+            } else {
+                Err(ConnectError::from_status(status, gnutls_rc))
+            };
+
+            match hook_data.callback.take() {
+                Some(callback) => {
+                    catch_panic(&weechat, HookKind::Connect, || {
+                        profile(
+                            HookKind::Connect,
+                            || hook_data.address.clone(),
+                            || callback(&weechat, connection),
+                        )
+                    })
+                    .unwrap_or(ReturnCode::Error) as i32
+                }
+                None => WEECHAT_RC_OK,
+            }
+        }
+
+        let data = Box::new(ConnectHookData {
+            callback: Cell::new(Some(callback)),
+            address: format!("{}/{}", options.address, options.port),
+            finished: Cell::new(false),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_connect = self.get().hook_connect.unwrap();
+
+        let proxy = options.proxy.as_deref().map(LossyCString::new);
+        let address = LossyCString::new(&options.address);
+        let local_hostname =
+            options.local_hostname.as_deref().map(LossyCString::new);
+        let priorities = options
+            .tls
+            .as_ref()
+            .map(|tls| LossyCString::new(&tls.priorities));
+
+        let (session, tls_callback, dhkey_size) = match &options.tls {
+            Some(tls) => (tls.session, tls.callback, tls.dhkey_size),
+            None => (ptr::null_mut(), ptr::null_mut(), 0),
+        };
+
+        let hook_ptr = unsafe {
+            hook_connect(
+                self.ptr,
+                proxy.as_ref().map_or(ptr::null(), |p| p.as_ptr()),
+                address.as_ptr(),
+                options.port as c_int,
+                options.ipv6 as c_int,
+                options.retry.min(i32::MAX as u32) as c_int,
+                session,
+                tls_callback,
+                dhkey_size,
+                priorities.as_ref().map_or(ptr::null(), |p| p.as_ptr()),
+                local_hostname.as_ref().map_or(ptr::null(), |h| h.as_ptr()),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        ConnectHook {
+            hook: Some(hook),
+            hook_data,
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Coalesce bursts of work into a single deferred call.
+//!
+//! Some work only needs to happen once after a burst of events, e.g. a
+//! nicklist rebuild after many joins or a bar item update after every
+//! incoming line. A `Debouncer` runs its callback once the events stopped
+//! for a while, a `Throttle` runs its callback at most once per interval.
+//! Both run the callback from a timer on the main loop, never from inside of
+//! `trigger`.
+//!
+//! ```ignore
+//! let update = Throttle::new(&weechat, Duration::from_millis(500), |w| {
+//!     w.update_bar_item("myplugin_status");
+//! });
+//!
+//! // In the callback of a print hook.
+//! update.trigger();
+//! ```
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::time::{Duration, Instant};
+
+use weechat_sys::t_weechat_plugin;
+
+use crate::Weechat;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Debounce,
+    Throttle,
+}
+
+struct DeferredState {
+    weechat_ptr: *mut t_weechat_plugin,
+    mode: Mode,
+    delay: Duration,
+    deadline: Option<Instant>,
+    last_run: Option<Instant>,
+    scheduled: bool,
+    callback: Option<Box<dyn FnMut(&Weechat)>>,
+}
+
+/// The state shared between the handle and the timers. The timers only hold
+/// a weak reference, so dropping the handle cancels a pending call.
+struct Deferred {
+    state: Rc<RefCell<DeferredState>>,
+}
+
+/// Runs a callback once calls to `trigger` stopped for the configured delay,
+/// see the module documentation.
+///
+/// A pending call is cancelled if the debouncer is dropped.
+pub struct Debouncer {
+    inner: Deferred,
+}
+
+/// Runs a callback at most once per interval, no matter how often `trigger`
+/// is called, see the module documentation.
+///
+/// The first call after a quiet period runs on the next iteration of the main
+/// loop. A pending call is cancelled if the throttle is dropped.
+pub struct Throttle {
+    inner: Deferred,
+}
+
+/// Round a delay up to whole milliseconds, the resolution of the timers, so
+/// the timer doesn't fire right before the deadline.
+fn timer_delay(delay: Duration) -> Duration {
+    let millis = delay.as_micros().div_ceil(1000);
+    Duration::from_millis(millis as u64)
+}
+
+fn schedule(state: &Rc<RefCell<DeferredState>>, delay: Duration) {
+    let mut deferred = state.borrow_mut();
+    if deferred.scheduled {
+        return;
+    }
+    deferred.scheduled = true;
+
+    let weechat = Weechat::from_ptr(deferred.weechat_ptr);
+    let state: Weak<RefCell<DeferredState>> = Rc::downgrade(state);
+
+    weechat.call_later(timer_delay(delay), move |_| {
+        if let Some(state) = state.upgrade() {
+            fire(&state);
+        }
+    });
+}
+
+/// Run the callback if the deadline was reached, otherwise wait for the rest
+/// of the time. A timer is only pending while `scheduled` is set, later
+/// triggers move the deadline instead of adding timers.
+fn fire(state: &Rc<RefCell<DeferredState>>) {
+    let now = Instant::now();
+
+    let deadline = {
+        let mut deferred = state.borrow_mut();
+        deferred.scheduled = false;
+        deferred.deadline
+    };
+
+    match deadline {
+        None => (),
+        Some(deadline) if deadline > now => schedule(state, deadline - now),
+        Some(_) => run(state),
+    }
+}
+
+/// Run the callback now. The state isn't borrowed while the callback runs,
+/// the callback may trigger the call again.
+fn run(state: &Rc<RefCell<DeferredState>>) {
+    let (weechat_ptr, callback) = {
+        let mut deferred = state.borrow_mut();
+        deferred.deadline = None;
+        deferred.last_run = Some(Instant::now());
+        (deferred.weechat_ptr, deferred.callback.take())
+    };
+
+    // The callback is missing if it's already running further up the stack.
+    if let Some(mut callback) = callback {
+        callback(&Weechat::from_ptr(weechat_ptr));
+        state.borrow_mut().callback = Some(callback);
+    }
+}
+
+impl Deferred {
+    fn new(
+        weechat: &Weechat,
+        mode: Mode,
+        delay: Duration,
+        callback: Box<dyn FnMut(&Weechat)>,
+    ) -> Deferred {
+        let state = DeferredState {
+            weechat_ptr: weechat.ptr,
+            mode,
+            delay,
+            deadline: None,
+            last_run: None,
+            scheduled: false,
+            callback: Some(callback),
+        };
+
+        Deferred {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    fn trigger(&self) {
+        let now = Instant::now();
+
+        let deadline = {
+            let mut deferred = self.state.borrow_mut();
+
+            let deadline = match (deferred.mode, deferred.deadline) {
+                (Mode::Debounce, _) => now + deferred.delay,
+                (Mode::Throttle, Some(deadline)) => deadline,
+                (Mode::Throttle, None) => match deferred.last_run {
+                    Some(last_run) => (last_run + deferred.delay).max(now),
+                    None => now,
+                },
+            };
+
+            deferred.deadline = Some(deadline);
+            deadline
+        };
+
+        schedule(&self.state, deadline.saturating_duration_since(now));
+    }
+
+    fn flush(&self) {
+        if self.is_pending() {
+            run(&self.state);
+        }
+    }
+
+    fn cancel(&self) {
+        self.state.borrow_mut().deadline = None;
+    }
+
+    fn is_pending(&self) -> bool {
+        self.state.borrow().deadline.is_some()
+    }
+}
+
+impl Debouncer {
+    /// Create a debouncer.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `delay` - How long no `trigger` calls need to happen before the
+    ///     callback runs.
+    /// * `callback` - The work that should be done once per burst.
+    pub fn new(
+        weechat: &Weechat,
+        delay: Duration,
+        callback: impl FnMut(&Weechat) + 'static,
+    ) -> Debouncer {
+        Debouncer {
+            inner: Deferred::new(
+                weechat,
+                Mode::Debounce,
+                delay,
+                Box::new(callback),
+            ),
+        }
+    }
+
+    /// Request a call of the callback, the call is delayed until no
+    /// requests were made for the delay of the debouncer.
+    pub fn trigger(&self) {
+        self.inner.trigger();
+    }
+
+    /// Run a pending call right away instead of waiting for the delay.
+    pub fn flush(&self) {
+        self.inner.flush();
+    }
+
+    /// Cancel a pending call.
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    /// Is a call of the callback pending.
+    pub fn is_pending(&self) -> bool {
+        self.inner.is_pending()
+    }
+}
+
+impl Throttle {
+    /// Create a throttle.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `interval` - The minimal time between two calls of the callback.
+    /// * `callback` - The work that should be done at most once per
+    ///     interval.
+    pub fn new(
+        weechat: &Weechat,
+        interval: Duration,
+        callback: impl FnMut(&Weechat) + 'static,
+    ) -> Throttle {
+        Throttle {
+            inner: Deferred::new(
+                weechat,
+                Mode::Throttle,
+                interval,
+                Box::new(callback),
+            ),
+        }
+    }
+
+    /// Request a call of the callback. Requests are coalesced, the call
+    /// happens on the next iteration of the main loop or, if the callback
+    /// ran recently, once the interval since the last call has passed.
+    pub fn trigger(&self) {
+        self.inner.trigger();
+    }
+
+    /// Run a pending call right away instead of waiting for the interval.
+    pub fn flush(&self) {
+        self.inner.flush();
+    }
+
+    /// Cancel a pending call.
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    /// Is a call of the callback pending.
+    pub fn is_pending(&self) -> bool {
+        self.inner.is_pending()
+    }
+}
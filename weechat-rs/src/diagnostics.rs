@@ -0,0 +1,204 @@
+//! Support information for bug reports.
+//!
+//! `Weechat::enable_debug_command` adds the command `/<plugin> debug`, it
+//! prints the versions of the plugin and WeeChat, the hooks and buffers of
+//! the plugin and the errors and panics that were recorded last. Users can
+//! paste the output into bug reports.
+//!
+//! The command can be enabled with the `weechat_plugin!` macro:
+//!
+//! ```ignore
+//! weechat_plugin!(
+//!     SamplePlugin,
+//!     name: "rust_sample",
+//!     author: "poljar",
+//!     description: "",
+//!     version: "0.1.0",
+//!     license: "MIT",
+//!     debug_command: true
+//! );
+//! ```
+//!
+//! Errors that are returned from command callbacks are recorded
+//! automatically, other errors can be recorded with `Weechat::record_error`.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::panic::{self, PanicHookInfo};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+
+use crate::export::plugin_hooks;
+use crate::{Buffer, CommandRunHook, ReturnCode, Weechat};
+
+/// How many errors are kept for the debug command.
+const MAX_ERRORS: usize = 10;
+
+/// Panics can happen on any thread, so the errors live in a static.
+static ERRORS: Mutex<VecDeque<(DateTime<Utc>, String)>> =
+    Mutex::new(VecDeque::new());
+
+type PanicHook = dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static;
+
+/// The hook of the debug command and the version it prints.
+struct DebugCommand {
+    _hook: CommandRunHook,
+    plugin_version: String,
+}
+
+thread_local! {
+    static DEBUG_COMMAND: RefCell<Option<DebugCommand>> =
+        const { RefCell::new(None) };
+    /// The panic hook that was set before the debug command was enabled.
+    static PREVIOUS_PANIC_HOOK: RefCell<Option<Arc<PanicHook>>> =
+        const { RefCell::new(None) };
+}
+
+fn record(error: String) {
+    if let Ok(mut errors) = ERRORS.lock() {
+        if errors.len() == MAX_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back((Utc::now(), error));
+    }
+}
+
+/// The plugin version of the debug command if it's enabled.
+pub(crate) fn debug_command_version() -> Option<String> {
+    DEBUG_COMMAND.with(|command| {
+        command
+            .borrow()
+            .as_ref()
+            .map(|command| command.plugin_version.clone())
+    })
+}
+
+pub(crate) fn print_debug_info(
+    weechat: &Weechat,
+    buffer: &Buffer,
+    plugin_version: &str,
+) {
+    let plugin = weechat.plugin_name().into_owned();
+    let weechat_version = weechat
+        .info_get("version", "")
+        .map(|version| version.into_owned())
+        .unwrap_or_default();
+
+    buffer.print(&format!("{} debug information:", plugin));
+    buffer.print(&format!("  plugin version: {}", plugin_version));
+    buffer.print(&format!(
+        "  weechat-rs version: {}",
+        env!("CARGO_PKG_VERSION")
+    ));
+    buffer.print(&format!("  WeeChat version: {}", weechat_version));
+
+    let hooks = plugin_hooks(weechat, &plugin);
+    buffer.print(&format!("  hooks ({}):", hooks.len()));
+    for (kind, name) in hooks {
+        buffer.print(&format!("    {} {}", kind, name));
+    }
+
+    let buffers = weechat.plugin_buffers();
+    buffer.print(&format!("  buffers ({}):", buffers.len()));
+    for plugin_buffer in buffers {
+        buffer.print(&format!("    {}", plugin_buffer.get_full_name()));
+    }
+
+    let errors = weechat.recent_errors();
+    buffer.print(&format!("  recent errors ({}):", errors.len()));
+    for (date, error) in errors {
+        buffer.print(&format!("    {} {}", date.format("%F %T"), error));
+    }
+}
+
+/// Remove the debug command and the panic hook.
+pub(crate) fn shutdown() {
+    drop(DEBUG_COMMAND.with(|hook| hook.borrow_mut().take()));
+
+    let previous = PREVIOUS_PANIC_HOOK.with(|hook| hook.borrow_mut().take());
+
+    if let Some(previous) = previous {
+        // The panic hook points into the plugin, restore the one that was
+        // set before.
+        let _ = panic::take_hook();
+        panic::set_hook(Box::new(move |info| previous(info)));
+    }
+}
+
+impl Weechat {
+    /// Add the command `/<plugin> debug` printing support information, see
+    /// the module documentation.
+    ///
+    /// The debug subcommand is handled before the command of the plugin
+    /// runs, other runs of the command are left alone. A `CommandRouter`
+    /// that is hooked under the name of the plugin lists the subcommand in
+    /// its help text and completion.
+    ///
+    /// Panics of the plugin are recorded as errors once the command is
+    /// enabled, including their location. Panics in the callbacks of hooks
+    /// are caught and the call fails, panics in other callbacks, e.g. the
+    /// input callback of a buffer, still abort WeeChat. The command is
+    /// removed and the previous panic hook is restored when the plugin is
+    /// unloaded.
+    /// * `plugin_version` - The version of the plugin that is printed.
+    pub fn enable_debug_command(&self, plugin_version: &str) {
+        let name = format!("/{}", self.plugin_name());
+        let version = plugin_version.to_owned();
+
+        // Hook every run of the command, matching on the debug subcommand
+        // itself would miss runs with more than one space before it.
+        let hook = CommandRunHook::new(
+            self,
+            &format!("{} *", name),
+            move |weechat, buffer, command| {
+                let mut args = command.split_whitespace();
+
+                if args.next() == Some(name.as_str())
+                    && args.next() == Some("debug")
+                {
+                    print_debug_info(weechat, &buffer, &version);
+                    ReturnCode::OkEat
+                } else {
+                    ReturnCode::Ok
+                }
+            },
+        );
+
+        let previous = DEBUG_COMMAND.with(|command| {
+            command.borrow_mut().replace(DebugCommand {
+                _hook: hook,
+                plugin_version: plugin_version.to_owned(),
+            })
+        });
+
+        if previous.is_none() {
+            let previous_hook: Arc<PanicHook> = Arc::from(panic::take_hook());
+            let chained_hook = Arc::clone(&previous_hook);
+
+            panic::set_hook(Box::new(move |info| {
+                record(format!("panic: {}", info));
+                chained_hook(info);
+            }));
+
+            PREVIOUS_PANIC_HOOK
+                .with(|hook| hook.borrow_mut().replace(previous_hook));
+        }
+    }
+
+    /// Record an error so it's shown by the debug command, only the last
+    /// few errors are kept.
+    /// * `error` - A description of the error.
+    pub fn record_error(&self, error: &str) {
+        record(error.to_owned());
+    }
+
+    /// Get the errors that were recorded last together with the time they
+    /// were recorded, the oldest error comes first.
+    pub fn recent_errors(&self) -> Vec<(DateTime<Utc>, String)> {
+        ERRORS
+            .lock()
+            .map(|errors| errors.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,443 @@
+//! Run futures on the WeeChat main loop.
+//!
+//! `Weechat::spawn` runs a future on the main thread of WeeChat, so futures
+//! don't need to be `Send` and can use the API of the plugin freely. A future
+//! is polled when it is woken up, the wakeups go through a file descriptor
+//! that is watched with a fd hook, so futures can be woken up from other
+//! threads as well.
+//!
+//! ```ignore
+//! let task = weechat.spawn(async move {
+//!     let reply = client.request("status").await;
+//!     buffer.print(&reply);
+//! });
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures::task::{waker, ArcWake};
+
+use crate::{FdHook, FdHookMode, Weechat};
+
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+thread_local! {
+    static EXECUTOR: RefCell<Option<Rc<Executor>>> =
+        const { RefCell::new(None) };
+}
+
+struct Executor {
+    tasks: RefCell<HashMap<u64, LocalFuture>>,
+    /// Tasks that were cancelled while they were polled, they aren't put
+    /// back into the map once the poll returns.
+    cancelled: RefCell<HashSet<u64>>,
+    next_id: Cell<u64>,
+    queue: Arc<WakeQueue>,
+    _hook: Option<FdHook<ExecutorWakeup>>,
+}
+
+/// The tasks that were woken up, shared with the wakers.
+struct WakeQueue {
+    woken: Mutex<Vec<u64>>,
+    writer: UnixStream,
+}
+
+/// The reading end of the socket that wakes up the main thread when a task
+/// is woken up.
+struct ExecutorWakeup {
+    reader: UnixStream,
+}
+
+struct TaskWaker {
+    id: u64,
+    queue: Arc<WakeQueue>,
+}
+
+struct TaskState<T> {
+    output: Option<T>,
+    finished: bool,
+    waker: Option<Waker>,
+}
+
+/// A handle to a future that was spawned with `Weechat::spawn`.
+///
+/// The task can be awaited to get the output of the future. Dropping the
+/// handle detaches the task, it keeps running until it's finished or the
+/// plugin is unloaded.
+pub struct Task<T> {
+    id: u64,
+    state: Rc<RefCell<TaskState<T>>>,
+}
+
+impl AsRawFd for ExecutorWakeup {
+    fn as_raw_fd(&self) -> RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl WakeQueue {
+    fn wake(&self, id: u64) {
+        self.woken.lock().unwrap().push(id);
+
+        // If the socket is full a wakeup is already pending, so the error can
+        // be ignored.
+        let _ = (&self.writer).write(&[0]);
+    }
+}
+
+impl ArcWake for TaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.queue.wake(arc_self.id);
+    }
+}
+
+impl Executor {
+    fn new(weechat: &Weechat) -> std::io::Result<Executor> {
+        fn wakeup_cb(_weechat: &Weechat, wakeup: &mut ExecutorWakeup) {
+            let mut buf = [0; 64];
+            while let Ok(n) = wakeup.reader.read(&mut buf) {
+                if n < buf.len() {
+                    break;
+                }
+            }
+
+            if let Some(executor) = EXECUTOR.with(|e| e.borrow().clone()) {
+                executor.run();
+            }
+        }
+
+        let (reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+
+        let hook = FdHook::new(
+            weechat,
+            ExecutorWakeup { reader },
+            FdHookMode::Read,
+            wakeup_cb,
+        );
+
+        Ok(Executor::with_wakeup(writer, Some(hook)))
+    }
+
+    /// Create an executor that signals wakeups through the given socket.
+    /// * `writer` - The writing end of the wakeup socket.
+    /// * `hook` - The fd hook that watches the reading end of the socket.
+    fn with_wakeup(
+        writer: UnixStream,
+        hook: Option<FdHook<ExecutorWakeup>>,
+    ) -> Executor {
+        Executor {
+            tasks: RefCell::new(HashMap::new()),
+            cancelled: RefCell::new(HashSet::new()),
+            next_id: Cell::new(0),
+            queue: Arc::new(WakeQueue {
+                woken: Mutex::new(Vec::new()),
+                writer,
+            }),
+            _hook: hook,
+        }
+    }
+
+    fn spawn<F>(&self, future: F) -> Task<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let state = Rc::new(RefCell::new(TaskState {
+            output: None,
+            finished: false,
+            waker: None,
+        }));
+        let task_state = state.clone();
+
+        let future = Box::pin(async move {
+            let output = future.await;
+            let mut state = task_state.borrow_mut();
+
+            state.output = Some(output);
+            state.finished = true;
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        self.tasks.borrow_mut().insert(id, future);
+        self.queue.wake(id);
+
+        Task { id, state }
+    }
+
+    /// Drop the future of a task.
+    ///
+    /// A task that is currently polled isn't in the map, it's marked as
+    /// cancelled and dropped once the poll returns.
+    fn cancel(&self, id: u64, finished: bool) {
+        let future = self.tasks.borrow_mut().remove(&id);
+
+        if future.is_none() && !finished {
+            self.cancelled.borrow_mut().insert(id);
+        }
+
+        drop(future);
+    }
+
+    /// Poll the tasks that were woken up.
+    ///
+    /// Tasks that wake up while they are polled are polled the next time the
+    /// main loop runs, so a busy task doesn't block WeeChat.
+    fn run(&self) {
+        let woken = std::mem::take(&mut *self.queue.woken.lock().unwrap());
+
+        for id in woken {
+            // The task is taken out of the map while it's polled so it can
+            // spawn other tasks.
+            let mut future = match self.tasks.borrow_mut().remove(&id) {
+                Some(future) => future,
+                None => continue,
+            };
+
+            let waker = waker(Arc::new(TaskWaker {
+                id,
+                queue: self.queue.clone(),
+            }));
+            let mut context = Context::from_waker(&waker);
+
+            let poll = future.as_mut().poll(&mut context);
+            let cancelled = self.cancelled.borrow_mut().remove(&id);
+
+            if poll.is_pending() && !cancelled {
+                self.tasks.borrow_mut().insert(id, future);
+            }
+        }
+    }
+}
+
+impl<T> Task<T> {
+    /// Check if the future of the task has completed.
+    pub fn is_finished(&self) -> bool {
+        self.state.borrow().finished
+    }
+
+    /// Cancel the task, the future is dropped without being polled again.
+    ///
+    /// A task may cancel itself, its future is dropped once the current poll
+    /// returns.
+    pub fn cancel(self) {
+        let executor = EXECUTOR.with(|e| e.borrow().clone());
+
+        if let Some(executor) = executor {
+            executor.cancel(self.id, self.is_finished());
+        }
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.borrow_mut();
+
+        match state.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Drop the executor together with the tasks that are still running.
+pub(crate) fn shutdown() {
+    let executor = EXECUTOR.with(|e| e.borrow_mut().take());
+    drop(executor);
+}
+
+impl Weechat {
+    /// Run a future on the WeeChat main loop.
+    ///
+    /// The future is first polled the next time the main loop runs, it
+    /// doesn't need to be `Send` since it is only ever polled on the main
+    /// thread.
+    ///
+    /// Returns a handle that can be awaited to get the output of the future.
+    /// * `future` - The future that should be run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the socket waking up the main loop can't be created.
+    pub fn spawn<F>(&self, future: F) -> Task<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let executor = EXECUTOR.with(|e| {
+            e.borrow_mut()
+                .get_or_insert_with(|| {
+                    Rc::new(
+                        Executor::new(self)
+                            .expect("can't create the executor wakeup socket"),
+                    )
+                })
+                .clone()
+        });
+
+        executor.spawn(future)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Install an executor without a fd hook, the tests run the woken tasks
+    /// themselves.
+    fn executor() -> Rc<Executor> {
+        let (_reader, writer) = UnixStream::pair().unwrap();
+        writer.set_nonblocking(true).unwrap();
+
+        let executor = Rc::new(Executor::with_wakeup(writer, None));
+        EXECUTOR.with(|e| *e.borrow_mut() = Some(executor.clone()));
+        executor
+    }
+
+    /// A future that is pending once and wakes itself up.
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    /// A value that is set by the test, awaiting it waits until it's set.
+    #[derive(Clone, Default)]
+    struct Slot(Rc<RefCell<(Option<u32>, Option<Waker>)>>);
+
+    impl Slot {
+        fn set(&self, value: u32) {
+            let mut slot = self.0.borrow_mut();
+            slot.0 = Some(value);
+            if let Some(waker) = slot.1.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    impl Future for Slot {
+        type Output = u32;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+            let mut slot = self.0.borrow_mut();
+
+            match slot.0.take() {
+                Some(value) => Poll::Ready(value),
+                None => {
+                    slot.1 = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spawned_task_runs_when_woken() {
+        let executor = executor();
+        let task = executor.spawn(async { 1 + 1 });
+
+        assert!(!task.is_finished());
+        executor.run();
+        assert!(task.is_finished());
+        assert!(executor.tasks.borrow().is_empty());
+    }
+
+    #[test]
+    fn task_output_can_be_awaited() {
+        let executor = executor();
+        let slot = Slot::default();
+
+        let first = executor.spawn(slot.clone());
+        let second = executor.spawn(async move { first.await * 2 });
+
+        executor.run();
+        assert!(!second.is_finished());
+
+        slot.set(21);
+        executor.run();
+        executor.run();
+        assert!(second.is_finished());
+        assert_eq!(second.state.borrow_mut().output.take(), Some(42));
+    }
+
+    #[test]
+    fn cancelled_task_is_not_polled() {
+        let executor = executor();
+        let polled = Rc::new(Cell::new(false));
+        let task_polled = polled.clone();
+
+        let task = executor.spawn(async move { task_polled.set(true) });
+        task.cancel();
+        executor.run();
+
+        assert!(!polled.get());
+        assert!(executor.tasks.borrow().is_empty());
+    }
+
+    #[test]
+    fn task_can_cancel_itself() {
+        let executor = executor();
+        let polls = Rc::new(Cell::new(0));
+        let handle: Rc<RefCell<Option<Task<()>>>> = Rc::default();
+
+        let task_polls = polls.clone();
+        let task_handle = handle.clone();
+        let task = executor.spawn(async move {
+            task_polls.set(task_polls.get() + 1);
+            if let Some(task) = task_handle.borrow_mut().take() {
+                task.cancel();
+            }
+            YieldNow(false).await;
+            task_polls.set(task_polls.get() + 1);
+        });
+        *handle.borrow_mut() = Some(task);
+
+        executor.run();
+        executor.run();
+
+        assert_eq!(polls.get(), 1);
+        assert!(executor.tasks.borrow().is_empty());
+        assert!(executor.cancelled.borrow().is_empty());
+    }
+
+    #[test]
+    fn cancelling_a_finished_task_is_a_noop() {
+        let executor = executor();
+        let task = executor.spawn(async {});
+
+        executor.run();
+        task.cancel();
+
+        assert!(executor.cancelled.borrow().is_empty());
+    }
+}
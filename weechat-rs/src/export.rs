@@ -0,0 +1,301 @@
+//! Export the state of a plugin so it can be inspected from WeeChat.
+//!
+//! A plugin implements `StateExport` for its state and registers it with
+//! `Weechat::hook_state_export`. The state, the buffers of the plugin and its
+//! hooks are then available as the infolist `<plugin>_state`, e.g. for
+//! scripts, and as JSON with the info `<plugin>_state`:
+//!
+//! ```text
+//! /eval -n ${info:myplugin_state}
+//! ```
+
+use std::fmt::Write;
+use std::rc::Rc;
+
+use crate::{
+    Buffer, HookKind, InfoHook, InfolistBuilder, InfolistHook, Weechat,
+};
+
+/// State of a plugin that can be exported for introspection.
+///
+/// Both methods return a list of key/value pairs, the keys `kind` and `name`
+/// are reserved for the items of the infolist.
+pub trait StateExport {
+    /// Summarize the state of the plugin, e.g. the number of connected
+    /// servers.
+    fn summary(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// Summarize the data the plugin keeps for one of its buffers.
+    /// * `buffer` - A buffer that was created by the plugin.
+    fn buffer_summary(&self, _buffer: &Buffer) -> Vec<(String, String)> {
+        Vec::new()
+    }
+}
+
+/// The hooks exporting the state of a plugin, the state stops being exported
+/// when the object is dropped.
+pub struct StateExportHooks {
+    _infolist: InfolistHook,
+    _info: InfoHook,
+}
+
+/// The kinds of hooks that are listed in the export, together with the field
+/// of the `hook` infolist that names the hook.
+const HOOK_NAMES: &[(HookKind, Option<&str>)] = &[
+    (HookKind::Command, Some("command")),
+    (HookKind::CommandRun, Some("command")),
+    (HookKind::Timer, None),
+    (HookKind::Fd, None),
+    (HookKind::Process, Some("command")),
+    (HookKind::Connect, Some("address")),
+    (HookKind::Line, None),
+    (HookKind::Print, Some("message")),
+    (HookKind::Signal, Some("signal")),
+    (HookKind::Hsignal, Some("signal")),
+    (HookKind::Config, Some("option")),
+    (HookKind::Completion, Some("completion_item")),
+    (HookKind::Modifier, Some("modifier")),
+    (HookKind::Info, Some("info_name")),
+    (HookKind::InfoHashtable, Some("info_name")),
+    (HookKind::Infolist, Some("infolist_name")),
+    (HookKind::Hdata, Some("hdata_name")),
+    (HookKind::Focus, Some("area")),
+];
+
+struct Snapshot {
+    plugin: String,
+    summary: Vec<(String, String)>,
+    buffers: Vec<(String, Vec<(String, String)>)>,
+    hooks: Vec<(&'static str, String)>,
+}
+
+impl Snapshot {
+    fn new(weechat: &Weechat, state: &dyn StateExport) -> Snapshot {
+        let plugin = weechat.plugin_name().into_owned();
+
+        let buffers = weechat
+            .plugin_buffers()
+            .iter()
+            .map(|buffer| {
+                (
+                    buffer.get_full_name().into_owned(),
+                    state.buffer_summary(buffer),
+                )
+            })
+            .collect();
+
+        Snapshot {
+            hooks: plugin_hooks(weechat, &plugin),
+            plugin,
+            summary: state.summary(),
+            buffers,
+        }
+    }
+
+    fn to_infolist(&self, infolist: &InfolistBuilder) {
+        let add_item = |kind: &str, name: &str, fields: &[(String, String)]| {
+            if let Some(item) = infolist.new_item() {
+                item.string("kind", kind).string("name", name);
+
+                for (key, value) in fields {
+                    item.string(key, value);
+                }
+            }
+        };
+
+        add_item("plugin", &self.plugin, &self.summary);
+
+        for (name, summary) in &self.buffers {
+            add_item("buffer", name, summary);
+        }
+
+        for (kind, name) in &self.hooks {
+            if let Some(item) = infolist.new_item() {
+                item.string("kind", "hook")
+                    .string("name", name)
+                    .string("hook_type", kind);
+            }
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let mut json = String::new();
+
+        let _ = write!(
+            json,
+            "{{\"plugin\":{},\"state\":{},\"buffers\":[",
+            json_string(&self.plugin),
+            json_object(&self.summary)
+        );
+
+        for (i, (name, summary)) in self.buffers.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"name\":{},\"state\":{}}}",
+                json_string(name),
+                json_object(summary)
+            );
+        }
+
+        json.push_str("],\"hooks\":[");
+
+        for (i, (kind, name)) in self.hooks.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"type\":{},\"name\":{}}}",
+                json_string(kind),
+                json_string(name)
+            );
+        }
+
+        json.push_str("]}");
+        json
+    }
+}
+
+/// List the hooks of a plugin as pairs of the hook type and the name of the
+/// hook.
+pub(crate) fn plugin_hooks(
+    weechat: &Weechat,
+    plugin: &str,
+) -> Vec<(&'static str, String)> {
+    let mut hooks = Vec::new();
+
+    for (kind, field) in HOOK_NAMES {
+        let infolist = match weechat.hooks(*kind) {
+            Some(infolist) => infolist,
+            None => continue,
+        };
+
+        while infolist.next() {
+            if infolist.get_string("plugin_name").as_deref() != Some(plugin) {
+                continue;
+            }
+
+            let name = field
+                .and_then(|field| infolist.get_string(field))
+                .map(|name| name.into_owned())
+                .unwrap_or_default();
+
+            hooks.push((kind.as_str(), name));
+        }
+    }
+
+    hooks
+}
+
+fn json_object(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| {
+            format!("{}:{}", json_string(key), json_string(value))
+        })
+        .collect();
+
+    format!("{{{}}}", fields.join(","))
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::with_capacity(string.len() + 2);
+    json.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+impl Weechat {
+    /// Export the state of the plugin as the infolist and the info
+    /// `<plugin>_state`.
+    ///
+    /// The export contains the summary of the state, the buffers of the
+    /// plugin with a summary of their data and the hooks of the plugin. The
+    /// info returns the same data as JSON.
+    /// * `state` - The state of the plugin that should be exported.
+    pub fn hook_state_export(
+        &self,
+        state: Rc<dyn StateExport>,
+    ) -> StateExportHooks {
+        let name = format!("{}_state", self.plugin_name());
+        let infolist_state = state.clone();
+
+        StateExportHooks {
+            _infolist: InfolistHook::new(
+                self,
+                &name,
+                "state, buffers and hooks of the plugin",
+                "",
+                "",
+                move |weechat, _pointer, _arguments, infolist| {
+                    Snapshot::new(weechat, infolist_state.as_ref())
+                        .to_infolist(infolist);
+                    true
+                },
+            ),
+            _info: InfoHook::new(
+                self,
+                &name,
+                "state, buffers and hooks of the plugin as JSON",
+                "",
+                move |weechat, _name, _arguments| {
+                    Some(Snapshot::new(weechat, state.as_ref()).to_json())
+                },
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_string_escapes_special_characters() {
+        assert_eq!(json_string("plain"), r#""plain""#);
+        assert_eq!(json_string(""), r#""""#);
+        assert_eq!(json_string(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(json_string("a\nb\rc\td"), r#""a\nb\rc\td""#);
+    }
+
+    #[test]
+    fn json_string_escapes_control_characters() {
+        assert_eq!(json_string("\x00\x1b\x1f"), r#""\u0000\u001b\u001f""#);
+        // DEL and non-ASCII characters don't need to be escaped.
+        assert_eq!(json_string("\x7fé→"), "\"\x7fé→\"");
+    }
+
+    #[test]
+    fn json_object_joins_fields() {
+        assert_eq!(json_object(&[]), "{}");
+
+        let fields = [
+            ("name".to_owned(), "irc.libera.#rust".to_owned()),
+            ("topic".to_owned(), "say \"hi\"\n".to_owned()),
+        ];
+        assert_eq!(
+            json_object(&fields),
+            r#"{"name":"irc.libera.#rust","topic":"say \"hi\"\n"}"#
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Focus hooks allow plugins to add information about the item under the
+//! mouse or cursor, this information can then be used by mouse and cursor
+//! key bindings.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::os::raw::c_void;
+use std::ptr;
+
+use weechat_sys::{t_gui_buffer, t_gui_window, t_hashtable, t_weechat_plugin};
+
+use crate::hashtable::hashtable_to_map;
+use crate::hooks::{sealed, Hook, HookCallback, HookSettings, ToggleHook};
+use crate::profiling::profile;
+use crate::{
+    Buffer, HashtableItemType, HookKind, Line, LossyCString, Weechat, Window,
+};
+
+/// A hook that provides focus information, the hook is removed when the
+/// object is dropped.
+///
+/// No information is added while the hook is disabled.
+pub struct FocusHook {
+    hook: Hook,
+    hook_data: Box<FocusHookData>,
+}
+
+type FocusCallback = dyn FnMut(&Weechat, &FocusInfo) -> HashMap<String, String>;
+
+struct FocusHookData {
+    callback: HookCallback<FocusCallback>,
+    name: &'static str,
+    buffer: Option<*mut t_gui_buffer>,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for FocusHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for FocusHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for FocusHook {}
+
+impl ToggleHook for FocusHook {}
+
+impl FocusHook {
+    /// Hook a focus area to add information about the item under the mouse
+    /// or cursor.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `area` - The area that should be hooked, `chat` for the chat area of
+    ///     buffers or the name of a bar item.
+    /// * `callback` - A closure that will be called with the current focus
+    ///     information, the returned key/value pairs are added to the focus
+    ///     information. Keys should not start with an underscore, those are
+    ///     reserved for WeeChat.
+    pub fn new<C>(weechat: &Weechat, area: &str, callback: C) -> FocusHook
+    where
+        C: FnMut(&Weechat, &FocusInfo) -> HashMap<String, String> + 'static,
+    {
+        weechat.new_focus_hook(
+            area,
+            None,
+            Box::new(callback),
+            std::any::type_name::<C>(),
+        )
+    }
+
+    /// Add information about the chat line under the mouse or cursor for a
+    /// buffer, e.g. a message id that was stored when the line was printed.
+    ///
+    /// The callback is only called if the focus is on the chat area of the
+    /// given buffer.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `buffer` - The buffer the focus information should be provided for.
+    /// * `callback` - A closure that will be called with the current focus
+    ///     information, the returned key/value pairs are added to the focus
+    ///     information.
+    pub fn for_buffer<C>(
+        weechat: &Weechat,
+        buffer: &Buffer,
+        callback: C,
+    ) -> FocusHook
+    where
+        C: FnMut(&Weechat, &FocusInfo) -> HashMap<String, String> + 'static,
+    {
+        weechat.new_focus_hook(
+            "chat",
+            Some(buffer.ptr),
+            Box::new(callback),
+            std::any::type_name::<C>(),
+        )
+    }
+}
+
+/// Parse a pointer that is stored as a hex string in a focus hashtable.
+pub(crate) fn parse_pointer(value: Option<&String>) -> Option<*mut c_void> {
+    let value = value?.trim_start_matches("0x");
+    let ptr = usize::from_str_radix(value, 16).ok()? as *mut c_void;
+
+    if ptr.is_null() {
+        None
+    } else {
+        Some(ptr)
+    }
+}
+
+/// The item under the mouse or cursor, passed to the callback of a
+/// `FocusHook`.
+pub struct FocusInfo {
+    /// The column of the focus on the screen.
+    pub x: i32,
+    /// The row of the focus on the screen.
+    pub y: i32,
+    /// The window under the focus.
+    pub window: Option<Window>,
+    /// The buffer under the focus.
+    pub buffer: Option<Buffer>,
+    /// The name of the bar under the focus.
+    pub bar_name: Option<String>,
+    /// The name of the bar item under the focus.
+    pub bar_item_name: Option<String>,
+    /// The line inside of the bar item under the focus.
+    pub bar_item_line: Option<i32>,
+    /// The column inside of the bar item under the focus.
+    pub bar_item_col: Option<i32>,
+    /// The chat line under the focus.
+    pub chat_line: Option<Line>,
+    /// The word of the chat area under the focus.
+    pub chat_word: Option<String>,
+    /// The key or mouse event that triggered the focus, e.g. `button1`.
+    pub key: Option<String>,
+    /// The raw focus information as it was provided by WeeChat, including
+    /// the information added by other focus hooks.
+    pub raw: HashMap<String, String>,
+}
+
+impl FocusInfo {
+    pub(crate) fn from_map(
+        weechat: &Weechat,
+        map: HashMap<String, String>,
+    ) -> FocusInfo {
+        let string =
+            |key: &str| map.get(key).filter(|value| !value.is_empty()).cloned();
+        let integer =
+            |key: &str| map.get(key).and_then(|value| value.parse().ok());
+
+        FocusInfo {
+            x: integer("_x").unwrap_or(-1),
+            y: integer("_y").unwrap_or(-1),
+            window: parse_pointer(map.get("_window")).map(|ptr| {
+                Window::from_ptr(weechat.ptr, ptr as *mut t_gui_window)
+            }),
+            buffer: parse_pointer(map.get("_buffer"))
+                .map(|ptr| Buffer::wrap(weechat.ptr, ptr as *mut t_gui_buffer)),
+            bar_name: string("_bar_name"),
+            bar_item_name: string("_bar_item_name"),
+            bar_item_line: integer("_bar_item_line"),
+            bar_item_col: integer("_bar_item_col"),
+            chat_line: parse_pointer(map.get("_chat_line")).map(|ptr| Line {
+                weechat_ptr: weechat.ptr,
+                ptr,
+            }),
+            chat_word: string("_chat_word"),
+            key: string("_key"),
+            raw: map,
+        }
+    }
+}
+
+impl Weechat {
+    /// Hook a focus area to add information about the item under the mouse
+    /// or cursor.
+    /// * `area` - The area that should be hooked, `chat` for the chat area of
+    ///     buffers or the name of a bar item.
+    /// * `callback` - A function that will be called with the current focus
+    ///     information, the returned key/value pairs are added to the focus
+    ///     information. Keys should not start with an underscore, those are
+    ///     reserved for WeeChat.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is
+    ///     unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `FocusHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_focus<T: Default + 'static>(
+        &self,
+        area: &str,
+        callback: fn(data: &T, info: &FocusInfo) -> HashMap<String, String>,
+        callback_data: Option<T>,
+    ) -> FocusHook {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_focus_hook(
+            area,
+            None,
+            Box::new(move |_, info| callback(&data, info)),
+            std::any::type_name::<T>(),
+        )
+    }
+
+    /// Add information about the chat line under the mouse or cursor for a
+    /// buffer, e.g. a message id that was stored when the line was printed.
+    ///
+    /// The callback is only called if the focus is on the chat area of the
+    /// given buffer.
+    /// * `buffer` - The buffer the focus information should be provided for.
+    /// * `callback` - A function that will be called with the current focus
+    ///     information, the returned key/value pairs are added to the focus
+    ///     information.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is
+    ///     unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `FocusHook::for_buffer` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_buffer_focus<T: Default + 'static>(
+        &self,
+        buffer: &Buffer,
+        callback: fn(data: &T, info: &FocusInfo) -> HashMap<String, String>,
+        callback_data: Option<T>,
+    ) -> FocusHook {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_focus_hook(
+            "chat",
+            Some(buffer.ptr),
+            Box::new(move |_, info| callback(&data, info)),
+            std::any::type_name::<T>(),
+        )
+    }
+
+    /// Get the chat line under the focus from the focus information that was
+    /// passed to a focus callback.
+    /// * `info` - The raw focus information, see `FocusInfo::raw`.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use the `chat_line` field of the `FocusInfo` passed to the callback instead"
+    )]
+    pub fn focus_chat_line(
+        &self,
+        info: &HashMap<String, String>,
+    ) -> Option<Line> {
+        parse_pointer(info.get("_chat_line")).map(|ptr| Line {
+            weechat_ptr: self.ptr,
+            ptr,
+        })
+    }
+
+    fn new_focus_hook(
+        &self,
+        area: &str,
+        buffer: Option<*mut t_gui_buffer>,
+        callback: Box<FocusCallback>,
+        name: &'static str,
+    ) -> FocusHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            info: *mut t_hashtable,
+        ) -> *mut t_hashtable {
+            let hook_data: &FocusHookData =
+                { &*(pointer as *const FocusHookData) };
+
+            if !hook_data.enabled.get() {
+                return info;
+            }
+
+            let name = hook_data.name;
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let focus =
+                FocusInfo::from_map(&weechat, hashtable_to_map(&weechat, info));
+
+            if let Some(buffer) = hook_data.buffer {
+                if focus.buffer.as_ref().map(|b| b.ptr) != Some(buffer) {
+                    return info;
+                }
+            }
+
+            let extra = hook_data.callback.call(
+                &weechat,
+                HookKind::Focus,
+                |callback| {
+                    profile(
+                        HookKind::Focus,
+                        || format!("{} ({:p})", name, pointer),
+                        || callback(&weechat, &focus),
+                    )
+                },
+            );
+
+            let extra = match extra {
+                Some(extra) if !extra.is_empty() => extra,
+                _ => return info,
+            };
+
+            // WeeChat merges the new hashtable into the focus information and
+            // frees it afterwards.
+            match weechat.new_hashtable(
+                extra.len().clamp(8, u16::MAX as usize) as u16,
+                HashtableItemType::String,
+                HashtableItemType::String,
+            ) {
+                Some(hashtable) => {
+                    for (key, value) in &extra {
+                        hashtable.set(key, value);
+                    }
+                    hashtable.into_raw()
+                }
+                None => info,
+            }
+        }
+
+        let data = Box::new(FocusHookData {
+            callback: HookCallback::new(callback),
+            name,
+            buffer,
+            enabled: Cell::new(true),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_focus = self.get().hook_focus.unwrap();
+
+        let area = LossyCString::new(area);
+
+        let hook_ptr = unsafe {
+            hook_focus(
+                self.ptr,
+                area.as_ptr(),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        FocusHook { hook, hook_data }
+    }
+}
@@ -0,0 +1,116 @@
+//! Efficient rendering of buffers with free content.
+//!
+//! A `FreeBufferView` keeps the lines of a buffer with free content as a
+//! model. The model can be changed freely, `refresh` then only prints the
+//! lines that changed since the last refresh, which keeps dashboards that are
+//! updated frequently cheap to redraw.
+//!
+//! ```ignore
+//! let mut view = FreeBufferView::new(buffer);
+//!
+//! view.set_lines(servers.iter().map(|s| s.status_line()).collect());
+//! view.refresh();
+//! ```
+
+use crate::Buffer;
+
+/// The lines of a buffer with free content, see the module documentation.
+pub struct FreeBufferView {
+    buffer: Buffer,
+    lines: Vec<String>,
+    drawn: Vec<String>,
+}
+
+impl FreeBufferView {
+    /// Create a view for a buffer, the buffer is turned into a buffer with
+    /// free content and cleared.
+    /// * `buffer` - The buffer the lines of the view are printed on.
+    pub fn new(buffer: Buffer) -> FreeBufferView {
+        buffer.set_type_free();
+        buffer.clear();
+
+        FreeBufferView {
+            buffer,
+            lines: Vec::new(),
+            drawn: Vec::new(),
+        }
+    }
+
+    /// Get the buffer of the view.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Get the lines of the model.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Replace all lines of the model.
+    /// * `lines` - The new lines, may contain color codes.
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        self.lines = lines;
+    }
+
+    /// Set a single line of the model, empty lines are added if the line is
+    /// past the end of the model.
+    /// * `y` - The line number, starting with 0.
+    /// * `line` - The new content of the line, may contain color codes.
+    pub fn set_line(&mut self, y: usize, line: impl Into<String>) {
+        if y >= self.lines.len() {
+            self.lines.resize(y + 1, String::new());
+        }
+
+        self.lines[y] = line.into();
+    }
+
+    /// Append a line to the model.
+    /// * `line` - The content of the line, may contain color codes.
+    pub fn push(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+    }
+
+    /// Remove the lines past the given number of lines from the model.
+    /// * `len` - The number of lines that are kept.
+    pub fn truncate(&mut self, len: usize) {
+        self.lines.truncate(len);
+    }
+
+    /// Remove all lines from the model.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Print the lines that changed since the last refresh.
+    ///
+    /// Returns the number of lines that were printed or removed.
+    pub fn refresh(&mut self) -> usize {
+        let mut changed = 0;
+
+        for (y, line) in self.lines.iter().enumerate() {
+            if self.drawn.get(y) != Some(line) {
+                self.buffer.print_y(y as i32, line);
+                changed += 1;
+            }
+        }
+
+        // Only the last line of a buffer is removed by printing an empty
+        // message, so the lines are removed starting from the end.
+        for y in (self.lines.len()..self.drawn.len()).rev() {
+            self.buffer.print_y(y as i32, "");
+            changed += 1;
+        }
+
+        self.drawn.clone_from(&self.lines);
+
+        changed
+    }
+
+    /// Clear the buffer and print all lines of the model, this needs to be
+    /// called if the buffer was changed outside of the view.
+    pub fn redraw(&mut self) {
+        self.buffer.clear();
+        self.drawn.clear();
+        self.refresh();
+    }
+}
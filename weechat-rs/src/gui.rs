@@ -0,0 +1,157 @@
+//! A module providing a typed wrapper for pointers to weechat GUI objects.
+
+use std::os::raw::c_void;
+use std::ptr;
+
+use weechat_sys::{
+    t_gui_bar, t_gui_bar_item, t_gui_buffer, t_gui_nick, t_gui_nick_group,
+    t_gui_window,
+};
+
+use crate::bar::{Bar, LightBarItem};
+use crate::hdata::HDataPointer;
+use crate::window::Window;
+use crate::{Buffer, LossyCString, Nick, Weechat};
+
+/// A weechat GUI object.
+///
+/// Weechat APIs often hand out untyped pointers (signals, infolists, hdata).
+/// A `GuiObject` can be created from such a pointer, the type of the object
+/// is verified at runtime by looking the pointer up in the weechat lists of
+/// the respective type.
+pub enum GuiObject {
+    /// A buffer
+    Buffer(Buffer),
+    /// A window
+    Window(Window),
+    /// A bar
+    Bar(Bar),
+    /// A bar item
+    BarItem(LightBarItem),
+    /// A nick in the nicklist of a buffer
+    Nick(Nick),
+}
+
+impl Weechat {
+    /// Check if a pointer is part of a weechat list.
+    /// * `hdata_name` - The name of the hdata describing the list elements.
+    /// * `list` - The name of the list.
+    /// * `pointer` - The pointer that should be searched in the list.
+    pub(crate) fn hdata_check_pointer(
+        &self,
+        hdata_name: &str,
+        list: &str,
+        pointer: *mut c_void,
+    ) -> bool {
+        let hdata_get = self.get().hdata_get.unwrap();
+        let hdata_get_list = self.get().hdata_get_list.unwrap();
+        let hdata_check_pointer = self.get().hdata_check_pointer.unwrap();
+
+        let hdata_name = LossyCString::new(hdata_name);
+        let list = LossyCString::new(list);
+
+        unsafe {
+            let hdata = hdata_get(self.ptr, hdata_name.as_ptr());
+
+            if hdata.is_null() {
+                return false;
+            }
+
+            let list = hdata_get_list(hdata, list.as_ptr());
+
+            !list.is_null() && hdata_check_pointer(hdata, list, pointer) == 1
+        }
+    }
+
+    fn search_nick(&self, pointer: *mut c_void) -> Option<Nick> {
+        let hdata_get = self.get().hdata_get.unwrap();
+        let hdata_get_list = self.get().hdata_get_list.unwrap();
+        let hdata_move = self.get().hdata_move.unwrap();
+        let get_next_item = self.get().nicklist_get_next_item.unwrap();
+
+        let hdata_name = LossyCString::new("buffer");
+        let list = LossyCString::new("gui_buffers");
+
+        unsafe {
+            let hdata = hdata_get(self.ptr, hdata_name.as_ptr());
+
+            if hdata.is_null() {
+                return None;
+            }
+
+            let mut buffer = hdata_get_list(hdata, list.as_ptr());
+
+            while !buffer.is_null() {
+                let mut group: *mut t_gui_nick_group = ptr::null_mut();
+                let mut nick: *mut t_gui_nick = ptr::null_mut();
+
+                loop {
+                    get_next_item(
+                        buffer as *mut t_gui_buffer,
+                        &mut group,
+                        &mut nick,
+                    );
+
+                    if group.is_null() && nick.is_null() {
+                        break;
+                    }
+
+                    if !nick.is_null() && nick as *mut c_void == pointer {
+                        return Some(Nick::from_ptr(
+                            nick,
+                            buffer as *mut t_gui_buffer,
+                            self.ptr,
+                        ));
+                    }
+                }
+
+                buffer = hdata_move(hdata, buffer, 1);
+            }
+        }
+
+        None
+    }
+
+    /// Create a GUI object from a pointer.
+    ///
+    /// Returns None if the pointer doesn't point to a known GUI object.
+    pub fn gui_object(&self, pointer: *mut c_void) -> Option<GuiObject> {
+        if pointer.is_null() {
+            return None;
+        }
+
+        if self.hdata_check_pointer("buffer", "gui_buffers", pointer) {
+            Some(GuiObject::Buffer(Buffer::wrap(
+                self.ptr,
+                pointer as *mut t_gui_buffer,
+            )))
+        } else if self.hdata_check_pointer("window", "gui_windows", pointer) {
+            Some(GuiObject::Window(Window::from_ptr(
+                self.ptr,
+                pointer as *mut t_gui_window,
+            )))
+        } else if self.hdata_check_pointer("bar", "gui_bars", pointer) {
+            Some(GuiObject::Bar(Bar {
+                ptr: pointer as *mut t_gui_bar,
+                weechat_ptr: self.ptr,
+            }))
+        } else if self.hdata_check_pointer("bar_item", "gui_bar_items", pointer)
+        {
+            Some(GuiObject::BarItem(LightBarItem {
+                ptr: pointer as *mut t_gui_bar_item,
+                weechat_ptr: self.ptr,
+            }))
+        } else {
+            self.search_nick(pointer).map(GuiObject::Nick)
+        }
+    }
+}
+
+impl HDataPointer {
+    /// Convert the pointer into a GUI object.
+    ///
+    /// Returns None if the pointer doesn't point to a known GUI object.
+    pub fn gui_object(&self) -> Option<GuiObject> {
+        Weechat::from_ptr(self.weechat).gui_object(self.ptr)
+    }
+}
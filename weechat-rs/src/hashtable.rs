@@ -1,9 +1,15 @@
 //! Hashtables allow storing key value pairs.
 
 use crate::{LossyCString, Weechat};
-use std::ffi::CString;
+use libc::c_char;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::os::raw::c_void;
 use weechat_sys::{t_hashtable, t_weechat_plugin};
 
+/// A hashtable of WeeChat, the hashtable is freed when the object is
+/// dropped.
 pub struct Hashtable {
     weechat_ptr: *mut t_weechat_plugin,
     pub(crate) ptr: *mut t_hashtable,
@@ -66,7 +72,69 @@ impl Weechat {
     }
 }
 
+impl Weechat {
+    /// Get some info from Weechat or a plugin in the form of a hashtable.
+    /// * `info_name` - The name of the info.
+    /// * `arguments` - The arguments for the info, the valid keys depend on
+    ///     the info.
+    ///
+    /// Returns None if the info doesn't exist.
+    pub fn info_get_hashtable(
+        &self,
+        info_name: &str,
+        arguments: &HashMap<&str, &str>,
+    ) -> Option<HashMap<String, String>> {
+        let info_get_hashtable = self.get().info_get_hashtable.unwrap();
+
+        let hashtable = self.new_hashtable(
+            arguments.len().clamp(8, u16::MAX as usize) as u16,
+            HashtableItemType::String,
+            HashtableItemType::String,
+        )?;
+
+        for (key, value) in arguments {
+            hashtable.set(key, value);
+        }
+
+        let info_name = LossyCString::new(info_name);
+
+        unsafe {
+            let info =
+                info_get_hashtable(self.ptr, info_name.as_ptr(), hashtable.ptr);
+
+            if info.is_null() {
+                None
+            } else {
+                let info = Hashtable {
+                    weechat_ptr: self.ptr,
+                    ptr: info,
+                };
+                Some(hashtable_to_map(self, info.ptr))
+            }
+        }
+    }
+}
+
+impl Drop for Hashtable {
+    fn drop(&mut self) {
+        let hashtable_free = Weechat::from_ptr(self.weechat_ptr)
+            .get()
+            .hashtable_free
+            .unwrap();
+
+        unsafe { hashtable_free(self.ptr) };
+    }
+}
+
 impl Hashtable {
+    /// Give up the ownership of the hashtable, e.g. when it's returned to
+    /// WeeChat which frees it afterwards.
+    pub(crate) fn into_raw(self) -> *mut t_hashtable {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
+    }
+
     /// Add or update an item in the hashtable.
     pub fn set(&self, key: &str, value: &str) {
         let weechat_hashtable_set = Weechat::from_ptr(self.weechat_ptr)
@@ -86,3 +154,69 @@ impl Hashtable {
         }
     }
 }
+
+/// Create a weechat hashtable with string keys and values out of a map.
+pub(crate) fn map_to_hashtable<K, V>(
+    weechat: &Weechat,
+    map: &HashMap<K, V>,
+) -> Option<Hashtable>
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let hashtable = weechat.new_hashtable(
+        map.len().clamp(8, u16::MAX as usize) as u16,
+        HashtableItemType::String,
+        HashtableItemType::String,
+    )?;
+
+    for (key, value) in map {
+        hashtable.set(key.as_ref(), value.as_ref());
+    }
+
+    Some(hashtable)
+}
+
+/// Convert a weechat hashtable into a map, all the keys and values are
+/// converted to strings.
+pub(crate) fn hashtable_to_map(
+    weechat: &Weechat,
+    hashtable: *mut t_hashtable,
+) -> HashMap<String, String> {
+    unsafe extern "C" fn c_map_cb(
+        data: *mut c_void,
+        _hashtable: *mut t_hashtable,
+        key: *const c_char,
+        value: *const c_char,
+    ) {
+        let map: &mut HashMap<String, String> =
+            { &mut *(data as *mut HashMap<String, String>) };
+
+        let key = CStr::from_ptr(key).to_string_lossy().into_owned();
+        let value = if value.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(value).to_string_lossy().into_owned()
+        };
+
+        map.insert(key, value);
+    }
+
+    let mut map = HashMap::new();
+
+    if hashtable.is_null() {
+        return map;
+    }
+
+    let hashtable_map_string = weechat.get().hashtable_map_string.unwrap();
+
+    unsafe {
+        hashtable_map_string(
+            hashtable,
+            Some(c_map_cb),
+            &mut map as *mut _ as *mut c_void,
+        );
+    }
+
+    map
+}
@@ -3,8 +3,10 @@
 use crate::{Buffer, LossyCString, Weechat};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::ffi::{c_void, CStr};
+use std::ptr;
 use weechat_sys::{t_hdata, t_weechat_plugin};
 
 /// The HData object represents a table of variables associated with an object.
@@ -12,9 +14,9 @@ use weechat_sys::{t_hdata, t_weechat_plugin};
 /// An HData object can be created from any Weechat type that implements [`HasHData`] using the
 /// [`get_hdata`](HasHData::get_hdata) function and the name of the hdata table you want to access.
 pub struct HData {
-    weechat_ptr: *mut t_weechat_plugin,
-    object: *mut c_void,
-    ptr: *mut t_hdata,
+    pub(crate) weechat_ptr: *mut t_weechat_plugin,
+    pub(crate) object: *mut c_void,
+    pub(crate) ptr: *mut t_hdata,
 }
 
 impl HData {
@@ -107,6 +109,49 @@ impl HasHData for Buffer {
     }
 }
 
+impl Buffer {
+    /// Create a buffer from a pointer found in hdata, e.g. the `buffer`
+    /// variable of a window.
+    ///
+    /// Returns None if the pointer doesn't point to an existing buffer, the
+    /// pointer is checked against the buffer list of the `buffer` hdata.
+    /// * `pointer` - The pointer that should be converted.
+    pub fn from_hdata_pointer(pointer: &HDataPointer) -> Option<Buffer> {
+        let weechat = Weechat::from_ptr(pointer.weechat);
+        let hdata_get = weechat.get().hdata_get.unwrap();
+        let hdata_check_pointer = weechat.get().hdata_check_pointer.unwrap();
+
+        if pointer.ptr.is_null() {
+            return None;
+        }
+
+        let name = LossyCString::new("buffer");
+
+        unsafe {
+            let hdata = hdata_get(pointer.weechat, name.as_ptr());
+
+            // Without a list WeeChat checks the pointer against all the
+            // lists of the hdata.
+            if hdata.is_null()
+                || hdata_check_pointer(hdata, ptr::null_mut(), pointer.ptr) == 0
+            {
+                None
+            } else {
+                Some(Buffer::wrap(pointer.weechat, pointer.ptr as *mut _))
+            }
+        }
+    }
+
+    /// Get the pointer of the buffer, e.g. to compare it with pointers found
+    /// in hdata or to traverse the `buffer` hdata starting at this buffer.
+    pub fn to_hdata_pointer(&self) -> HDataPointer {
+        HDataPointer {
+            ptr: self.ptr as *mut c_void,
+            weechat: self.weechat,
+        }
+    }
+}
+
 /// A trait for types of hdata values.
 pub trait HDataType: Sized {
     /// Retrieve the value of a hdata variable by name.
@@ -321,10 +366,42 @@ impl HDataType for DateTime<Utc> {
     }
 }
 
+impl HDataType for HashMap<String, String> {
+    fn hdata_value(hdata: &HData, name: &str) -> Option<Self> {
+        let weechat = Weechat::from_ptr(hdata.weechat_ptr);
+        let hdata_hashtable = weechat.get().hdata_hashtable.unwrap();
+        let hdata_get_var_type = weechat.get().hdata_get_var_type.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe {
+            if hdata_get_var_type(hdata.ptr, name.as_ptr())
+                != weechat_sys::WEECHAT_HDATA_HASHTABLE as i32
+            {
+                return None;
+            }
+
+            let hashtable =
+                hdata_hashtable(hdata.ptr, hdata.object, name.as_ptr());
+
+            if hashtable.is_null() {
+                None
+            } else {
+                Some(crate::hashtable::hashtable_to_map(&weechat, hashtable))
+            }
+        }
+    }
+
+    fn hdata_set_value(_hdata: &HData, _name: &str, _value: Self) -> usize {
+        // Weechat doesn't support updating hashtable variables.
+        0
+    }
+}
+
 /// An opaque wrapper for a pointer stored in hdata
 pub struct HDataPointer {
-    ptr: *mut c_void,
-    weechat: *mut t_weechat_plugin,
+    pub(crate) ptr: *mut c_void,
+    pub(crate) weechat: *mut t_weechat_plugin,
 }
 
 impl HDataPointer {
@@ -0,0 +1,94 @@
+//! Access to the input history of buffers.
+
+use crate::hdata::{HDataPointer, HasHData};
+use crate::{Buffer, ModifierHook};
+
+/// An iterator over the input history of a buffer, the most recent entry
+/// comes first.
+pub struct InputHistory {
+    current: Option<HDataPointer>,
+}
+
+impl Iterator for InputHistory {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let entry = self.current.take()?;
+            if entry.ptr.is_null() {
+                return None;
+            }
+
+            let hdata = entry.get_hdata("history")?;
+            self.current = entry.advance(&hdata, 1);
+
+            if let Some(text) = hdata.get_var::<String>("text") {
+                return Some(text);
+            }
+        }
+    }
+}
+
+impl Buffer {
+    /// Get the input history of the buffer, the entries the user can recall
+    /// with the up arrow.
+    pub fn input_history(&self) -> InputHistory {
+        let current = self
+            .get_hdata("buffer")
+            .and_then(|hdata| hdata.get_var::<HDataPointer>("history"));
+
+        InputHistory { current }
+    }
+
+    /// Add a text to the input history of the buffer without sending it.
+    ///
+    /// Plugins that send input for the user, e.g. from a custom input bar,
+    /// can use this to keep the history consistent with what was sent. The
+    /// text is added to the global history as well. The current content of
+    /// the input line is left unchanged.
+    ///
+    /// The text is added by submitting it from the input line, other plugins
+    /// and scripts see this: the `input_text_changed` signal is sent when the
+    /// text is put into the input line and again when the previous content
+    /// is restored. The submitted text is dropped before any other
+    /// `input_text_for_buffer` modifier or command hook sees it.
+    /// * `text` - The text that should be added, e.g. a message or a
+    ///     command.
+    pub fn add_to_history(&self, text: &str) {
+        // The command that submits the input line, it's sent through the same
+        // modifier as the submitted text.
+        const SUBMIT: &str = "/input return";
+
+        if text.is_empty() {
+            return;
+        }
+
+        let weechat = self.get_weechat();
+        let input = self.input().into_owned();
+        let input_pos = self.get_integer("input_pos");
+
+        // WeeChat adds the input to the history when it is submitted, the
+        // submitted input is dropped before it is sent or executed. The hook
+        // runs before the modifiers of other plugins, so the input is dropped
+        // even if another modifier would change it.
+        let mut submitting = true;
+        let _drop_hook = ModifierHook::new(
+            &weechat,
+            "100000|input_text_for_buffer",
+            move |_, _, _, string| {
+                if submitting && string == SUBMIT {
+                    submitting = false;
+                    None
+                } else {
+                    Some(String::new())
+                }
+            },
+        );
+
+        self.set("input", text);
+        self.run_command(SUBMIT);
+
+        self.set("input", &input);
+        self.set("input_pos", &input_pos.to_string());
+    }
+}
@@ -0,0 +1,131 @@
+//! A container keeping many hooks alive as a group.
+//!
+//! Hooks are removed when their handle is dropped, so plugins need to keep
+//! every handle around. A `HookRegistry` owns hooks of any type, they can be
+//! added anonymously or under a name that allows removing them later:
+//!
+//! ```ignore
+//! let mut hooks = HookRegistry::new();
+//!
+//! hooks.add(SignalHook::new(&weechat, "buffer_switch", on_switch));
+//! hooks.insert("poll", TimerHook::new(&weechat, interval, 0, 0, on_poll));
+//!
+//! // Stop polling, the timer is unhooked.
+//! hooks.remove("poll");
+//! ```
+
+use std::any::Any;
+
+/// Owns hooks of any type, the hooks are unhooked when the registry is
+/// dropped or cleared.
+#[derive(Default)]
+pub struct HookRegistry {
+    hooks: Vec<(Option<String>, Box<dyn Any>)>,
+}
+
+impl HookRegistry {
+    /// Create an empty registry.
+    pub fn new() -> HookRegistry {
+        HookRegistry { hooks: Vec::new() }
+    }
+
+    /// Add a hook that doesn't need to be removed by name.
+    /// * `hook` - The hook, e.g. a `SignalHook` or a `CommandHook`.
+    pub fn add<H: 'static>(&mut self, hook: H) {
+        self.hooks.push((None, Box::new(hook)));
+    }
+
+    /// Add a hook under a name, a hook that was added under the same name
+    /// before is unhooked.
+    /// * `name` - The name the hook can be removed with.
+    /// * `hook` - The hook, e.g. a `SignalHook` or a `CommandHook`.
+    pub fn insert<H: 'static>(&mut self, name: &str, hook: H) {
+        self.remove(name);
+        self.hooks.push((Some(name.to_owned()), Box::new(hook)));
+    }
+
+    /// Remove and unhook the hook with the given name.
+    ///
+    /// Returns false if no hook with this name exists.
+    /// * `name` - The name the hook was inserted with.
+    pub fn remove(&mut self, name: &str) -> bool {
+        match self.position(name) {
+            Some(index) => {
+                self.hooks.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the hook with the given name from the registry without
+    /// unhooking it.
+    ///
+    /// Returns None if no hook with this name exists or if the hook has a
+    /// different type, the hook stays in the registry in the latter case.
+    /// * `name` - The name the hook was inserted with.
+    pub fn take<H: 'static>(&mut self, name: &str) -> Option<H> {
+        let index = self.position(name)?;
+
+        if !self.hooks[index].1.is::<H>() {
+            return None;
+        }
+
+        let (_, hook) = self.hooks.remove(index);
+        hook.downcast().ok().map(|hook| *hook)
+    }
+
+    /// Get the hook with the given name.
+    ///
+    /// Returns None if no hook with this name exists or if the hook has a
+    /// different type.
+    /// * `name` - The name the hook was inserted with.
+    pub fn get<H: 'static>(&self, name: &str) -> Option<&H> {
+        let index = self.position(name)?;
+        self.hooks[index].1.downcast_ref()
+    }
+
+    /// Get the hook with the given name mutably, e.g. to disable it.
+    ///
+    /// Returns None if no hook with this name exists or if the hook has a
+    /// different type.
+    /// * `name` - The name the hook was inserted with.
+    pub fn get_mut<H: 'static>(&mut self, name: &str) -> Option<&mut H> {
+        let index = self.position(name)?;
+        self.hooks[index].1.downcast_mut()
+    }
+
+    /// Check if a hook with the given name exists.
+    /// * `name` - The name the hook was inserted with.
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// The number of hooks in the registry.
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    /// Check if the registry is empty.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Unhook all hooks, the hooks are unhooked in the reverse order of
+    /// their insertion.
+    pub fn clear(&mut self) {
+        while self.hooks.pop().is_some() {}
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.hooks
+            .iter()
+            .position(|(hook_name, _)| hook_name.as_deref() == Some(name))
+    }
+}
+
+impl Drop for HookRegistry {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
@@ -2,48 +2,611 @@
 //! Weechat hooks are used for many different things, to create commands, to
 //! listen to events on a file descriptor, add completions to weechat, etc.
 //! This module contains hook creation methods for the `Weechat` object.
+//!
+//! # Migrating from `fn` callbacks
+//!
+//! Hooks used to be created with methods on the `Weechat` object that take a
+//! function pointer and a piece of data that is passed to the function, e.g.
+//! `Weechat::hook_command`. These methods are deprecated in favour of the
+//! `new` constructors of the hook types, which accept closures. The state
+//! that used to be passed as data can be moved into the closure instead:
+//!
+//! ```ignore
+//! // Before
+//! fn command_cb(data: &Rc<RefCell<State>>, buffer: Buffer, args: ArgsWeechat) {
+//!     data.borrow_mut().runs += 1;
+//! }
+//!
+//! let command = weechat.hook_command(info, command_cb, Some(state.clone()))?;
+//!
+//! // After
+//! let command = CommandHook::new(&weechat, info, move |_, buffer, args| {
+//!     state.borrow_mut().runs += 1;
+//! })?;
+//! ```
+//!
+//! Existing callbacks can be kept as they are by calling them from the
+//! closure, e.g. `move |_, buffer, args| command_cb(&state, buffer, args)`.
+//!
+//! The hook types are no longer generic over the type of the data, fields
+//! such as `CommandHook<State>` become `CommandHook` and `FdHook<State, F>`
+//! becomes `FdHook<F>`. The deprecated methods return the same types, so
+//! plugins can be migrated one hook at a time.
+//!
+//! The same applies outside of this module: bar items are created with
+//! `BarItem::new` instead of `Weechat::new_bar_item` and buffers with
+//! callbacks with a `BufferBuilder` instead of `Weechat::buffer_new`. The
+//! data that used to be handed to the close callback of a buffer can be
+//! moved into the close closure.
+//!
+//! A callback is never run while it is already running. If WeeChat runs a
+//! hook from inside of its own callback, e.g. a signal callback that sends
+//! the same signal, the nested call is ignored and recorded as an error, see
+//! the `diagnostics` module.
 
+use chrono::{DateTime, TimeZone, Utc};
 use libc::{c_char, c_int};
 use std::borrow::Cow;
-use std::ffi::CStr;
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
 use std::os::raw::c_void;
 use std::os::unix::io::AsRawFd;
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
 use std::time::Duration;
 
-use weechat_sys::{t_gui_buffer, t_hook, t_weechat_plugin, WEECHAT_RC_OK};
+use weechat_sys::{
+    t_gui_buffer, t_hashtable, t_hook, t_weechat_plugin, WEECHAT_RC_ERROR,
+    WEECHAT_RC_OK,
+};
 
-use crate::{ArgsWeechat, Buffer, LossyCString, ReturnCode, Weechat};
+use crate::hashtable::{hashtable_to_map, map_to_hashtable};
+use crate::profiling::profile;
+use crate::{
+    ArgsWeechat, Buffer, Completion, CompletionHook, FromArgs, Infolist,
+    LossyCString, ReturnCode, Weechat,
+};
 
-/// Weechat Hook type. The hook is unhooked automatically when the object is
-/// dropped.
-pub(crate) struct Hook {
-    pub(crate) ptr: *mut t_hook,
-    pub(crate) weechat_ptr: *mut t_weechat_plugin,
+pub(crate) use self::sealed::Hook;
+
+/// The callback of a hook.
+///
+/// WeeChat may run a hook while its callback is still running, e.g. if a
+/// signal callback sends the same signal again. The callback is borrowed for
+/// the duration of a call, a nested call is refused and recorded as an error
+/// instead of creating a second mutable reference to the closure.
+///
+/// A panic of the callback is caught before it reaches WeeChat, where it
+/// would abort the process, and recorded as an error.
+pub(crate) struct HookCallback<C: ?Sized> {
+    callback: RefCell<Box<C>>,
+}
+
+impl<C: ?Sized> HookCallback<C> {
+    pub(crate) fn new(callback: Box<C>) -> Self {
+        HookCallback {
+            callback: RefCell::new(callback),
+        }
+    }
+
+    /// Run a closure with the callback.
+    /// * `weechat` - The Weechat object, used to record a refused call.
+    /// * `kind` - The kind of the hook, used in the recorded error.
+    /// * `f` - The closure that calls the callback.
+    ///
+    /// Returns None if the callback is already running or if it panicked.
+    pub(crate) fn call<R>(
+        &self,
+        weechat: &Weechat,
+        kind: HookKind,
+        f: impl FnOnce(&mut C) -> R,
+    ) -> Option<R> {
+        match self.callback.try_borrow_mut() {
+            Ok(mut callback) => catch_panic(weechat, kind, || f(&mut callback)),
+            Err(_) => {
+                weechat.record_error(&format!(
+                    "the callback of a {} hook was called while it was \
+                     running, the call was ignored",
+                    kind.as_str()
+                ));
+                None
+            }
+        }
+    }
+}
+
+/// Run a callback of the plugin, catching a panic before it reaches WeeChat,
+/// where it would abort the process.
+/// * `weechat` - The Weechat object, used to record the panic.
+/// * `kind` - The kind of the hook, used in the recorded error.
+/// * `f` - The closure that calls the callback.
+///
+/// Returns None if the callback panicked.
+pub(crate) fn catch_panic<R>(
+    weechat: &Weechat,
+    kind: HookKind,
+    f: impl FnOnce() -> R,
+) -> Option<R> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(ret) => Some(ret),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(|s| &**s))
+                .unwrap_or("unknown panic");
+
+            weechat.record_error(&format!(
+                "the callback of a {} hook panicked: {}",
+                kind.as_str(),
+                message
+            ));
+            None
+        }
+    }
+}
+
+/// Settings that all hook types share.
+///
+/// ```ignore
+/// use weechat::HookSettings;
+///
+/// let hook = SignalHook::new(&weechat, "buffer_switch", callback);
+/// hook.set_subplugin("myscript");
+/// ```
+pub trait HookSettings: sealed::RawHook {
+    /// Set a property of the hook, see `weechat_hook_set` in the WeeChat
+    /// plugin API reference. Does nothing while the hook is disabled or once
+    /// a hook that only runs once finished.
+    /// * `property` - The name of the property, e.g. `subplugin`.
+    /// * `value` - The new value of the property.
+    fn set(&self, property: &str, value: &str) {
+        if let Some(hook) = self.raw_hook() {
+            hook.set(property, value);
+        }
+    }
+
+    /// Set the name of the subplugin the hook belongs to, e.g. the name of a
+    /// script. The name is shown by the `/debug hooks` command and it's kept
+    /// if the hook is disabled and enabled again.
+    fn set_subplugin(&self, subplugin: &str) {
+        self.remember_subplugin(subplugin);
+        self.set("subplugin", subplugin);
+    }
+
+    /// Get the raw pointer of the hook, e.g. to call functions of
+    /// `weechat-sys` that aren't wrapped yet.
+    ///
+    /// The pointer is only valid as long as the hook object exists and it
+    /// must not be unhooked manually. Returns a null pointer while the hook
+    /// is unhooked, e.g. a disabled command or a finished process.
+    fn as_ptr(&self) -> *mut t_hook {
+        self.raw_hook().map_or(ptr::null_mut(), |hook| hook.ptr)
+    }
+}
+
+/// Hooks that can be disabled temporarily without dropping them.
+///
+/// The callback and the data it owns are kept while the hook is disabled.
+/// What WeeChat does in the meantime is described on the hook types, e.g. a
+/// disabled modifier leaves the string unchanged.
+pub trait ToggleHook: sealed::Switch {
+    /// Is the callback of the hook currently called.
+    fn is_enabled(&self) -> bool {
+        self.enabled()
+    }
+
+    /// Temporarily stop calling the callback, it can be called again using
+    /// `enable`.
+    fn disable(&mut self) {
+        self.set_enabled(false);
+    }
+
+    /// Call the callback again after the hook was disabled.
+    fn enable(&mut self) {
+        self.set_enabled(true);
+    }
+}
+
+/// The parts of the hook types that `HookSettings` and `ToggleHook` build on.
+/// They are private to the crate, so the public traits can't be implemented
+/// by other crates.
+pub(crate) mod sealed {
+    use weechat_sys::{t_hook, t_weechat_plugin};
+
+    use crate::{LossyCString, Weechat};
+
+    /// Weechat Hook type. The hook is unhooked automatically when the object
+    /// is dropped.
+    pub struct Hook {
+        pub(crate) ptr: *mut t_hook,
+        pub(crate) weechat_ptr: *mut t_weechat_plugin,
+    }
+
+    impl Hook {
+        /// Set a property of the hook, e.g. `stdin` for process hooks.
+        pub(crate) fn set(&self, property: &str, value: &str) {
+            let weechat = Weechat::from_ptr(self.weechat_ptr);
+            let hook_set = weechat.get().hook_set.unwrap();
+
+            let property = LossyCString::new(property);
+            let value = LossyCString::new(value);
+
+            unsafe { hook_set(self.ptr, property.as_ptr(), value.as_ptr()) };
+        }
+    }
+
+    impl Drop for Hook {
+        fn drop(&mut self) {
+            let weechat = Weechat::from_ptr(self.weechat_ptr);
+            let unhook = weechat.get().unhook.unwrap();
+            unsafe { unhook(self.ptr) };
+        }
+    }
+
+    /// Access to the hook of a hook type for the `HookSettings` methods.
+    pub trait RawHook {
+        /// The hook, None while it's unhooked.
+        fn raw_hook(&self) -> Option<&Hook>;
+
+        /// Store the subplugin for hooks that are hooked again when they are
+        /// enabled.
+        fn remember_subplugin(&self, _subplugin: &str) {}
+    }
+
+    /// The state behind the `ToggleHook` methods.
+    pub trait Switch {
+        fn enabled(&self) -> bool;
+        fn set_enabled(&mut self, enabled: bool);
+    }
+}
+
+/// A hook that was created through `weechat-sys`, e.g. by a function that
+/// isn't wrapped yet. The hook is unhooked when the object is dropped.
+pub struct ForeignHook {
+    hook: Hook,
+}
+
+impl ForeignHook {
+    /// Take ownership of a hook that was created through `weechat-sys`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `ptr` - The pointer of the hook.
+    ///
+    /// # Safety
+    ///
+    /// The pointer has to point to a valid hook of this plugin and nothing
+    /// else may unhook it. The data of the callback of the hook has to stay
+    /// valid as long as the ForeignHook object exists.
+    pub unsafe fn from_raw(weechat: &Weechat, ptr: *mut t_hook) -> ForeignHook {
+        ForeignHook {
+            hook: Hook {
+                ptr,
+                weechat_ptr: weechat.ptr,
+            },
+        }
+    }
 }
 
-impl Drop for Hook {
-    fn drop(&mut self) {
-        let weechat = Weechat::from_ptr(self.weechat_ptr);
-        let unhook = weechat.get().unhook.unwrap();
-        unsafe { unhook(self.ptr) };
+impl sealed::RawHook for ForeignHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
     }
 }
 
+impl HookSettings for ForeignHook {}
+
 /// Hook for a weechat command, the command is removed when the object is
 /// dropped.
-pub struct CommandHook<T> {
-    _hook: Hook,
-    _hook_data: Box<CommandHookData<T>>,
+///
+/// The command is removed from WeeChat while the hook is disabled. If
+/// another command with the same name was hooked in the meantime `enable`
+/// records an error and the command stays disabled, `try_enable` returns the
+/// error instead.
+pub struct CommandHook {
+    hook: Option<Hook>,
+    hook_data: Box<CommandHookData>,
+    command: CommandStrings,
+    subplugin: RefCell<Option<String>>,
+    _completion_hooks: Vec<CompletionHook>,
+}
+
+/// Error returned when a command can't be hooked because a command with the
+/// same name already exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandExists {
+    /// The name of the command that already exists.
+    pub name: String,
+}
+
+impl fmt::Display for CommandExists {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the command {} already exists", self.name)
+    }
+}
+
+impl Error for CommandExists {}
+
+struct CommandStrings {
+    name: CString,
+    description: CString,
+    args: CString,
+    args_description: CString,
+    completion: CString,
+}
+
+impl CommandHook {
+    /// Create a new weechat command. The command is unhooked when the hook is
+    /// dropped.
+    ///
+    /// ```ignore
+    /// let state = Rc::new(RefCell::new(State::default()));
+    /// let command_state = state.clone();
+    ///
+    /// let command = CommandHook::new(
+    ///     &weechat,
+    ///     CommandDescription {
+    ///         name: "myplugin",
+    ///         ..Default::default()
+    ///     },
+    ///     move |_, buffer, args| command_state.borrow_mut().run(&buffer, args),
+    /// )?;
+    /// ```
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `command_info` - The description of the command.
+    /// * `callback` - A closure that will be called when the command is run,
+    ///     it receives the Weechat object, the buffer the command was run on
+    ///     and the arguments of the command. The callback can return a
+    ///     `Result` to report a failure, see `CommandResult`.
+    ///
+    /// Returns an error if a command with the same name already exists, the
+    /// command isn't hooked in that case.
+    pub fn new<R: CommandResult>(
+        weechat: &Weechat,
+        command_info: CommandDescription,
+        mut callback: impl FnMut(&Weechat, Buffer, ArgsWeechat) -> R + 'static,
+    ) -> Result<CommandHook, CommandExists> {
+        weechat.new_command_hook(
+            command_info,
+            Box::new(move |weechat, buffer, args| {
+                let result_buffer = Buffer::wrap(buffer.weechat, buffer.ptr);
+                callback(weechat, buffer, args).into_return_code(&result_buffer)
+            }),
+        )
+    }
+
+    /// Hook a command whose arguments are parsed into a typed struct, see
+    /// the `arg_parser` module.
+    ///
+    /// The `args` and `args_description` help texts are generated from the
+    /// parser if the description doesn't contain any. If the arguments can't
+    /// be parsed the usage error is printed to the buffer the command was run
+    /// on and the callback isn't called.
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `command_info` - The description of the command.
+    /// * `callback` - A closure that will be called when the command is run,
+    ///     it receives the Weechat object, the buffer the command was run on
+    ///     and the parsed arguments.
+    ///
+    /// Returns an error if a command with the same name already exists, the
+    /// command isn't hooked in that case.
+    pub fn with_args<A: FromArgs, R: CommandResult>(
+        weechat: &Weechat,
+        command_info: CommandDescription,
+        mut callback: impl FnMut(&Weechat, Buffer, A) -> R + 'static,
+    ) -> Result<CommandHook, CommandExists> {
+        let parser = A::parser();
+        let args = parser.args();
+        let args_description = parser.args_description();
+
+        let command_info = if command_info.args.is_empty()
+            && command_info.arg_specs.is_empty()
+        {
+            CommandDescription {
+                args: &args,
+                args_description: &args_description,
+                ..command_info
+            }
+        } else {
+            command_info
+        };
+
+        CommandHook::new(weechat, command_info, move |weechat, buffer, args| {
+            parser
+                .parse(args)
+                .and_then(A::from_args)
+                .map(|args| callback(weechat, buffer, args))
+        })
+    }
 }
 
-struct CommandHookData<T> {
-    callback: fn(&T, Buffer, ArgsWeechat),
-    callback_data: T,
+impl CommandHook {
+    /// Hook the command again after it was disabled, see `ToggleHook`.
+    ///
+    /// Does nothing if the command is already enabled. Returns an error if
+    /// another command with the same name was hooked in the meantime, the
+    /// command stays disabled in that case.
+    pub fn try_enable(&mut self) -> Result<(), CommandExists> {
+        if self.hook.is_some() {
+            return Ok(());
+        }
+
+        let weechat = Weechat::from_ptr(self.hook_data.weechat_ptr);
+        let name = self.command.name.to_string_lossy();
+
+        if weechat.command_exists(&name) {
+            return Err(CommandExists {
+                name: name.into_owned(),
+            });
+        }
+
+        let hook = weechat.register_command(&self.command, &self.hook_data);
+
+        if let Some(subplugin) = &*self.subplugin.borrow() {
+            hook.set("subplugin", subplugin);
+        }
+
+        self.hook = Some(hook);
+
+        Ok(())
+    }
+}
+
+impl sealed::RawHook for CommandHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        self.hook.as_ref()
+    }
+
+    fn remember_subplugin(&self, subplugin: &str) {
+        *self.subplugin.borrow_mut() = Some(subplugin.to_owned());
+    }
+}
+
+impl sealed::Switch for CommandHook {
+    fn enabled(&self) -> bool {
+        self.hook.is_some()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.hook = None;
+        } else if let Err(error) = self.try_enable() {
+            let weechat = Weechat::from_ptr(self.hook_data.weechat_ptr);
+            weechat.record_error(&error.to_string());
+        }
+    }
+}
+
+impl HookSettings for CommandHook {}
+
+impl ToggleHook for CommandHook {}
+
+/// A completion callback that is hooked together with a command, see
+/// `CommandDescription::dynamic_completions`.
+pub type CompletionCallback = Box<dyn FnMut(&Weechat, &Buffer, &Completion)>;
+
+struct CommandHookData {
+    callback:
+        HookCallback<dyn FnMut(&Weechat, Buffer, ArgsWeechat) -> ReturnCode>,
     weechat_ptr: *mut t_weechat_plugin,
 }
 
+/// The value a command callback returns, it decides if the command succeeded.
+///
+/// Callbacks can return nothing, a `ReturnCode` or a `Result`. If the
+/// callback returns an error, the error is printed to the buffer the command
+/// was run on and WeeChat is told that the command failed. The error is also
+/// recorded for the debug command, see the `diagnostics` module.
+pub trait CommandResult {
+    /// Convert the value into the return code for WeeChat.
+    /// * `buffer` - The buffer the command was run on.
+    fn into_return_code(self, buffer: &Buffer) -> ReturnCode;
+}
+
+impl CommandResult for () {
+    fn into_return_code(self, _buffer: &Buffer) -> ReturnCode {
+        ReturnCode::Ok
+    }
+}
+
+impl CommandResult for ReturnCode {
+    fn into_return_code(self, _buffer: &Buffer) -> ReturnCode {
+        self
+    }
+}
+
+impl<T: CommandResult, E: fmt::Display> CommandResult for Result<T, E> {
+    fn into_return_code(self, buffer: &Buffer) -> ReturnCode {
+        match self {
+            Ok(value) => value.into_return_code(buffer),
+            Err(error) => {
+                let weechat = buffer.get_weechat();
+                let error = error.to_string();
+
+                buffer.print(&format!(
+                    "{}{}",
+                    weechat.get_prefix("error"),
+                    error
+                ));
+                weechat.record_error(&error);
+                ReturnCode::Error
+            }
+        }
+    }
+}
+
+/// The kind of a hook, used to list hooks with `Weechat::hooks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookKind {
+    /// Command hooks.
+    Command,
+    /// Hooks that run when a command is executed.
+    CommandRun,
+    /// Timer hooks.
+    Timer,
+    /// File descriptor hooks.
+    Fd,
+    /// Process hooks.
+    Process,
+    /// Connection hooks.
+    Connect,
+    /// Line hooks.
+    Line,
+    /// Print hooks.
+    Print,
+    /// Signal hooks.
+    Signal,
+    /// Hsignal hooks.
+    Hsignal,
+    /// Configuration option hooks.
+    Config,
+    /// Completion hooks.
+    Completion,
+    /// Modifier hooks.
+    Modifier,
+    /// Info hooks.
+    Info,
+    /// Info hooks that return a hashtable.
+    InfoHashtable,
+    /// Infolist hooks.
+    Infolist,
+    /// Hdata hooks.
+    Hdata,
+    /// Focus hooks.
+    Focus,
+}
+
+impl HookKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            HookKind::Command => "command",
+            HookKind::CommandRun => "command_run",
+            HookKind::Timer => "timer",
+            HookKind::Fd => "fd",
+            HookKind::Process => "process",
+            HookKind::Connect => "connect",
+            HookKind::Line => "line",
+            HookKind::Print => "print",
+            HookKind::Signal => "signal",
+            HookKind::Hsignal => "hsignal",
+            HookKind::Config => "config",
+            HookKind::Completion => "completion",
+            HookKind::Modifier => "modifier",
+            HookKind::Info => "info",
+            HookKind::InfoHashtable => "info_hashtable",
+            HookKind::Infolist => "infolist",
+            HookKind::Hdata => "hdata",
+            HookKind::Focus => "focus",
+        }
+    }
+}
+
 /// Setting for the FdHook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FdHookMode {
     /// Catch read events.
     Read,
@@ -71,41 +634,519 @@ impl FdHookMode {
 }
 
 /// Hook for a file descriptor, the hook is removed when the object is dropped.
-pub struct FdHook<T, F> {
-    _hook: Hook,
-    _hook_data: Box<FdHookData<T, F>>,
+///
+/// The file descriptor isn't watched while the hook is disabled.
+pub struct FdHook<F> {
+    hook: Option<Hook>,
+    hook_data: Box<FdHookData<F>>,
+    mode: FdHookMode,
+    subplugin: RefCell<Option<String>>,
 }
 
-struct FdHookData<T, F> {
-    callback: fn(&T, fd_object: &mut F),
-    callback_data: T,
-    fd_object: F,
+struct FdHookData<F> {
+    callback: HookCallback<dyn FnMut(&Weechat, &mut F)>,
+    name: &'static str,
+    fd_object: RefCell<F>,
+    weechat_ptr: *mut t_weechat_plugin,
 }
 
+impl<F: AsRawFd> FdHook<F> {
+    /// Hook an object that can be turned into a raw file descriptor.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `fd_object` - An object for which the file descriptor will be
+    ///     watched and the callback called when read or write operations can
+    ///     happen on it.
+    /// * `mode` - Configure the hook to watch for writes, reads or both on the
+    ///     file descriptor.
+    /// * `callback` - A closure that will be called with the watched object
+    ///     if a watched event on the file descriptor happens.
+    pub fn new<C>(
+        weechat: &Weechat,
+        fd_object: F,
+        mode: FdHookMode,
+        callback: C,
+    ) -> FdHook<F>
+    where
+        C: FnMut(&Weechat, &mut F) + 'static,
+    {
+        weechat.new_fd_hook(
+            fd_object,
+            mode,
+            Box::new(callback),
+            std::any::type_name::<C>(),
+        )
+    }
+
+    /// Get a reference to the object that is watched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it's called from the callback of the hook, the callback
+    /// already has a mutable reference to the object.
+    pub fn fd_object(&self) -> Ref<'_, F> {
+        self.hook_data.fd_object.borrow()
+    }
+
+    /// Get a mutable reference to the object that is watched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it's called from the callback of the hook, the callback
+    /// already has a mutable reference to the object.
+    pub fn fd_object_mut(&mut self) -> RefMut<'_, F> {
+        self.hook_data.fd_object.borrow_mut()
+    }
+
+    /// Remove the hook and return the object that was watched.
+    pub fn unhook(self) -> F {
+        let FdHook {
+            hook, hook_data, ..
+        } = self;
+
+        // The hook needs to be removed before the data the callback uses is
+        // taken apart.
+        drop(hook);
+
+        hook_data.fd_object.into_inner()
+    }
+
+    /// Remove the hook and return the object that was watched, same as
+    /// `unhook`.
+    pub fn into_inner(self) -> F {
+        self.unhook()
+    }
+
+    /// Get the events that are watched on the file descriptor.
+    pub fn mode(&self) -> FdHookMode {
+        self.mode
+    }
+
+    /// Change the events that are watched on the file descriptor.
+    ///
+    /// The hook is registered again with the new mode, the callback and the
+    /// object are kept. A disabled hook stays disabled and uses the new mode
+    /// once it's enabled.
+    /// * `mode` - The events that should be watched.
+    pub fn set_mode(&mut self, mode: FdHookMode) {
+        if self.mode == mode {
+            return;
+        }
+
+        self.mode = mode;
+
+        if self.is_enabled() {
+            self.disable();
+            self.enable();
+        }
+    }
+
+    /// Watch a new object instead of the current one, e.g. the new socket
+    /// after a reconnect.
+    ///
+    /// The hook is registered again for the file descriptor of the new
+    /// object, the callback and the mode are kept.
+    /// * `fd_object` - The object that should be watched from now on.
+    ///
+    /// Returns the object that was watched before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it's called from the callback of the hook.
+    pub fn replace_fd_object(&mut self, fd_object: F) -> F {
+        // The old file descriptor might be closed once the old object is
+        // dropped, it must not be watched anymore at that point.
+        let enabled = self.is_enabled();
+        self.disable();
+
+        let old = self.hook_data.fd_object.replace(fd_object);
+
+        if enabled {
+            self.enable();
+        }
+
+        old
+    }
+}
+
+impl<F> sealed::RawHook for FdHook<F> {
+    fn raw_hook(&self) -> Option<&Hook> {
+        self.hook.as_ref()
+    }
+
+    fn remember_subplugin(&self, subplugin: &str) {
+        *self.subplugin.borrow_mut() = Some(subplugin.to_owned());
+    }
+}
+
+impl<F: AsRawFd> sealed::Switch for FdHook<F> {
+    fn enabled(&self) -> bool {
+        self.hook.is_some()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.hook = None;
+            return;
+        }
+
+        if self.hook.is_some() {
+            return;
+        }
+
+        let weechat = Weechat::from_ptr(self.hook_data.weechat_ptr);
+        let hook = weechat.register_fd(&self.hook_data, self.mode);
+
+        if let Some(subplugin) = &*self.subplugin.borrow() {
+            hook.set("subplugin", subplugin);
+        }
+
+        self.hook = Some(hook);
+    }
+}
+
+impl<F> HookSettings for FdHook<F> {}
+
+impl<F: AsRawFd> ToggleHook for FdHook<F> {}
+
 /// Hook for a weechat command, the hook is removed when the object is dropped.
-pub struct CommandRunHook<T> {
-    _hook: Hook,
-    _hook_data: Box<CommandRunHookData<T>>,
+///
+/// The command runs as if it wasn't hooked while the hook is disabled.
+pub struct CommandRunHook {
+    hook: Hook,
+    hook_data: Box<CommandRunHookData>,
 }
 
-struct CommandRunHookData<T> {
-    callback: fn(&T, Buffer, Cow<str>) -> ReturnCode,
-    callback_data: T,
+struct CommandRunHookData {
+    callback: HookCallback<dyn FnMut(&Weechat, Buffer, Cow<str>) -> ReturnCode>,
+    enabled: Cell<bool>,
     weechat_ptr: *mut t_weechat_plugin,
 }
 
+impl sealed::RawHook for CommandRunHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for CommandRunHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for CommandRunHook {}
+
+impl ToggleHook for CommandRunHook {}
+
+impl CommandRunHook {
+    /// Hook a command when Weechat runs it, see `Weechat::hook_command_run`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `command` - The command to hook (wildcard `*` is allowed).
+    /// * `callback` - A closure that will be called when the command is run,
+    ///     returning `ReturnCode::OkEat` prevents the command from being
+    ///     executed.
+    pub fn new(
+        weechat: &Weechat,
+        command: &str,
+        callback: impl FnMut(&Weechat, Buffer, Cow<str>) -> ReturnCode + 'static,
+    ) -> CommandRunHook {
+        weechat.new_command_run_hook(command, Box::new(callback))
+    }
+}
+
 /// Hook for a signal, the hook is removed when the object is dropped.
-pub struct SignalHook<T> {
-    _hook: Hook,
-    _hook_data: Box<SignalHookData<T>>,
+///
+/// The signal is ignored while the hook is disabled.
+pub struct SignalHook {
+    hook: Hook,
+    hook_data: Box<SignalHookData>,
+}
+
+struct SignalHookData {
+    callback: HookCallback<dyn FnMut(&Weechat, SignalHookValue) -> ReturnCode>,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for SignalHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for SignalHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for SignalHook {}
+
+impl ToggleHook for SignalHook {}
+
+impl SignalHook {
+    /// Hook a signal.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `signal` - The signal to hook (wildcard `*` is allowed).
+    /// * `callback` - A closure that will be called when the signal is
+    ///     received.
+    pub fn new(
+        weechat: &Weechat,
+        signal: &str,
+        callback: impl FnMut(&Weechat, SignalHookValue) -> ReturnCode + 'static,
+    ) -> SignalHook {
+        weechat.new_signal_hook(signal, Box::new(callback))
+    }
+}
+
+/// Hook for a hsignal, the hook is removed when the object is dropped.
+///
+/// The signal is ignored while the hook is disabled.
+pub struct HsignalHook {
+    hook: Hook,
+    hook_data: Box<HsignalHookData>,
+}
+
+struct HsignalHookData {
+    callback: HookCallback<
+        dyn FnMut(&Weechat, &str, HashMap<String, String>) -> ReturnCode,
+    >,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for HsignalHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for HsignalHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for HsignalHook {}
+
+impl ToggleHook for HsignalHook {}
+
+impl HsignalHook {
+    /// Hook a hsignal, see `Weechat::hook_hsignal`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `signal` - The signal to hook (wildcard `*` is allowed).
+    /// * `callback` - A closure that will be called with the name of the
+    ///     signal and its hashtable when the signal is received.
+    pub fn new(
+        weechat: &Weechat,
+        signal: &str,
+        callback: impl FnMut(&Weechat, &str, HashMap<String, String>) -> ReturnCode
+            + 'static,
+    ) -> HsignalHook {
+        weechat.new_hsignal_hook(signal, Box::new(callback))
+    }
+}
+
+/// Hook for a modifier, the hook is removed when the object is dropped.
+///
+/// The string is left unchanged while the hook is disabled.
+pub struct ModifierHook {
+    hook: Hook,
+    hook_data: Box<ModifierHookData>,
+}
+
+struct ModifierHookData {
+    callback:
+        HookCallback<dyn FnMut(&Weechat, &str, &str, &str) -> Option<String>>,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for ModifierHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for ModifierHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for ModifierHook {}
+
+impl ToggleHook for ModifierHook {}
+
+impl ModifierHook {
+    /// Hook a modifier, see `Weechat::hook_modifier`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `modifier` - The name of the modifier to hook.
+    /// * `callback` - A closure that will be called with the name of the
+    ///     modifier, the modifier data and the string, it returns the new
+    ///     string or `None` if the string should stay unchanged.
+    pub fn new(
+        weechat: &Weechat,
+        modifier: &str,
+        callback: impl FnMut(&Weechat, &str, &str, &str) -> Option<String> + 'static,
+    ) -> ModifierHook {
+        weechat.new_modifier_hook(modifier, Box::new(callback))
+    }
+}
+
+/// Hook for option changes, the hook is removed when the object is dropped.
+///
+/// Changes of the option are ignored while the hook is disabled.
+pub struct ConfigHook {
+    hook: Hook,
+    hook_data: Box<ConfigHookData>,
+}
+
+struct ConfigHookData {
+    callback:
+        HookCallback<dyn FnMut(&Weechat, &str, Option<&str>) -> ReturnCode>,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for ConfigHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for ConfigHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for ConfigHook {}
+
+impl ToggleHook for ConfigHook {}
+
+impl ConfigHook {
+    /// Hook changes of options, see `Weechat::hook_config`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `option` - The full name of the option, wildcard `*` is allowed.
+    /// * `callback` - A closure that will be called with the full name of the
+    ///     option and the new value when a matching option changes.
+    pub fn new(
+        weechat: &Weechat,
+        option: &str,
+        callback: impl FnMut(&Weechat, &str, Option<&str>) -> ReturnCode + 'static,
+    ) -> ConfigHook {
+        weechat.new_config_hook(option, Box::new(callback))
+    }
+}
+
+/// Hook for printed lines, the hook is removed when the object is dropped.
+///
+/// Printed lines are ignored while the hook is disabled.
+pub struct PrintHook {
+    hook: Hook,
+    hook_data: Box<PrintHookData>,
 }
 
-struct SignalHookData<T> {
-    callback: fn(&T, &Weechat, SignalHookValue) -> ReturnCode,
-    callback_data: T,
+struct PrintHookData {
+    callback: HookCallback<dyn FnMut(&Weechat, PrintedLine) -> ReturnCode>,
+    name: &'static str,
+    enabled: Cell<bool>,
     weechat_ptr: *mut t_weechat_plugin,
 }
 
+impl sealed::RawHook for PrintHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for PrintHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for PrintHook {}
+
+impl ToggleHook for PrintHook {}
+
+impl PrintHook {
+    /// Hook lines that are printed to buffers, see `Weechat::hook_print`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `buffer` - The buffer whose lines should be hooked, None to hook the
+    ///     lines of all buffers.
+    /// * `tags` - Only lines with these tags are hooked, a comma separates
+    ///     alternatives and `+` joins tags that all have to be present.
+    /// * `message` - Only lines containing this string are hooked.
+    /// * `strip_colors` - Should the colors be removed from the prefix and the
+    ///     message before the callback is called.
+    /// * `callback` - A closure that will be called when a matching line is
+    ///     printed.
+    pub fn new<C>(
+        weechat: &Weechat,
+        buffer: Option<&Buffer>,
+        tags: &str,
+        message: &str,
+        strip_colors: bool,
+        callback: C,
+    ) -> PrintHook
+    where
+        C: FnMut(&Weechat, PrintedLine) -> ReturnCode + 'static,
+    {
+        weechat.new_print_hook(
+            buffer,
+            tags,
+            message,
+            strip_colors,
+            Box::new(callback),
+            std::any::type_name::<C>(),
+        )
+    }
+}
+
+/// A line that was printed, passed to the callback of a `PrintHook`.
+pub struct PrintedLine<'a> {
+    /// The buffer the line was printed to.
+    pub buffer: Buffer,
+    /// The date of the line.
+    pub date: DateTime<Utc>,
+    /// The tags of the line.
+    pub tags: Vec<Cow<'a, str>>,
+    /// Is the line displayed or is it filtered.
+    pub displayed: bool,
+    /// Is the line a highlight.
+    pub highlight: bool,
+    /// The prefix of the line.
+    pub prefix: Cow<'a, str>,
+    /// The message of the line.
+    pub message: Cow<'a, str>,
+}
+
 /// The type of data returned by a signal
 #[derive(Debug)]
 pub enum SignalHookValue {
@@ -144,18 +1185,98 @@ impl SignalHookValue {
     }
 }
 
+/// The data that can be sent with a signal.
+pub enum SignalData<'a> {
+    /// String data
+    String(&'a str),
+    /// Integer data
+    Integer(i32),
+    /// A buffer, sent as a pointer
+    Buffer(&'a Buffer),
+}
+
+impl<'a> From<&'a str> for SignalData<'a> {
+    fn from(string: &'a str) -> Self {
+        SignalData::String(string)
+    }
+}
+
+impl<'a> From<i32> for SignalData<'a> {
+    fn from(integer: i32) -> Self {
+        SignalData::Integer(integer)
+    }
+}
+
+impl<'a> From<&'a Buffer> for SignalData<'a> {
+    fn from(buffer: &'a Buffer) -> Self {
+        SignalData::Buffer(buffer)
+    }
+}
+
 /// A hook for a timer, the hook will be removed when the object is dropped.
-pub struct TimerHook<T> {
-    _hook: Hook,
-    _hook_data: Box<TimerHookData<T>>,
+///
+/// The timer keeps running while the hook is disabled, but the callback isn't called.
+pub struct TimerHook {
+    hook: Hook,
+    hook_data: Box<TimerHookData>,
 }
 
-struct TimerHookData<T> {
-    callback: fn(&T, &Weechat, i32),
-    callback_data: T,
+struct TimerHookData {
+    callback: HookCallback<dyn FnMut(&Weechat, i32)>,
+    name: &'static str,
+    enabled: Cell<bool>,
     weechat_ptr: *mut t_weechat_plugin,
 }
 
+impl sealed::RawHook for TimerHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for TimerHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for TimerHook {}
+
+impl ToggleHook for TimerHook {}
+
+impl TimerHook {
+    /// Create a timer that will repeatedly fire, see `Weechat::hook_timer`.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `interval` - The delay between calls.
+    /// * `align_second` - The alignment on a second.
+    /// * `max_calls` - The number of calls to timer (if 0, then timer has no
+    ///     end)
+    /// * `callback` - A closure that will be called when the timer fires, the
+    ///     `remaining` argument will be -1 if the timer has no end.
+    pub fn new<C>(
+        weechat: &Weechat,
+        interval: Duration,
+        align_second: i32,
+        max_calls: i32,
+        callback: C,
+    ) -> TimerHook
+    where
+        C: FnMut(&Weechat, i32) + 'static,
+    {
+        weechat.new_timer_hook(
+            interval,
+            align_second,
+            max_calls,
+            Box::new(callback),
+            std::any::type_name::<C>(),
+        )
+    }
+}
+
 #[derive(Default)]
 /// Description for a weechat command that should will be hooked.
 /// The fields of this struct accept the same string formats that are described
@@ -171,80 +1292,386 @@ pub struct CommandDescription<'a> {
     pub args_description: &'a str,
     /// Completion template for the command.
     pub completion: &'a str,
+    /// Completion callbacks that are hooked together with the command.
+    ///
+    /// Each entry is a name and a callback that populates the completion.
+    /// The name can be referenced in the `completion` template as
+    /// `%(name)`, the crate hooks the callback under a completion item
+    /// name that is unique to the command and rewrites the template
+    /// accordingly. The completions are unhooked when the command is.
+    pub dynamic_completions: Vec<(&'a str, CompletionCallback)>,
+    /// Structured description of the command arguments.
+    ///
+    /// If this isn't empty the `args` and `args_description` help texts are
+    /// generated from it, as is the `completion` template if it is empty.
+    pub arg_specs: Vec<ArgSpec<'a>>,
+}
+
+/// Description of a subcommand or argument of a command, used to generate
+/// the help text and the completion template of the command.
+#[derive(Default)]
+pub struct ArgSpec<'a> {
+    /// Name of the subcommand or argument, e.g. `add` or `<nick>`.
+    pub name: &'a str,
+    /// Arguments that follow the name, e.g. `<name> [<value>]`.
+    pub args: &'a str,
+    /// Description of the subcommand or argument.
+    pub description: &'a str,
+    /// Completion template for the arguments that follow the name, e.g.
+    /// `%(buffers_names)`. For placeholder names like `<nick>` this is the
+    /// completion of the argument itself.
+    pub completion: &'a str,
+}
+
+impl<'a> CommandDescription<'a> {
+    /// Generate the `args` help text from the argument specs.
+    fn generated_args(&self) -> String {
+        self.arg_specs
+            .iter()
+            .map(|spec| {
+                if spec.args.is_empty() {
+                    spec.name.to_owned()
+                } else {
+                    format!("{} {}", spec.name, spec.args)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" || ")
+    }
+
+    /// Generate the `args_description` help text from the argument specs,
+    /// the names are right aligned like in the help of WeeChat commands.
+    fn generated_args_description(&self) -> String {
+        let width = self
+            .arg_specs
+            .iter()
+            .map(|spec| spec.name.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        self.arg_specs
+            .iter()
+            .map(|spec| {
+                format!(
+                    "{:>width$}: {}",
+                    spec.name,
+                    spec.description,
+                    width = width
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Generate the completion template from the argument specs.
+    fn generated_completion(&self) -> String {
+        self.arg_specs
+            .iter()
+            .filter_map(|spec| {
+                // Placeholders like `<nick>` aren't completed literally.
+                if spec.name.starts_with('<') {
+                    Some(spec.completion.to_owned())
+                        .filter(|completion| !completion.is_empty())
+                } else if spec.completion.is_empty() {
+                    Some(spec.name.to_owned())
+                } else {
+                    Some(format!("{} {}", spec.name, spec.completion))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" || ")
+    }
 }
 
 impl Weechat {
+    /// Send a signal.
+    ///
+    /// * `signal` - The name of the signal that should be sent.
+    /// * `data` - The data that should be sent with the signal, this can be a
+    ///     string, an integer or a buffer.
+    ///
+    /// Returns the return code of the last callback that handled the signal.
+    pub fn signal_send<'a>(
+        &self,
+        signal: &str,
+        data: impl Into<SignalData<'a>>,
+    ) -> ReturnCode {
+        let signal_send = self.get().hook_signal_send.unwrap();
+
+        let signal = LossyCString::new(signal);
+
+        let ret = match data.into() {
+            SignalData::String(string) => {
+                let string = LossyCString::new(string);
+                unsafe {
+                    signal_send(
+                        signal.as_ptr(),
+                        b"string\0".as_ptr() as *const c_char,
+                        string.as_ptr() as *mut c_void,
+                    )
+                }
+            }
+            SignalData::Integer(mut integer) => unsafe {
+                signal_send(
+                    signal.as_ptr(),
+                    b"int\0".as_ptr() as *const c_char,
+                    &mut integer as *mut i32 as *mut c_void,
+                )
+            },
+            SignalData::Buffer(buffer) => unsafe {
+                signal_send(
+                    signal.as_ptr(),
+                    b"pointer\0".as_ptr() as *const c_char,
+                    buffer.ptr as *mut c_void,
+                )
+            },
+        };
+
+        ReturnCode::from_int(ret)
+    }
+
+    /// Send a hsignal, a signal that carries a hashtable.
+    ///
+    /// This is used to talk to other plugins, e.g. to redirect the output of
+    /// an IRC command with the `irc_redirect_command` hsignal.
+    /// * `signal` - The name of the hsignal that should be sent.
+    /// * `hashtable` - The key/value pairs that should be sent with the
+    ///     hsignal.
+    ///
+    /// Returns the return code of the last callback that handled the hsignal,
+    /// `ReturnCode::Error` if the hashtable couldn't be created.
+    pub fn hsignal_send(
+        &self,
+        signal: &str,
+        hashtable: &HashMap<String, String>,
+    ) -> ReturnCode {
+        let hsignal_send = self.get().hook_hsignal_send.unwrap();
+
+        let table = match map_to_hashtable(self, hashtable) {
+            Some(table) => table,
+            None => return ReturnCode::Error,
+        };
+
+        let signal = LossyCString::new(signal);
+
+        let ret = unsafe { hsignal_send(signal.as_ptr(), table.ptr) };
+
+        ReturnCode::from_int(ret)
+    }
+
+    /// Send a hsignal and collect the hashtable after all the callbacks ran.
+    ///
+    /// Callbacks of a hsignal receive the hashtable that was sent and may
+    /// modify it, some plugins use this to answer a request, e.g. the
+    /// `irc_redirect_pattern` hsignal reports errors in the `error` key.
+    /// * `signal` - The name of the hsignal that should be sent.
+    /// * `payload` - The key/value pairs that should be sent with the
+    ///     hsignal.
+    ///
+    /// Returns the content of the hashtable after the hsignal was handled,
+    /// None if the hashtable couldn't be created.
+    pub fn hsignal_request(
+        &self,
+        signal: &str,
+        payload: &HashMap<String, String>,
+    ) -> Option<HashMap<String, String>> {
+        let hsignal_send = self.get().hook_hsignal_send.unwrap();
+
+        let table = map_to_hashtable(self, payload)?;
+
+        let signal = LossyCString::new(signal);
+
+        unsafe { hsignal_send(signal.as_ptr(), table.ptr) };
+
+        Some(hashtable_to_map(self, table.ptr))
+    }
+
     /// Create a new weechat command. Returns the hook of the command. The
     /// command is unhooked if the hook is dropped.
+    ///
+    /// Returns an error if a command with the same name already exists, the
+    /// command isn't hooked in that case.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `CommandHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
     pub fn hook_command<T>(
         &self,
         command_info: CommandDescription,
         callback: fn(data: &T, buffer: Buffer, args: ArgsWeechat),
         callback_data: Option<T>,
-    ) -> CommandHook<T>
+    ) -> Result<CommandHook, CommandExists>
     where
-        T: Default,
+        T: Default + 'static,
     {
-        unsafe extern "C" fn c_hook_cb<T>(
-            pointer: *const c_void,
-            _data: *mut c_void,
-            buffer: *mut t_gui_buffer,
-            argc: i32,
-            argv: *mut *mut c_char,
-            _argv_eol: *mut *mut c_char,
-        ) -> c_int {
-            let hook_data: &mut CommandHookData<T> =
-                { &mut *(pointer as *mut CommandHookData<T>) };
-            let buffer = Buffer::from_ptr(hook_data.weechat_ptr, buffer);
-            let callback = hook_data.callback;
-            let callback_data = &hook_data.callback_data;
-            let args = ArgsWeechat::new(argc, argv);
+        let data = callback_data.unwrap_or_default();
 
-            callback(callback_data, buffer, args);
+        self.new_command_hook(
+            command_info,
+            Box::new(move |_, buffer, args| {
+                callback(&data, buffer, args);
+                ReturnCode::Ok
+            }),
+        )
+    }
 
-            WEECHAT_RC_OK
+    fn new_command_hook(
+        &self,
+        command_info: CommandDescription,
+        callback: Box<dyn FnMut(&Weechat, Buffer, ArgsWeechat) -> ReturnCode>,
+    ) -> Result<CommandHook, CommandExists> {
+        if self.command_exists(command_info.name) {
+            return Err(CommandExists {
+                name: command_info.name.to_owned(),
+            });
         }
 
-        let name = LossyCString::new(command_info.name);
-        let description = LossyCString::new(command_info.description);
-        let args = LossyCString::new(command_info.args);
-        let args_description = LossyCString::new(command_info.args_description);
-        let completion = LossyCString::new(command_info.completion);
+        let (args, args_description) = if command_info.arg_specs.is_empty() {
+            (
+                command_info.args.to_owned(),
+                command_info.args_description.to_owned(),
+            )
+        } else {
+            (
+                command_info.generated_args(),
+                command_info.generated_args_description(),
+            )
+        };
+
+        let mut completion_template = if command_info.completion.is_empty() {
+            command_info.generated_completion()
+        } else {
+            command_info.completion.to_owned()
+        };
+        let mut completion_hooks = Vec::new();
+
+        for (item, mut callback) in command_info.dynamic_completions {
+            let item_name = format!("{}_{}", command_info.name, item);
+
+            completion_template = completion_template
+                .replace(&format!("%({})", item), &format!("%({})", item_name));
+
+            completion_hooks.push(CompletionHook::new(
+                self,
+                &item_name,
+                "",
+                move |weechat, buffer, _, completion| {
+                    callback(weechat, &buffer, &completion);
+                    ReturnCode::Ok
+                },
+            ));
+        }
+
+        let command = CommandStrings {
+            name: LossyCString::new(command_info.name),
+            description: LossyCString::new(command_info.description),
+            args: LossyCString::new(args),
+            args_description: LossyCString::new(args_description),
+            completion: LossyCString::new(completion_template),
+        };
 
-        let data = Box::new(CommandHookData {
-            callback,
-            callback_data: callback_data.unwrap_or_default(),
+        let hook_data = Box::new(CommandHookData {
+            callback: HookCallback::new(callback),
             weechat_ptr: self.ptr,
         });
 
-        let data_ref = Box::leak(data);
+        let hook = self.register_command(&command, &hook_data);
+
+        Ok(CommandHook {
+            hook: Some(hook),
+            hook_data,
+            command,
+            subplugin: RefCell::new(None),
+            _completion_hooks: completion_hooks,
+        })
+    }
+
+    fn register_command(
+        &self,
+        command: &CommandStrings,
+        hook_data: &CommandHookData,
+    ) -> Hook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            buffer: *mut t_gui_buffer,
+            argc: i32,
+            argv: *mut *mut c_char,
+            argv_eol: *mut *mut c_char,
+        ) -> c_int {
+            let hook_data: &CommandHookData =
+                { &*(pointer as *const CommandHookData) };
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+            let buffer = Buffer::wrap(hook_data.weechat_ptr, buffer);
+            let args = ArgsWeechat::with_eol(argc, argv, argv_eol);
+
+            hook_data
+                .callback
+                .call(&weechat, HookKind::Command, |callback| {
+                    profile(
+                        HookKind::Command,
+                        || CStr::from_ptr(*argv).to_string_lossy().into_owned(),
+                        || callback(&weechat, buffer, args),
+                    )
+                })
+                .unwrap_or(ReturnCode::Error) as i32
+        }
 
         let hook_command = self.get().hook_command.unwrap();
         let hook_ptr = unsafe {
             hook_command(
                 self.ptr,
-                name.as_ptr(),
-                description.as_ptr(),
-                args.as_ptr(),
-                args_description.as_ptr(),
-                completion.as_ptr(),
-                Some(c_hook_cb::<T>),
-                data_ref as *const _ as *const c_void,
+                command.name.as_ptr(),
+                command.description.as_ptr(),
+                command.args.as_ptr(),
+                command.args_description.as_ptr(),
+                command.completion.as_ptr(),
+                Some(c_hook_cb),
+                hook_data as *const _ as *const c_void,
                 ptr::null_mut(),
             )
         };
-        let hook_data = unsafe { Box::from_raw(data_ref) };
-        let hook = Hook {
+
+        Hook {
             ptr: hook_ptr,
             weechat_ptr: self.ptr,
-        };
-
-        CommandHook::<T> {
-            _hook: hook,
-            _hook_data: hook_data,
         }
     }
 
+    /// Check if a command is currently hooked by WeeChat or any plugin.
+    ///
+    /// This can be used to avoid registering a command that would clash with
+    /// a command of another plugin.
+    /// * `name` - The name of the command, without the leading command char.
+    pub fn command_exists(&self, name: &str) -> bool {
+        self.hooks_matching(HookKind::Command, name)
+            .is_some_and(|infolist| infolist.next())
+    }
+
+    /// Get an infolist containing all the hooks of a kind that are currently
+    /// registered by WeeChat and all plugins.
+    ///
+    /// The fields of the infolist items depend on the kind of the hook, see
+    /// the `hook` infolist in the WeeChat C API documentation. Every item
+    /// contains the `plugin_name` and the `priority` of the hook.
+    /// * `kind` - The kind of hooks that should be listed.
+    pub fn hooks(&self, kind: HookKind) -> Option<Infolist> {
+        self.infolist_get("hook", kind.as_str())
+    }
+
+    /// Get an infolist containing the hooks of a kind whose arguments match
+    /// a mask, e.g. the name of a command or signal.
+    /// * `kind` - The kind of hooks that should be listed.
+    /// * `mask` - The mask that the hook arguments should match, the wildcard
+    ///     `*` is allowed.
+    pub fn hooks_matching(
+        &self,
+        kind: HookKind,
+        mask: &str,
+    ) -> Option<Infolist> {
+        self.infolist_get("hook", &format!("{},{}", kind.as_str(), mask))
+    }
+
     /// Hook an object that can be turned into a raw file descriptor.
     /// Returns the hook object.
     /// * `fd_object` - An object for wich the file descriptor will be watched
@@ -257,70 +1684,113 @@ impl Weechat {
     /// * `callback_data` - Data that will be passed to the callback every time
     ///     the callback runs. This data will be freed when the hook is
     ///     unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `FdHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
     pub fn hook_fd<T, F>(
         &self,
         fd_object: F,
         mode: FdHookMode,
         callback: fn(data: &T, fd_object: &mut F),
         callback_data: Option<T>,
-    ) -> FdHook<T, F>
+    ) -> FdHook<F>
+    where
+        T: Default + 'static,
+        F: AsRawFd + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_fd_hook(
+            fd_object,
+            mode,
+            Box::new(move |_, fd_object| callback(&data, fd_object)),
+            std::any::type_name::<T>(),
+        )
+    }
+
+    fn new_fd_hook<F>(
+        &self,
+        fd_object: F,
+        mode: FdHookMode,
+        callback: Box<dyn FnMut(&Weechat, &mut F)>,
+        name: &'static str,
+    ) -> FdHook<F>
     where
-        T: Default,
         F: AsRawFd,
     {
-        unsafe extern "C" fn c_hook_cb<T, F>(
+        let hook_data = Box::new(FdHookData {
+            callback: HookCallback::new(callback),
+            name,
+            fd_object: RefCell::new(fd_object),
+            weechat_ptr: self.ptr,
+        });
+
+        let hook = self.register_fd(&hook_data, mode);
+
+        FdHook {
+            hook: Some(hook),
+            hook_data,
+            mode,
+            subplugin: RefCell::new(None),
+        }
+    }
+
+    fn register_fd<F: AsRawFd>(
+        &self,
+        hook_data: &FdHookData<F>,
+        mode: FdHookMode,
+    ) -> Hook {
+        unsafe extern "C" fn c_hook_cb<F>(
             pointer: *const c_void,
             _data: *mut c_void,
             _fd: i32,
         ) -> c_int {
-            let hook_data: &mut FdHookData<T, F> =
-                { &mut *(pointer as *mut FdHookData<T, F>) };
-            let callback = hook_data.callback;
-            let callback_data = &hook_data.callback_data;
-            let fd_object = &mut hook_data.fd_object;
+            let hook_data: &FdHookData<F> =
+                { &*(pointer as *const FdHookData<F>) };
+            let name = hook_data.name;
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
 
-            callback(callback_data, fd_object);
+            hook_data
+                .callback
+                .call(&weechat, HookKind::Fd, |callback| {
+                    let mut fd_object = hook_data.fd_object.borrow_mut();
 
-            WEECHAT_RC_OK
-        }
+                    profile(
+                        HookKind::Fd,
+                        || format!("{} ({:p})", name, pointer),
+                        || callback(&weechat, &mut fd_object),
+                    );
 
-        let fd = fd_object.as_raw_fd();
-
-        let data = Box::new(FdHookData {
-            callback,
-            callback_data: callback_data.unwrap_or_default(),
-            fd_object,
-        });
+                    WEECHAT_RC_OK
+                })
+                .unwrap_or(WEECHAT_RC_ERROR)
+        }
 
-        let data_ref = Box::leak(data);
         let hook_fd = self.get().hook_fd.unwrap();
         let (read, write) = mode.as_tuple();
 
         let hook_ptr = unsafe {
             hook_fd(
                 self.ptr,
-                fd,
+                hook_data.fd_object.borrow().as_raw_fd(),
                 read,
                 write,
                 0,
-                Some(c_hook_cb::<T, F>),
-                data_ref as *const _ as *const c_void,
+                Some(c_hook_cb::<F>),
+                hook_data as *const _ as *const c_void,
                 ptr::null_mut(),
             )
         };
-        let hook_data = unsafe { Box::from_raw(data_ref) };
-        let hook = Hook {
+
+        Hook {
             ptr: hook_ptr,
             weechat_ptr: self.ptr,
-        };
-
-        FdHook::<T, F> {
-            _hook: hook,
-            _hook_data: hook_data,
         }
     }
 
-    /// Create a timer that will repeatedly fire.
+    /// Create a timer that will repeatedly fire. The timer is removed when
+    /// the returned hook is dropped.
     ///
     /// * `interval` - The delay between calls in milliseconds.
     /// * `align_second` - The alignment on a second. For example, if current time is 09:00, if
@@ -331,6 +1801,10 @@ impl Weechat {
     ///     will be -1 if the timer has no end.
     /// * `callback_data` - Data that will be passed to the callback every time
     ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `TimerHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
     pub fn hook_timer<T>(
         &self,
         interval: Duration,
@@ -338,32 +1812,63 @@ impl Weechat {
         max_calls: i32,
         callback: fn(data: &T, weechat: &Weechat, remaining: i32),
         callback_data: Option<T>,
-    ) -> TimerHook<T>
+    ) -> TimerHook
     where
-        T: Default,
+        T: Default + 'static,
     {
-        unsafe extern "C" fn c_hook_cb<T>(
+        let data = callback_data.unwrap_or_default();
+
+        self.new_timer_hook(
+            interval,
+            align_second,
+            max_calls,
+            Box::new(move |weechat, remaining| {
+                callback(&data, weechat, remaining)
+            }),
+            std::any::type_name::<T>(),
+        )
+    }
+
+    fn new_timer_hook(
+        &self,
+        interval: Duration,
+        align_second: i32,
+        max_calls: i32,
+        callback: Box<dyn FnMut(&Weechat, i32)>,
+        name: &'static str,
+    ) -> TimerHook {
+        unsafe extern "C" fn c_hook_cb(
             pointer: *const c_void,
             _data: *mut c_void,
             remaining: i32,
         ) -> c_int {
-            let hook_data: &mut TimerHookData<T> =
-                { &mut *(pointer as *mut TimerHookData<T>) };
-            let callback = &hook_data.callback;
-            let callback_data = &hook_data.callback_data;
-
-            callback(
-                callback_data,
-                &Weechat::from_ptr(hook_data.weechat_ptr),
-                remaining,
-            );
+            let hook_data: &TimerHookData =
+                { &*(pointer as *const TimerHookData) };
+
+            if !hook_data.enabled.get() {
+                return WEECHAT_RC_OK;
+            }
+
+            let name = hook_data.name;
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            hook_data
+                .callback
+                .call(&weechat, HookKind::Timer, |callback| {
+                    profile(
+                        HookKind::Timer,
+                        || format!("{} ({:p})", name, pointer),
+                        || callback(&weechat, remaining),
+                    )
+                });
 
             WEECHAT_RC_OK
         }
 
-        let data = Box::new(TimerHookData::<T> {
-            callback,
-            callback_data: callback_data.unwrap_or_default(),
+        let data = Box::new(TimerHookData {
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
+            name,
             weechat_ptr: self.ptr,
         });
 
@@ -376,7 +1881,7 @@ impl Weechat {
                 interval.as_millis() as i64,
                 align_second,
                 max_calls,
-                Some(c_hook_cb::<T>),
+                Some(c_hook_cb),
                 data_ref as *const _ as *const c_void,
                 ptr::null_mut(),
             )
@@ -387,60 +1892,96 @@ impl Weechat {
             weechat_ptr: self.ptr,
         };
 
-        TimerHook {
-            _hook: hook,
-            _hook_data: hook_data,
-        }
+        TimerHook { hook, hook_data }
     }
 
     /// Hook a command when Weechat runs it.
     ///
+    /// This can be used to intercept built-in commands, e.g. `/input return`
+    /// or `/buffer close`.
     /// * `command` - The command to hook (wildcard `*` is allowed).
-    /// * `callback` - A function that will be called when the command is run.
+    /// * `callback` - A function that will be called when the command is run,
+    ///     it receives the buffer the command is run on and the full command
+    ///     including its arguments. Returning `ReturnCode::OkEat` prevents the
+    ///     command from being executed, `ReturnCode::Ok` lets it continue.
     /// * `callback_data` - Data that will be passed to the callback every time
     ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `CommandRunHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
     pub fn hook_command_run<T>(
         &self,
         command: &str,
         callback: fn(data: &T, buffer: Buffer, command: Cow<str>) -> ReturnCode,
         callback_data: Option<T>,
-    ) -> CommandRunHook<T>
+    ) -> CommandRunHook
     where
-        T: Default,
+        T: Default + 'static,
     {
-        unsafe extern "C" fn c_hook_cb<T>(
+        let data = callback_data.unwrap_or_default();
+
+        self.new_command_run_hook(
+            command,
+            Box::new(move |_, buffer, command| {
+                callback(&data, buffer, command)
+            }),
+        )
+    }
+
+    fn new_command_run_hook(
+        &self,
+        command: &str,
+        callback: Box<dyn FnMut(&Weechat, Buffer, Cow<str>) -> ReturnCode>,
+    ) -> CommandRunHook {
+        unsafe extern "C" fn c_hook_cb(
             pointer: *const c_void,
             _data: *mut c_void,
             buffer: *mut t_gui_buffer,
             command: *const std::os::raw::c_char,
         ) -> c_int {
-            let hook_data: &mut CommandRunHookData<T> =
-                { &mut *(pointer as *mut CommandRunHookData<T>) };
-            let callback = hook_data.callback;
-            let callback_data = &hook_data.callback_data;
+            let hook_data: &CommandRunHookData =
+                { &*(pointer as *const CommandRunHookData) };
+
+            if !hook_data.enabled.get() {
+                return WEECHAT_RC_OK;
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
 
-            let buffer = Buffer::from_ptr(hook_data.weechat_ptr, buffer);
+            let buffer = Buffer::wrap(hook_data.weechat_ptr, buffer);
             let command = CStr::from_ptr(command).to_string_lossy();
 
-            callback(callback_data, buffer, command) as isize as i32
+            let ret = hook_data
+                .callback
+                .call(&weechat, HookKind::CommandRun, |callback| {
+                    profile(
+                        HookKind::CommandRun,
+                        || command.clone().into_owned(),
+                        || callback(&weechat, buffer, command.clone()),
+                    )
+                })
+                .unwrap_or(ReturnCode::Error);
+
+            ret as isize as i32
         }
 
         let data = Box::new(CommandRunHookData {
-            callback,
-            callback_data: callback_data.unwrap_or_default(),
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
             weechat_ptr: self.ptr,
         });
 
         let data_ref = Box::leak(data);
-        let hook_timer = self.get().hook_command_run.unwrap();
+        let hook_command_run = self.get().hook_command_run.unwrap();
 
         let command = LossyCString::new(command);
 
         let hook_ptr = unsafe {
-            hook_timer(
+            hook_command_run(
                 self.ptr,
                 command.as_ptr(),
-                Some(c_hook_cb::<T>),
+                Some(c_hook_cb),
                 data_ref as *const _ as *const c_void,
                 ptr::null_mut(),
             )
@@ -451,10 +1992,7 @@ impl Weechat {
             weechat_ptr: self.ptr,
         };
 
-        CommandRunHook::<T> {
-            _hook: hook,
-            _hook_data: hook_data,
-        }
+        CommandRunHook { hook, hook_data }
     }
 
     /// Hook a signal.
@@ -463,6 +2001,10 @@ impl Weechat {
     /// * `callback` - A function that will be called when the signal is received.
     /// * `callback_data` - Data that will be passed to the callback every time
     ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `SignalHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
     pub fn hook_signal<T>(
         &self,
         signal: &str,
@@ -472,21 +2014,38 @@ impl Weechat {
             signal_value: SignalHookValue,
         ) -> ReturnCode,
         callback_data: Option<T>,
-    ) -> SignalHook<T>
+    ) -> SignalHook
     where
-        T: Default,
+        T: Default + 'static,
     {
-        unsafe extern "C" fn c_hook_cb<T>(
+        let data = callback_data.unwrap_or_default();
+
+        self.new_signal_hook(
+            signal,
+            Box::new(move |weechat, value| callback(&data, weechat, value)),
+        )
+    }
+
+    fn new_signal_hook(
+        &self,
+        signal: &str,
+        callback: Box<dyn FnMut(&Weechat, SignalHookValue) -> ReturnCode>,
+    ) -> SignalHook {
+        unsafe extern "C" fn c_hook_cb(
             pointer: *const c_void,
             _data: *mut c_void,
-            _signal: *const c_char,
+            signal: *const c_char,
             data_type: *const c_char,
             signal_data: *mut c_void,
         ) -> c_int {
-            let hook_data: &mut SignalHookData<T> =
-                { &mut *(pointer as *mut SignalHookData<T>) };
-            let callback = hook_data.callback;
-            let callback_data = &hook_data.callback_data;
+            let hook_data: &SignalHookData =
+                { &*(pointer as *const SignalHookData) };
+
+            if !hook_data.enabled.get() {
+                return WEECHAT_RC_OK;
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
 
             // this cannot contain invalid utf
             let data_type =
@@ -494,19 +2053,28 @@ impl Weechat {
             if let Some(value) =
                 SignalHookValue::from_raw_with_type(data_type, signal_data)
             {
-                callback(
-                    callback_data,
-                    &Weechat::from_ptr(hook_data.weechat_ptr),
-                    value,
-                ) as i32
+                hook_data
+                    .callback
+                    .call(&weechat, HookKind::Signal, |callback| {
+                        profile(
+                            HookKind::Signal,
+                            || {
+                                CStr::from_ptr(signal)
+                                    .to_string_lossy()
+                                    .into_owned()
+                            },
+                            || callback(&weechat, value),
+                        )
+                    })
+                    .unwrap_or(ReturnCode::Error) as i32
             } else {
                 WEECHAT_RC_OK
             }
         }
 
         let data = Box::new(SignalHookData {
-            callback,
-            callback_data: callback_data.unwrap_or_default(),
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
             weechat_ptr: self.ptr,
         });
 
@@ -519,7 +2087,350 @@ impl Weechat {
             hook_signal(
                 self.ptr,
                 signal.as_ptr(),
-                Some(c_hook_cb::<T>),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        SignalHook { hook, hook_data }
+    }
+
+    /// Hook a hsignal, a signal that carries a hashtable.
+    ///
+    /// * `signal` - The signal to hook (wildcard `*` is allowed).
+    /// * `callback` - A function that will be called when the signal is
+    ///     received, it receives the name of the signal and the hashtable of
+    ///     the signal converted into a map. Pointers in the hashtable are
+    ///     converted to hex strings.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `HsignalHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_hsignal<T>(
+        &self,
+        signal: &str,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            signal: &str,
+            hashtable: HashMap<String, String>,
+        ) -> ReturnCode,
+        callback_data: Option<T>,
+    ) -> HsignalHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_hsignal_hook(
+            signal,
+            Box::new(move |weechat, signal, hashtable| {
+                callback(&data, weechat, signal, hashtable)
+            }),
+        )
+    }
+
+    fn new_hsignal_hook(
+        &self,
+        signal: &str,
+        callback: Box<
+            dyn FnMut(&Weechat, &str, HashMap<String, String>) -> ReturnCode,
+        >,
+    ) -> HsignalHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            signal: *const c_char,
+            hashtable: *mut t_hashtable,
+        ) -> c_int {
+            let hook_data: &HsignalHookData =
+                { &*(pointer as *const HsignalHookData) };
+
+            if !hook_data.enabled.get() {
+                return WEECHAT_RC_OK;
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let signal = CStr::from_ptr(signal).to_string_lossy();
+            let hashtable = hashtable_to_map(&weechat, hashtable);
+
+            hook_data
+                .callback
+                .call(&weechat, HookKind::Hsignal, |callback| {
+                    profile(
+                        HookKind::Hsignal,
+                        || signal.clone().into_owned(),
+                        || callback(&weechat, &signal, hashtable),
+                    )
+                })
+                .unwrap_or(ReturnCode::Error) as i32
+        }
+
+        let data = Box::new(HsignalHookData {
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_hsignal = self.get().hook_hsignal.unwrap();
+
+        let signal = LossyCString::new(signal);
+
+        let hook_ptr = unsafe {
+            hook_hsignal(
+                self.ptr,
+                signal.as_ptr(),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        HsignalHook { hook, hook_data }
+    }
+
+    /// Hook changes of options, this can be used to watch the options of
+    /// WeeChat or of other plugins.
+    ///
+    /// * `option` - The full name of the option, e.g.
+    ///     `weechat.look.buffer_time_format`. Wildcard `*` is allowed, e.g.
+    ///     `irc.server.*`.
+    /// * `callback` - A function that will be called when a matching option
+    ///     changes, it receives the full name of the option and the new
+    ///     value, the value is None if the option was removed.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `ConfigHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_config<T>(
+        &self,
+        option: &str,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            option: &str,
+            value: Option<&str>,
+        ) -> ReturnCode,
+        callback_data: Option<T>,
+    ) -> ConfigHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_config_hook(
+            option,
+            Box::new(move |weechat, option, value| {
+                callback(&data, weechat, option, value)
+            }),
+        )
+    }
+
+    fn new_config_hook(
+        &self,
+        option: &str,
+        callback: Box<dyn FnMut(&Weechat, &str, Option<&str>) -> ReturnCode>,
+    ) -> ConfigHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            option: *const c_char,
+            value: *const c_char,
+        ) -> c_int {
+            let hook_data: &ConfigHookData =
+                { &*(pointer as *const ConfigHookData) };
+
+            if !hook_data.enabled.get() {
+                return WEECHAT_RC_OK;
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let option = CStr::from_ptr(option).to_string_lossy();
+            let value = if value.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(value).to_string_lossy())
+            };
+
+            hook_data
+                .callback
+                .call(&weechat, HookKind::Config, |callback| {
+                    profile(
+                        HookKind::Config,
+                        || option.clone().into_owned(),
+                        || callback(&weechat, &option, value.as_deref()),
+                    )
+                })
+                .unwrap_or(ReturnCode::Error) as i32
+        }
+
+        let data = Box::new(ConfigHookData {
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_config = self.get().hook_config.unwrap();
+
+        let option = LossyCString::new(option);
+
+        let hook_ptr = unsafe {
+            hook_config(
+                self.ptr,
+                option.as_ptr(),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        ConfigHook { hook, hook_data }
+    }
+
+    /// Hook a modifier, modifiers allow changing strings, e.g. messages
+    /// before they are printed with the `weechat_print` modifier.
+    ///
+    /// * `modifier` - The name of the modifier to hook.
+    /// * `callback` - A function that will be called when the modifier is
+    ///     used, it receives the name of the modifier, the modifier data and
+    ///     the string that should be modified. The callback returns the new
+    ///     string or `None` if the string should stay unchanged. Returning an
+    ///     empty string stops the processing, e.g. the message won't be
+    ///     printed for the `weechat_print` modifier.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `ModifierHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_modifier<T>(
+        &self,
+        modifier: &str,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            modifier: &str,
+            modifier_data: &str,
+            string: &str,
+        ) -> Option<String>,
+        callback_data: Option<T>,
+    ) -> ModifierHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_modifier_hook(
+            modifier,
+            Box::new(move |weechat, modifier, modifier_data, string| {
+                callback(&data, weechat, modifier, modifier_data, string)
+            }),
+        )
+    }
+
+    fn new_modifier_hook(
+        &self,
+        modifier: &str,
+        callback: Box<dyn FnMut(&Weechat, &str, &str, &str) -> Option<String>>,
+    ) -> ModifierHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            modifier: *const c_char,
+            modifier_data: *const c_char,
+            string: *const c_char,
+        ) -> *mut c_char {
+            let hook_data: &ModifierHookData =
+                { &*(pointer as *const ModifierHookData) };
+
+            if !hook_data.enabled.get() {
+                return ptr::null_mut();
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let modifier = CStr::from_ptr(modifier).to_string_lossy();
+            let modifier_data = if modifier_data.is_null() {
+                Cow::Borrowed("")
+            } else {
+                CStr::from_ptr(modifier_data).to_string_lossy()
+            };
+            let string = if string.is_null() {
+                Cow::Borrowed("")
+            } else {
+                CStr::from_ptr(string).to_string_lossy()
+            };
+
+            // A nested call leaves the string unchanged.
+            let new_string = hook_data
+                .callback
+                .call(&weechat, HookKind::Modifier, |callback| {
+                    profile(
+                        HookKind::Modifier,
+                        || modifier.clone().into_owned(),
+                        || {
+                            callback(
+                                &weechat,
+                                &modifier,
+                                &modifier_data,
+                                &string,
+                            )
+                        },
+                    )
+                })
+                .flatten();
+
+            match new_string {
+                // WeeChat frees the returned string, so it needs to be
+                // allocated with malloc.
+                Some(new_string) => {
+                    let new_string = LossyCString::new(new_string);
+                    libc::strdup(new_string.as_ptr())
+                }
+                None => ptr::null_mut(),
+            }
+        }
+
+        let data = Box::new(ModifierHookData {
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_modifier = self.get().hook_modifier.unwrap();
+
+        let modifier = LossyCString::new(modifier);
+
+        let hook_ptr = unsafe {
+            hook_modifier(
+                self.ptr,
+                modifier.as_ptr(),
+                Some(c_hook_cb),
                 data_ref as *const _ as *const c_void,
                 ptr::null_mut(),
             )
@@ -530,9 +2441,203 @@ impl Weechat {
             weechat_ptr: self.ptr,
         };
 
-        SignalHook::<T> {
-            _hook: hook,
-            _hook_data: hook_data,
+        ModifierHook { hook, hook_data }
+    }
+
+    /// Run a modifier and get the modified string.
+    ///
+    /// * `modifier` - The name of the modifier, e.g. `irc_color_decode` or
+    ///     `color_encode_ansi`.
+    /// * `modifier_data` - The data for the modifier, the meaning depends on
+    ///     the modifier.
+    /// * `string` - The string that should be modified.
+    ///
+    /// Returns None if the modifier couldn't be run.
+    pub fn modifier_exec(
+        &self,
+        modifier: &str,
+        modifier_data: &str,
+        string: &str,
+    ) -> Option<String> {
+        let hook_modifier_exec = self.get().hook_modifier_exec.unwrap();
+
+        let modifier = LossyCString::new(modifier);
+        let modifier_data = LossyCString::new(modifier_data);
+        let string = LossyCString::new(string);
+
+        unsafe {
+            let ret = hook_modifier_exec(
+                self.ptr,
+                modifier.as_ptr(),
+                modifier_data.as_ptr(),
+                string.as_ptr(),
+            );
+
+            if ret.is_null() {
+                None
+            } else {
+                let modified =
+                    CStr::from_ptr(ret).to_string_lossy().into_owned();
+                libc::free(ret as *mut c_void);
+                Some(modified)
+            }
+        }
+    }
+
+    /// Hook lines that are printed to buffers.
+    ///
+    /// * `buffer` - The buffer whose lines should be hooked, None to hook the
+    ///     lines of all buffers.
+    /// * `tags` - Only lines with these tags are hooked. Tags separated by
+    ///     commas are alternatives and tags joined with `+` all have to be
+    ///     present, e.g. `irc_privmsg+notify_private,irc_notice` hooks
+    ///     private messages and notices. `*` may be used as a wildcard, an
+    ///     empty string hooks lines with any tags.
+    /// * `message` - Only lines containing this string are hooked, the case
+    ///     is ignored. An empty string hooks all lines.
+    /// * `strip_colors` - Should the colors be removed from the prefix and the
+    ///     message before the callback is called.
+    /// * `callback` - A function that will be called when a matching line is
+    ///     printed.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `PrintHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_print<T>(
+        &self,
+        buffer: Option<&Buffer>,
+        tags: &str,
+        message: &str,
+        strip_colors: bool,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            line: PrintedLine,
+        ) -> ReturnCode,
+        callback_data: Option<T>,
+    ) -> PrintHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_print_hook(
+            buffer,
+            tags,
+            message,
+            strip_colors,
+            Box::new(move |weechat, line| callback(&data, weechat, line)),
+            std::any::type_name::<T>(),
+        )
+    }
+
+    fn new_print_hook(
+        &self,
+        buffer: Option<&Buffer>,
+        tags: &str,
+        message: &str,
+        strip_colors: bool,
+        callback: Box<dyn FnMut(&Weechat, PrintedLine) -> ReturnCode>,
+        name: &'static str,
+    ) -> PrintHook {
+        #[allow(clippy::too_many_arguments)]
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            buffer: *mut t_gui_buffer,
+            date: libc::time_t,
+            tags_count: c_int,
+            tags: *mut *const c_char,
+            displayed: c_int,
+            highlight: c_int,
+            prefix: *const c_char,
+            message: *const c_char,
+        ) -> c_int {
+            let hook_data: &PrintHookData =
+                { &*(pointer as *const PrintHookData) };
+
+            if !hook_data.enabled.get() {
+                return WEECHAT_RC_OK;
+            }
+
+            let name = hook_data.name;
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let to_str = |string: *const c_char| {
+                if string.is_null() {
+                    Cow::Borrowed("")
+                } else {
+                    CStr::from_ptr(string).to_string_lossy()
+                }
+            };
+
+            let tags = if tags.is_null() {
+                Vec::new()
+            } else {
+                (0..tags_count.max(0) as usize)
+                    .map(|i| to_str(*tags.add(i)))
+                    .collect()
+            };
+
+            let line = PrintedLine {
+                buffer: Buffer::wrap(hook_data.weechat_ptr, buffer),
+                date: Utc
+                    .timestamp_opt(date, 0)
+                    .single()
+                    .unwrap_or_else(Utc::now),
+                tags,
+                displayed: displayed != 0,
+                highlight: highlight != 0,
+                prefix: to_str(prefix),
+                message: to_str(message),
+            };
+
+            hook_data
+                .callback
+                .call(&weechat, HookKind::Print, |callback| {
+                    profile(
+                        HookKind::Print,
+                        || format!("{} ({:p})", name, pointer),
+                        || callback(&weechat, line),
+                    )
+                })
+                .unwrap_or(ReturnCode::Error) as i32
         }
+
+        let data = Box::new(PrintHookData {
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
+            name,
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_print = self.get().hook_print.unwrap();
+
+        let buffer = buffer.map_or(ptr::null_mut(), |buffer| buffer.ptr);
+        let tags = LossyCString::new(tags);
+        let message = LossyCString::new(message);
+
+        let hook_ptr = unsafe {
+            hook_print(
+                self.ptr,
+                buffer,
+                tags.as_ptr(),
+                message.as_ptr(),
+                strip_colors as i32,
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        PrintHook { hook, hook_data }
     }
 }
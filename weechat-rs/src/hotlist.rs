@@ -0,0 +1,102 @@
+//! Read the hotlist, the list of buffers with unread activity.
+
+use chrono::{DateTime, TimeZone, Utc};
+use weechat_sys::t_gui_buffer;
+
+use crate::buffer::HotlistPriority;
+use crate::{Buffer, Infolist, Weechat};
+
+/// An iterator over the entries of the hotlist, created with
+/// `Weechat::hotlist`.
+///
+/// The entries are sorted the way WeeChat displays them, by default the
+/// buffers with the highest priority come first.
+pub struct Hotlist {
+    infolist: Option<Infolist>,
+}
+
+/// A buffer in the hotlist.
+pub struct HotlistEntry {
+    /// The buffer with unread activity.
+    pub buffer: Buffer,
+    /// The highest priority of the unread lines of the buffer.
+    pub priority: HotlistPriority,
+    /// The time the buffer was added to the hotlist.
+    pub creation_time: Option<DateTime<Utc>>,
+    /// The number of unread lines per priority, indexed by the priority,
+    /// e.g. `counts[HotlistPriority::Highlight as usize]`.
+    pub counts: [u32; 4],
+}
+
+impl HotlistEntry {
+    /// Get the number of unread lines with the given priority.
+    /// * `priority` - The priority of the lines that should be counted.
+    pub fn count(&self, priority: HotlistPriority) -> u32 {
+        self.counts[priority as usize]
+    }
+}
+
+impl Iterator for Hotlist {
+    type Item = HotlistEntry;
+
+    fn next(&mut self) -> Option<HotlistEntry> {
+        let infolist = self.infolist.as_ref()?;
+
+        while infolist.next() {
+            let buffer = infolist.get_pointer("buffer_pointer");
+            if buffer.is_null() {
+                continue;
+            }
+
+            let priority = match HotlistPriority::from_int(
+                infolist.get_integer("priority"),
+            ) {
+                Some(priority) => priority,
+                None => continue,
+            };
+
+            let mut counts = [0; 4];
+            for (i, count) in counts.iter_mut().enumerate() {
+                *count = infolist.get_integer(&format!("count_{:02}", i)).max(0)
+                    as u32;
+            }
+
+            return Some(HotlistEntry {
+                buffer: Buffer::wrap(
+                    infolist.weechat_ptr,
+                    buffer as *mut t_gui_buffer,
+                ),
+                priority,
+                creation_time: creation_time(infolist),
+                counts,
+            });
+        }
+
+        None
+    }
+}
+
+/// The creation time of a hotlist entry is stored as a `struct timeval`.
+fn creation_time(infolist: &Infolist) -> Option<DateTime<Utc>> {
+    let bytes = infolist.get_bytes("creation_time")?;
+
+    if bytes.len() != std::mem::size_of::<libc::timeval>() {
+        return None;
+    }
+
+    let time = unsafe {
+        std::ptr::read_unaligned(bytes.as_ptr() as *const libc::timeval)
+    };
+
+    Utc.timestamp_opt(time.tv_sec as i64, time.tv_usec as u32 * 1000)
+        .single()
+}
+
+impl Weechat {
+    /// Get the entries of the hotlist.
+    pub fn hotlist(&self) -> Hotlist {
+        Hotlist {
+            infolist: self.infolist_get("hotlist", ""),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Translation of plugin messages using the gettext support of WeeChat.
+//!
+//! The `tr!` and `tr_plural!` macros translate a message and substitute
+//! arguments into it. Since translated messages are only known at runtime
+//! the arguments are substituted at runtime as well, every `{}` in the
+//! message is replaced by the next argument, `{{` and `}}` produce literal
+//! braces.
+
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::fmt::Display;
+
+use crate::{LossyCString, Weechat};
+
+impl Weechat {
+    /// Get the translation of a message.
+    ///
+    /// Returns the message itself if no translation is found.
+    pub fn gettext(&self, message: &str) -> Cow<str> {
+        let gettext = self.get().gettext.unwrap();
+
+        let message = LossyCString::new(message);
+
+        unsafe { CStr::from_ptr(gettext(message.as_ptr())).to_string_lossy() }
+    }
+
+    /// Get the translation of a message, choosing the singular or plural
+    /// form depending on a count.
+    /// * `single` - The singular form of the message.
+    /// * `plural` - The plural form of the message.
+    /// * `count` - The count the form is chosen by.
+    pub fn ngettext(&self, single: &str, plural: &str, count: i32) -> Cow<str> {
+        let ngettext = self.get().ngettext.unwrap();
+
+        let single = LossyCString::new(single);
+        let plural = LossyCString::new(plural);
+
+        unsafe {
+            CStr::from_ptr(ngettext(single.as_ptr(), plural.as_ptr(), count))
+                .to_string_lossy()
+        }
+    }
+}
+
+/// Substitute arguments into a message that is only known at runtime.
+///
+/// Every `{}` in the message is replaced by the next argument, `{{` and `}}`
+/// produce literal braces. Placeholders without a matching argument are
+/// left empty.
+pub fn format_translated(message: &str, args: &[&dyn Display]) -> String {
+    let mut formatted = String::with_capacity(message.len());
+    let mut args = args.iter();
+    let mut chars = message.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                formatted.push(c);
+                chars.next();
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                if let Some(arg) = args.next() {
+                    formatted.push_str(&arg.to_string());
+                }
+            }
+            _ => formatted.push(c),
+        }
+    }
+
+    formatted
+}
+
+/// Translate a message and substitute arguments into it.
+///
+/// ```ignore
+/// let message = tr!(weechat, "Connected to {}", server);
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($weechat:expr, $message:expr) => {
+        $weechat.gettext($message).into_owned()
+    };
+    ($weechat:expr, $message:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format_translated(
+            &$weechat.gettext($message),
+            &[$(&$arg as &dyn ::std::fmt::Display),+],
+        )
+    };
+}
+
+/// Translate a message choosing the singular or plural form by a count and
+/// substitute arguments into it.
+///
+/// ```ignore
+/// let message = tr_plural!(weechat, "{} new message", "{} new messages", n, n);
+/// ```
+#[macro_export]
+macro_rules! tr_plural {
+    ($weechat:expr, $single:expr, $plural:expr, $count:expr) => {
+        $weechat.ngettext($single, $plural, $count as i32).into_owned()
+    };
+    ($weechat:expr, $single:expr, $plural:expr, $count:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format_translated(
+            &$weechat.ngettext($single, $plural, $count as i32),
+            &[$(&$arg as &dyn ::std::fmt::Display),+],
+        )
+    };
+}
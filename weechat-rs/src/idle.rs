@@ -0,0 +1,279 @@
+//! Defer work until WeeChat is back in its main loop.
+//!
+//! Some callbacks, e.g. the ones of modifiers or buffer close callbacks, run
+//! while WeeChat is in the middle of an operation. Work that changes buffers
+//! or hooks should be deferred out of such callbacks, an idle callback runs
+//! once on the next iteration of the main loop.
+//!
+//! `Weechat::call_later` runs a closure once after a delay, the timer cleans
+//! itself up after it fired so no handle needs to be kept. Timers that are
+//! still pending when the plugin is unloaded are removed together with their
+//! closures.
+
+use libc::c_int;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+use std::time::Duration;
+
+use weechat_sys::{t_weechat_plugin, WEECHAT_RC_OK};
+
+use crate::hooks::{catch_panic, sealed, Hook, HookSettings};
+use crate::profiling::profile;
+use crate::{HookKind, Weechat};
+
+/// A closure of `Weechat::call_later` whose timer didn't fire yet.
+struct PendingCall {
+    hook: Hook,
+    callback: Box<dyn FnOnce(&Weechat)>,
+}
+
+thread_local! {
+    /// The pending calls by the id that is passed to the timer callback.
+    static PENDING_CALLS: RefCell<HashMap<usize, PendingCall>> =
+        RefCell::new(HashMap::new());
+    static NEXT_CALL_ID: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Remove the timers of `Weechat::call_later` that didn't fire yet and free
+/// their closures.
+pub(crate) fn shutdown() {
+    let pending =
+        PENDING_CALLS.with(|calls| mem::take(&mut *calls.borrow_mut()));
+    drop(pending);
+}
+
+/// An idle callback that is waiting to run, the callback is cancelled if the
+/// object is dropped before it ran.
+///
+/// Use `Weechat::call_later` for callbacks that should run without keeping
+/// a handle around.
+#[must_use = "the idle callback is cancelled if the hook is dropped"]
+pub struct IdleHook {
+    hook: Option<Hook>,
+    hook_data: Box<IdleHookData>,
+}
+
+struct IdleHookData {
+    callback: Cell<Option<Box<dyn FnOnce(&Weechat)>>>,
+    name: &'static str,
+    finished: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for IdleHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        self.hook
+            .as_ref()
+            .filter(|_| !self.hook_data.finished.get())
+    }
+}
+
+impl HookSettings for IdleHook {}
+
+impl IdleHook {
+    /// Run a closure once, as soon as WeeChat is back in its main loop.
+    ///
+    /// The closure never runs inside of the callback that scheduled it, even
+    /// if it is scheduled from a timer.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `callback` - The closure that should be run.
+    pub fn new<C>(weechat: &Weechat, callback: C) -> IdleHook
+    where
+        C: FnOnce(&Weechat) + 'static,
+    {
+        weechat.new_idle_hook(Box::new(callback), std::any::type_name::<C>())
+    }
+
+    /// Has the callback run.
+    pub fn is_finished(&self) -> bool {
+        self.hook_data.finished.get()
+    }
+}
+
+impl Drop for IdleHook {
+    fn drop(&mut self) {
+        // WeeChat removes the timer itself after its last call.
+        if self.hook_data.finished.get() {
+            if let Some(hook) = self.hook.take() {
+                mem::forget(hook);
+            }
+        }
+    }
+}
+
+impl Weechat {
+    /// Run a callback once, as soon as WeeChat is back in its main loop.
+    ///
+    /// The callback never runs inside of the callback that scheduled it, even
+    /// if it is scheduled from a timer.
+    /// * `callback` - The function that should be run.
+    /// * `callback_data` - Data that will be passed to the callback. This data
+    ///     will be freed when the hook is dropped.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `IdleHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn on_idle<T>(
+        &self,
+        callback: fn(data: &T, weechat: &Weechat),
+        callback_data: Option<T>,
+    ) -> IdleHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_idle_hook(
+            Box::new(move |weechat| callback(&data, weechat)),
+            std::any::type_name::<T>(),
+        )
+    }
+
+    fn new_idle_hook(
+        &self,
+        callback: Box<dyn FnOnce(&Weechat)>,
+        name: &'static str,
+    ) -> IdleHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            _remaining: i32,
+        ) -> c_int {
+            let hook_data: &IdleHookData =
+                { &*(pointer as *const IdleHookData) };
+            hook_data.finished.set(true);
+
+            let name = hook_data.name;
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            if let Some(callback) = hook_data.callback.take() {
+                catch_panic(&weechat, HookKind::Timer, || {
+                    profile(
+                        HookKind::Timer,
+                        || name.to_owned(),
+                        || callback(&weechat),
+                    )
+                });
+            }
+
+            WEECHAT_RC_OK
+        }
+
+        let data = Box::new(IdleHookData {
+            callback: Cell::new(Some(callback)),
+            name,
+            finished: Cell::new(false),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_timer = self.get().hook_timer.unwrap();
+
+        // WeeChat doesn't accept timers with an interval of 0, a 1ms timer
+        // with a single call runs on the next iteration of the main loop.
+        let hook_ptr = unsafe {
+            hook_timer(
+                self.ptr,
+                1,
+                0,
+                1,
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        IdleHook {
+            hook: Some(hook),
+            hook_data,
+        }
+    }
+
+    /// Run a closure once after a delay.
+    ///
+    /// The timer removes itself after it fired, so unlike other hooks no
+    /// handle needs to be kept. If the plugin is unloaded before the delay
+    /// elapsed the timer is removed and the closure is dropped without being
+    /// called.
+    /// * `delay` - The time to wait before the closure is called, delays
+    ///     shorter than a millisecond run on the next iteration of the main
+    ///     loop.
+    /// * `callback` - The closure that should be called.
+    pub fn call_later(
+        &self,
+        delay: Duration,
+        callback: impl FnOnce(&Weechat) + 'static,
+    ) {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            _remaining: i32,
+        ) -> c_int {
+            let id = pointer as usize;
+            let call =
+                PENDING_CALLS.with(|calls| calls.borrow_mut().remove(&id));
+
+            if let Some(PendingCall { hook, callback }) = call {
+                let weechat = Weechat::from_ptr(hook.weechat_ptr);
+
+                // The timer only fires once and WeeChat removes it
+                // afterwards.
+                mem::forget(hook);
+
+                catch_panic(&weechat, HookKind::Timer, || {
+                    profile(
+                        HookKind::Timer,
+                        || "call_later".to_owned(),
+                        || callback(&weechat),
+                    )
+                });
+            }
+
+            WEECHAT_RC_OK
+        }
+
+        let id = NEXT_CALL_ID.with(|next| {
+            let id = next.get();
+            next.set(id.wrapping_add(1));
+            id
+        });
+
+        let hook_timer = self.get().hook_timer.unwrap();
+        let interval = delay.as_millis().clamp(1, i64::MAX as u128) as i64;
+
+        let hook_ptr = unsafe {
+            hook_timer(
+                self.ptr,
+                interval,
+                0,
+                1,
+                Some(c_hook_cb),
+                id as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+
+        // The callback will never run if the timer couldn't be created.
+        if hook_ptr.is_null() {
+            return;
+        }
+
+        let call = PendingCall {
+            hook: Hook {
+                ptr: hook_ptr,
+                weechat_ptr: self.ptr,
+            },
+            callback: Box::new(callback),
+        };
+
+        PENDING_CALLS.with(|calls| calls.borrow_mut().insert(id, call));
+    }
+}
@@ -0,0 +1,429 @@
+//! Info hooks allow plugins to provide information to other plugins, scripts
+//! and to `/eval`, e.g. `${info:myplugin_status}`.
+
+use libc::c_char;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::c_void;
+use std::ptr;
+
+use weechat_sys::{t_hashtable, t_weechat_plugin};
+
+use crate::hashtable::{hashtable_to_map, map_to_hashtable, Hashtable};
+use crate::hooks::{sealed, Hook, HookCallback, HookSettings, ToggleHook};
+use crate::profiling::profile;
+use crate::{HookKind, LossyCString, Weechat};
+
+/// Hook for an info, the info is removed when the object is dropped.
+///
+/// The info returns nothing while the hook is disabled.
+pub struct InfoHook {
+    hook: Hook,
+    hook_data: Box<InfoHookData>,
+}
+
+type InfoCallback = dyn FnMut(&Weechat, &str, &str) -> Option<String>;
+
+struct InfoHookData {
+    callback: HookCallback<InfoCallback>,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for InfoHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for InfoHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for InfoHook {}
+
+impl ToggleHook for InfoHook {}
+
+impl InfoHook {
+    /// Provide an info that can be queried with `info_get`.
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `name` - The name of the info, it should be prefixed with the name
+    ///     of the plugin.
+    /// * `description` - A description of the info.
+    /// * `args_description` - A description of the arguments the info
+    ///     accepts, can be empty.
+    /// * `callback` - A closure that will be called when the info is
+    ///     requested, it receives the name of the info and the arguments.
+    ///     The closure returns the value of the info or `None` if there is
+    ///     no value for the arguments.
+    pub fn new<C>(
+        weechat: &Weechat,
+        name: &str,
+        description: &str,
+        args_description: &str,
+        callback: C,
+    ) -> InfoHook
+    where
+        C: FnMut(&Weechat, &str, &str) -> Option<String> + 'static,
+    {
+        weechat.new_info_hook(
+            name,
+            description,
+            args_description,
+            Box::new(callback),
+        )
+    }
+}
+
+/// Hook for an info that returns a hashtable, the info is removed when the
+/// object is dropped.
+///
+/// The info returns nothing while the hook is disabled.
+pub struct InfoHashtableHook {
+    hook: Hook,
+    hook_data: Box<InfoHashtableHookData>,
+}
+
+type InfoHashtableCallback = dyn FnMut(
+    &Weechat,
+    &str,
+    &HashMap<String, String>,
+) -> Option<HashMap<String, String>>;
+
+struct InfoHashtableHookData {
+    callback: HookCallback<InfoHashtableCallback>,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for InfoHashtableHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for InfoHashtableHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for InfoHashtableHook {}
+
+impl ToggleHook for InfoHashtableHook {}
+
+impl InfoHashtableHook {
+    /// Provide an info that returns a hashtable, it can be queried with
+    /// `info_get_hashtable`.
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `name` - The name of the info, it should be prefixed with the name
+    ///     of the plugin.
+    /// * `description` - A description of the info.
+    /// * `args_description` - A description of the keys the input hashtable
+    ///     accepts.
+    /// * `output_description` - A description of the keys of the returned
+    ///     hashtable.
+    /// * `callback` - A closure that will be called when the info is
+    ///     requested, it receives the name of the info and the input
+    ///     hashtable. The closure returns the output hashtable or `None` if
+    ///     there is no value for the input.
+    pub fn new<C>(
+        weechat: &Weechat,
+        name: &str,
+        description: &str,
+        args_description: &str,
+        output_description: &str,
+        callback: C,
+    ) -> InfoHashtableHook
+    where
+        C: FnMut(
+                &Weechat,
+                &str,
+                &HashMap<String, String>,
+            ) -> Option<HashMap<String, String>>
+            + 'static,
+    {
+        weechat.new_info_hashtable_hook(
+            name,
+            description,
+            args_description,
+            output_description,
+            Box::new(callback),
+        )
+    }
+}
+
+impl Weechat {
+    /// Provide an info that can be queried with `info_get`.
+    ///
+    /// * `name` - The name of the info, it should be prefixed with the name
+    ///     of the plugin.
+    /// * `description` - A description of the info.
+    /// * `args_description` - A description of the arguments the info
+    ///     accepts, can be empty.
+    /// * `callback` - A function that will be called when the info is
+    ///     requested, it receives the name of the info and the arguments.
+    ///     The callback returns the value of the info or `None` if there is
+    ///     no value for the arguments.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is
+    ///     unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `InfoHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_info<T>(
+        &self,
+        name: &str,
+        description: &str,
+        args_description: &str,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            name: &str,
+            arguments: &str,
+        ) -> Option<String>,
+        callback_data: Option<T>,
+    ) -> InfoHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_info_hook(
+            name,
+            description,
+            args_description,
+            Box::new(move |weechat, name, arguments| {
+                callback(&data, weechat, name, arguments)
+            }),
+        )
+    }
+
+    fn new_info_hook(
+        &self,
+        name: &str,
+        description: &str,
+        args_description: &str,
+        callback: Box<InfoCallback>,
+    ) -> InfoHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            info_name: *const c_char,
+            arguments: *const c_char,
+        ) -> *mut c_char {
+            let hook_data: &InfoHookData =
+                { &*(pointer as *const InfoHookData) };
+
+            if !hook_data.enabled.get() {
+                return ptr::null_mut();
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let info_name = CStr::from_ptr(info_name).to_string_lossy();
+            let arguments = if arguments.is_null() {
+                Cow::Borrowed("")
+            } else {
+                CStr::from_ptr(arguments).to_string_lossy()
+            };
+
+            let value = hook_data
+                .callback
+                .call(&weechat, HookKind::Info, |callback| {
+                    profile(
+                        HookKind::Info,
+                        || info_name.clone().into_owned(),
+                        || callback(&weechat, &info_name, &arguments),
+                    )
+                })
+                .flatten();
+
+            match value {
+                // WeeChat frees the returned string, so it needs to be
+                // allocated with malloc.
+                Some(value) => {
+                    let value = LossyCString::new(value);
+                    libc::strdup(value.as_ptr())
+                }
+                None => ptr::null_mut(),
+            }
+        }
+
+        let data = Box::new(InfoHookData {
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_info = self.get().hook_info.unwrap();
+
+        let name = LossyCString::new(name);
+        let description = LossyCString::new(description);
+        let args_description = LossyCString::new(args_description);
+
+        let hook_ptr = unsafe {
+            hook_info(
+                self.ptr,
+                name.as_ptr(),
+                description.as_ptr(),
+                args_description.as_ptr(),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        InfoHook { hook, hook_data }
+    }
+
+    /// Provide an info that returns a hashtable, it can be queried with
+    /// `info_get_hashtable`.
+    ///
+    /// * `name` - The name of the info, it should be prefixed with the name
+    ///     of the plugin.
+    /// * `description` - A description of the info.
+    /// * `args_description` - A description of the keys the input hashtable
+    ///     accepts.
+    /// * `output_description` - A description of the keys of the returned
+    ///     hashtable.
+    /// * `callback` - A function that will be called when the info is
+    ///     requested, it receives the name of the info and the input
+    ///     hashtable. The callback returns the output hashtable or `None` if
+    ///     there is no value for the input.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is
+    ///     unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `InfoHashtableHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_info_hashtable<T>(
+        &self,
+        name: &str,
+        description: &str,
+        args_description: &str,
+        output_description: &str,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            name: &str,
+            input: &HashMap<String, String>,
+        ) -> Option<HashMap<String, String>>,
+        callback_data: Option<T>,
+    ) -> InfoHashtableHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_info_hashtable_hook(
+            name,
+            description,
+            args_description,
+            output_description,
+            Box::new(move |weechat, name, input| {
+                callback(&data, weechat, name, input)
+            }),
+        )
+    }
+
+    fn new_info_hashtable_hook(
+        &self,
+        name: &str,
+        description: &str,
+        args_description: &str,
+        output_description: &str,
+        callback: Box<InfoHashtableCallback>,
+    ) -> InfoHashtableHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            info_name: *const c_char,
+            hashtable: *mut t_hashtable,
+        ) -> *mut t_hashtable {
+            let hook_data: &InfoHashtableHookData =
+                { &*(pointer as *const InfoHashtableHookData) };
+
+            if !hook_data.enabled.get() {
+                return ptr::null_mut();
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let info_name = CStr::from_ptr(info_name).to_string_lossy();
+            let input = hashtable_to_map(&weechat, hashtable);
+
+            let output = hook_data
+                .callback
+                .call(&weechat, HookKind::InfoHashtable, |callback| {
+                    profile(
+                        HookKind::InfoHashtable,
+                        || info_name.clone().into_owned(),
+                        || callback(&weechat, &info_name, &input),
+                    )
+                })
+                .flatten();
+
+            // The caller of the info frees the returned hashtable.
+            output
+                .and_then(|output| map_to_hashtable(&weechat, &output))
+                .map_or(ptr::null_mut(), Hashtable::into_raw)
+        }
+
+        let data = Box::new(InfoHashtableHookData {
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_info_hashtable = self.get().hook_info_hashtable.unwrap();
+
+        let name = LossyCString::new(name);
+        let description = LossyCString::new(description);
+        let args_description = LossyCString::new(args_description);
+        let output_description = LossyCString::new(output_description);
+
+        let hook_ptr = unsafe {
+            hook_info_hashtable(
+                self.ptr,
+                name.as_ptr(),
+                description.as_ptr(),
+                args_description.as_ptr(),
+                output_description.as_ptr(),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        InfoHashtableHook { hook, hook_data }
+    }
+}
@@ -1,12 +1,21 @@
 //! Weechat Infolist module.
 
+use chrono::{DateTime, TimeZone, Utc};
+use libc::c_char;
+use std::cell::Cell;
 use std::ffi::CStr;
+use std::marker::PhantomData;
 use std::os::raw::c_void;
 use std::ptr;
 
-use weechat_sys::{t_gui_buffer, t_infolist, t_weechat_plugin};
+use weechat_sys::{
+    t_gui_buffer, t_infolist, t_infolist_item, t_weechat_plugin,
+};
 
-use crate::{Buffer, LossyCString, Weechat};
+use crate::hdata::HDataPointer;
+use crate::hooks::{sealed, Hook, HookCallback, HookSettings, ToggleHook};
+use crate::profiling::profile;
+use crate::{Buffer, HookKind, LossyCString, Weechat};
 use std::borrow::Cow;
 
 /// Weechat Infolist type.
@@ -95,7 +104,7 @@ impl Infolist {
         }
     }
 
-    fn get_pointer(&self, name: &str) -> *mut c_void {
+    pub(crate) fn get_pointer(&self, name: &str) -> *mut c_void {
         let weechat = Weechat::from_ptr(self.weechat_ptr);
         let infolist_pointer = weechat.get().infolist_pointer.unwrap();
 
@@ -112,7 +121,61 @@ impl Infolist {
         if ptr.is_null() {
             None
         } else {
-            Some(Buffer::from_ptr(self.weechat_ptr, ptr as *mut t_gui_buffer))
+            Some(Buffer::wrap(self.weechat_ptr, ptr as *mut t_gui_buffer))
+        }
+    }
+
+    /// Get the value of an integer variable in the current infolist item.
+    ///
+    /// Returns 0 if the item doesn't have such a variable.
+    /// * `name` - The variable name of the infolist item.
+    pub fn get_integer(&self, name: &str) -> i32 {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let infolist_integer = weechat.get().infolist_integer.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe { infolist_integer(self.ptr, name.as_ptr()) }
+    }
+
+    /// Get the content of a buffer variable in the current infolist item, e.g.
+    /// a C struct.
+    pub(crate) fn get_bytes(&self, name: &str) -> Option<&[u8]> {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let infolist_buffer = weechat.get().infolist_buffer.unwrap();
+
+        let name = LossyCString::new(name);
+        let mut size = 0;
+
+        unsafe {
+            let ret = infolist_buffer(self.ptr, name.as_ptr(), &mut size);
+            if ret.is_null() || size < 0 {
+                None
+            } else {
+                Some(std::slice::from_raw_parts(
+                    ret as *const u8,
+                    size as usize,
+                ))
+            }
+        }
+    }
+
+    /// Get the value of a time variable in the current infolist item.
+    ///
+    /// Returns None if the item doesn't have such a variable.
+    /// * `name` - The variable name of the infolist item.
+    pub fn get_time(&self, name: &str) -> Option<DateTime<Utc>> {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let infolist_time = weechat.get().infolist_time.unwrap();
+
+        let name = LossyCString::new(name);
+
+        let time = unsafe { infolist_time(self.ptr, name.as_ptr()) };
+
+        if time == 0 {
+            None
+        } else {
+            Utc.timestamp_opt(time as _, 0).single()
         }
     }
 
@@ -134,3 +197,385 @@ impl Infolist {
         }
     }
 }
+
+/// An infolist that is being built by the callback of an `InfolistHook`.
+pub struct InfolistBuilder {
+    ptr: *mut t_infolist,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+/// An item of an infolist that is being built, variables are added to the
+/// item with the builder methods.
+pub struct InfolistItem<'a> {
+    ptr: *mut t_infolist_item,
+    weechat_ptr: *mut t_weechat_plugin,
+    infolist: PhantomData<&'a InfolistBuilder>,
+}
+
+impl InfolistBuilder {
+    /// Add a new item to the infolist.
+    ///
+    /// Returns None if the item couldn't be created.
+    pub fn new_item(&self) -> Option<InfolistItem> {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let infolist_new_item = weechat.get().infolist_new_item.unwrap();
+
+        let ptr = unsafe { infolist_new_item(self.ptr) };
+
+        if ptr.is_null() {
+            None
+        } else {
+            Some(InfolistItem {
+                ptr,
+                weechat_ptr: self.weechat_ptr,
+                infolist: PhantomData,
+            })
+        }
+    }
+}
+
+impl InfolistItem<'_> {
+    /// Add an integer variable to the item.
+    pub fn integer(&self, name: &str, value: i32) -> &Self {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let new_var_integer = weechat.get().infolist_new_var_integer.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe {
+            new_var_integer(self.ptr, name.as_ptr(), value);
+        }
+
+        self
+    }
+
+    /// Add a string variable to the item.
+    pub fn string(&self, name: &str, value: &str) -> &Self {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let new_var_string = weechat.get().infolist_new_var_string.unwrap();
+
+        let name = LossyCString::new(name);
+        let value = LossyCString::new(value);
+
+        unsafe {
+            new_var_string(self.ptr, name.as_ptr(), value.as_ptr());
+        }
+
+        self
+    }
+
+    fn pointer(&self, name: &str, value: *mut c_void) -> &Self {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let new_var_pointer = weechat.get().infolist_new_var_pointer.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe {
+            new_var_pointer(self.ptr, name.as_ptr(), value);
+        }
+
+        self
+    }
+
+    /// Add the pointer of a buffer to the item, WeeChat names this variable
+    /// `buffer` in its own infolists.
+    pub fn buffer(&self, name: &str, buffer: &Buffer) -> &Self {
+        self.pointer(name, buffer.ptr as *mut c_void)
+    }
+
+    /// Add a pointer that was read from hdata to the item, e.g. the pointer
+    /// of a line that consumers of the infolist can pass to other API calls.
+    pub fn hdata_pointer(&self, name: &str, pointer: &HDataPointer) -> &Self {
+        self.pointer(name, pointer.ptr)
+    }
+
+    /// Add a time variable to the item, e.g. the date of a line.
+    pub fn time(&self, name: &str, value: DateTime<Utc>) -> &Self {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let new_var_time = weechat.get().infolist_new_var_time.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe {
+            new_var_time(self.ptr, name.as_ptr(), value.timestamp() as _);
+        }
+
+        self
+    }
+
+    /// Add a buffer variable to the item, the bytes are copied into the
+    /// infolist.
+    ///
+    /// Buffer variables are only readable by C code, e.g. to pass a struct.
+    pub fn bytes(&self, name: &str, value: &[u8]) -> &Self {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let new_var_buffer = weechat.get().infolist_new_var_buffer.unwrap();
+
+        let name = LossyCString::new(name);
+        let size = value.len().min(i32::MAX as usize) as i32;
+
+        unsafe {
+            new_var_buffer(
+                self.ptr,
+                name.as_ptr(),
+                value.as_ptr() as *mut c_void,
+                size,
+            );
+        }
+
+        self
+    }
+}
+
+/// Hook for an infolist, the infolist is removed when the object is dropped.
+///
+/// The infolist is empty while the hook is disabled.
+pub struct InfolistHook {
+    hook: Hook,
+    hook_data: Box<InfolistHookData>,
+}
+
+type InfolistCallback =
+    dyn FnMut(&Weechat, *mut c_void, &str, &InfolistBuilder) -> bool;
+
+struct InfolistHookData {
+    callback: HookCallback<InfolistCallback>,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for InfolistHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for InfolistHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for InfolistHook {}
+
+impl ToggleHook for InfolistHook {}
+
+impl InfolistHook {
+    /// Provide an infolist that can be queried with `infolist_get`.
+    ///
+    /// ```ignore
+    /// let hook = InfolistHook::new(
+    ///     &weechat,
+    ///     "myplugin_rooms",
+    ///     "list of rooms",
+    ///     "",
+    ///     "",
+    ///     move |_, _pointer, _arguments, infolist| {
+    ///         for room in rooms.borrow().iter() {
+    ///             if let Some(item) = infolist.new_item() {
+    ///                 item.string("name", &room.name)
+    ///                     .integer("members", room.members)
+    ///                     .buffer("buffer", &room.buffer)
+    ///                     .time("last_activity", room.last_activity);
+    ///             }
+    ///         }
+    ///         true
+    ///     },
+    /// );
+    /// ```
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `name` - The name of the infolist, it should be prefixed with the
+    ///     name of the plugin.
+    /// * `description` - A description of the infolist.
+    /// * `pointer_description` - A description of the pointer that can be
+    ///     passed when the infolist is requested, can be empty.
+    /// * `args_description` - A description of the arguments the infolist
+    ///     accepts, can be empty.
+    /// * `callback` - A closure that will be called when the infolist is
+    ///     requested, it receives the pointer and the arguments that were
+    ///     passed and adds the items to the infolist. The closure returns
+    ///     false if no infolist should be returned.
+    pub fn new<C>(
+        weechat: &Weechat,
+        name: &str,
+        description: &str,
+        pointer_description: &str,
+        args_description: &str,
+        callback: C,
+    ) -> InfolistHook
+    where
+        C: FnMut(&Weechat, *mut c_void, &str, &InfolistBuilder) -> bool
+            + 'static,
+    {
+        weechat.new_infolist_hook(
+            name,
+            description,
+            pointer_description,
+            args_description,
+            Box::new(callback),
+        )
+    }
+}
+
+impl Weechat {
+    /// Provide an infolist that can be queried with `infolist_get`.
+    ///
+    /// * `name` - The name of the infolist, it should be prefixed with the
+    ///     name of the plugin.
+    /// * `description` - A description of the infolist.
+    /// * `pointer_description` - A description of the pointer that can be
+    ///     passed when the infolist is requested, can be empty.
+    /// * `args_description` - A description of the arguments the infolist
+    ///     accepts, can be empty.
+    /// * `callback` - A function that will be called when the infolist is
+    ///     requested, it receives the pointer and the arguments that were
+    ///     passed and adds the items to the infolist. The callback returns
+    ///     false if no infolist should be returned.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is
+    ///     unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `InfolistHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_infolist<T>(
+        &self,
+        name: &str,
+        description: &str,
+        pointer_description: &str,
+        args_description: &str,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            pointer: *mut c_void,
+            arguments: &str,
+            infolist: &InfolistBuilder,
+        ) -> bool,
+        callback_data: Option<T>,
+    ) -> InfolistHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_infolist_hook(
+            name,
+            description,
+            pointer_description,
+            args_description,
+            Box::new(move |weechat, pointer, arguments, infolist| {
+                callback(&data, weechat, pointer, arguments, infolist)
+            }),
+        )
+    }
+
+    fn new_infolist_hook(
+        &self,
+        name: &str,
+        description: &str,
+        pointer_description: &str,
+        args_description: &str,
+        callback: Box<InfolistCallback>,
+    ) -> InfolistHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            infolist_name: *const c_char,
+            obj_pointer: *mut c_void,
+            arguments: *const c_char,
+        ) -> *mut t_infolist {
+            let hook_data: &InfolistHookData =
+                { &*(pointer as *const InfolistHookData) };
+
+            if !hook_data.enabled.get() {
+                return ptr::null_mut();
+            }
+
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let infolist_new = weechat.get().infolist_new.unwrap();
+            let infolist_free = weechat.get().infolist_free.unwrap();
+
+            let infolist_name = CStr::from_ptr(infolist_name).to_string_lossy();
+            let arguments = if arguments.is_null() {
+                Cow::Borrowed("")
+            } else {
+                CStr::from_ptr(arguments).to_string_lossy()
+            };
+
+            let infolist = InfolistBuilder {
+                ptr: infolist_new(hook_data.weechat_ptr),
+                weechat_ptr: hook_data.weechat_ptr,
+            };
+
+            if infolist.ptr.is_null() {
+                return ptr::null_mut();
+            }
+
+            let filled = hook_data
+                .callback
+                .call(&weechat, HookKind::Infolist, |callback| {
+                    profile(
+                        HookKind::Infolist,
+                        || infolist_name.clone().into_owned(),
+                        || {
+                            callback(
+                                &weechat,
+                                obj_pointer,
+                                &arguments,
+                                &infolist,
+                            )
+                        },
+                    )
+                })
+                .unwrap_or(false);
+
+            // The caller of the infolist frees it.
+            if filled {
+                infolist.ptr
+            } else {
+                infolist_free(infolist.ptr);
+                ptr::null_mut()
+            }
+        }
+
+        let data = Box::new(InfolistHookData {
+            callback: HookCallback::new(callback),
+            enabled: Cell::new(true),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_infolist = self.get().hook_infolist.unwrap();
+
+        let name = LossyCString::new(name);
+        let description = LossyCString::new(description);
+        let pointer_description = LossyCString::new(pointer_description);
+        let args_description = LossyCString::new(args_description);
+
+        let hook_ptr = unsafe {
+            hook_infolist(
+                self.ptr,
+                name.as_ptr(),
+                description.as_ptr(),
+                pointer_description.as_ptr(),
+                args_description.as_ptr(),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        InfolistHook { hook, hook_data }
+    }
+}
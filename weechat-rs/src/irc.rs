@@ -0,0 +1,110 @@
+//! Helpers for plugins that extend the IRC plugin.
+
+use std::collections::HashMap;
+
+use crate::Weechat;
+
+/// An IRC message that was parsed by the IRC plugin.
+///
+/// Positions are byte offsets into `message_without_tags`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IrcMessage {
+    /// The IRCv3 message tags, the values are unescaped.
+    pub tags: HashMap<String, String>,
+    /// The message without the tags.
+    pub message_without_tags: String,
+    /// The nick of the sender.
+    pub nick: Option<String>,
+    /// The user name of the sender.
+    pub user: Option<String>,
+    /// The host of the sender.
+    pub host: Option<String>,
+    /// The command, e.g. `PRIVMSG`.
+    pub command: String,
+    /// The target channel of the message.
+    pub channel: Option<String>,
+    /// The arguments of the command, including the channel.
+    pub arguments: String,
+    /// The text of the message, e.g. the message a user sent.
+    pub text: String,
+    /// The parameters of the command.
+    pub params: Vec<String>,
+    /// The position of the command in the message.
+    pub pos_command: Option<usize>,
+    /// The position of the arguments in the message.
+    pub pos_arguments: Option<usize>,
+    /// The position of the channel in the message.
+    pub pos_channel: Option<usize>,
+    /// The position of the text in the message.
+    pub pos_text: Option<usize>,
+}
+
+impl IrcMessage {
+    fn from_map(mut map: HashMap<String, String>) -> IrcMessage {
+        let mut take = |key: &str| map.remove(key).unwrap_or_default();
+
+        let non_empty = |value: String| {
+            if value.is_empty() {
+                None
+            } else {
+                Some(value)
+            }
+        };
+
+        let position = |value: String| value.parse::<usize>().ok();
+
+        let num_params = take("num_params").parse::<usize>().unwrap_or(0);
+
+        let mut message = IrcMessage {
+            message_without_tags: take("message_without_tags"),
+            nick: non_empty(take("nick")),
+            user: non_empty(take("user")),
+            host: non_empty(take("host")),
+            command: take("command"),
+            channel: non_empty(take("channel")),
+            arguments: take("arguments"),
+            text: take("text"),
+            params: (1..=num_params)
+                .map(|i| take(&format!("param{}", i)))
+                .collect(),
+            pos_command: position(take("pos_command")),
+            pos_arguments: position(take("pos_arguments")),
+            pos_channel: position(take("pos_channel")),
+            pos_text: position(take("pos_text")),
+            ..Default::default()
+        };
+
+        message.tags = map
+            .into_iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix("tag_").map(|k| (k.to_owned(), value))
+            })
+            .collect();
+
+        message
+    }
+}
+
+impl Weechat {
+    /// Parse an IRC message using the IRC plugin.
+    /// * `message` - The raw IRC message.
+    /// * `server` - The name of the server the message was received from,
+    ///     this is used to get the channel types of the server. Can be None.
+    ///
+    /// Returns None if the IRC plugin isn't loaded.
+    pub fn parse_irc_message(
+        &self,
+        message: &str,
+        server: Option<&str>,
+    ) -> Option<IrcMessage> {
+        let mut arguments = HashMap::new();
+        arguments.insert("message", message);
+
+        if let Some(server) = server {
+            arguments.insert("server", server);
+        }
+
+        self.info_get_hashtable("irc_message_parse", &arguments)
+            .map(IrcMessage::from_map)
+    }
+}
@@ -0,0 +1,136 @@
+//! Capture the next key combo the user presses, e.g. for "press a key to
+//! bind" configuration commands.
+//!
+//! WeeChat sends a `key_combo_*` signal for every key that is added to the
+//! current key combo. Keys like `meta-a` consist of multiple keys, a combo is
+//! considered complete once no key was pressed for a short delay, the same
+//! way the `/input grab_key` command works.
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use crate::{ReturnCode, SignalHook, SignalHookValue, Weechat};
+
+/// A grab of the next key combo.
+///
+/// While the grab is active the pressed keys aren't processed by WeeChat.
+/// The grab stops once a key combo was captured, the hook of the grab is
+/// removed when the object is dropped.
+pub struct KeyGrab {
+    state: Rc<RefCell<KeyGrabState>>,
+    _signal: SignalHook,
+}
+
+#[derive(Default)]
+struct KeyGrabState {
+    callback: Option<Box<dyn FnOnce(&Weechat, &str)>>,
+    delay: Duration,
+    combo: String,
+    /// The number of keys pressed so far, a timer only completes the combo
+    /// if no key was pressed after it was started.
+    keys: u64,
+    done: bool,
+}
+
+impl KeyGrab {
+    /// Has a key combo been captured.
+    pub fn is_done(&self) -> bool {
+        self.state.borrow().done
+    }
+
+    /// Stop the grab without capturing a key combo, the callback won't be
+    /// called anymore.
+    pub fn cancel(&self) {
+        let mut state = self.state.borrow_mut();
+        state.done = true;
+        state.callback = None;
+    }
+}
+
+impl Weechat {
+    /// Capture the next key combo that the user presses.
+    ///
+    /// * `delay` - The time without a key press after which the key combo is
+    ///     considered complete. WeeChat uses 500 milliseconds for the
+    ///     `/input grab_key` command.
+    /// * `callback` - A closure that will be called with the raw key combo
+    ///     once it's complete, e.g. `\x01[a` for `meta-a`.
+    pub fn grab_key(
+        &self,
+        delay: Duration,
+        callback: impl FnOnce(&Weechat, &str) + 'static,
+    ) -> KeyGrab {
+        fn signal_cb(
+            state: &Rc<RefCell<KeyGrabState>>,
+            weechat: &Weechat,
+            value: SignalHookValue,
+        ) -> ReturnCode {
+            let (keys, delay) = {
+                let mut state = state.borrow_mut();
+
+                if state.done {
+                    return ReturnCode::Ok;
+                }
+
+                if let SignalHookValue::String(key) = value {
+                    state.combo.push_str(&key);
+                }
+                state.keys += 1;
+
+                (state.keys, state.delay)
+            };
+
+            // The timer only holds a weak reference, dropping the grab
+            // cancels it.
+            let state: Weak<RefCell<KeyGrabState>> = Rc::downgrade(state);
+            weechat.call_later(delay, move |weechat| {
+                if let Some(state) = state.upgrade() {
+                    timer_cb(&state, weechat, keys);
+                }
+            });
+
+            ReturnCode::OkEat
+        }
+
+        fn timer_cb(
+            state: &Rc<RefCell<KeyGrabState>>,
+            weechat: &Weechat,
+            keys: u64,
+        ) {
+            let (callback, combo) = {
+                let mut state = state.borrow_mut();
+
+                // Another key was pressed since the timer was started, the
+                // timer of that key completes the combo.
+                if state.done || state.keys != keys {
+                    return;
+                }
+
+                state.done = true;
+                (state.callback.take(), std::mem::take(&mut state.combo))
+            };
+
+            if let Some(callback) = callback {
+                callback(weechat, &combo);
+            }
+        }
+
+        let state = Rc::new(RefCell::new(KeyGrabState {
+            callback: Some(Box::new(callback)),
+            delay,
+            ..Default::default()
+        }));
+
+        let signal_state = state.clone();
+        let signal =
+            SignalHook::new(self, "key_combo_*", move |weechat, value| {
+                signal_cb(&signal_state, weechat, value)
+            });
+
+        KeyGrab {
+            state,
+            _signal: signal,
+        }
+    }
+}
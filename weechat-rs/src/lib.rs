@@ -1,38 +1,128 @@
 #![warn(missing_docs)]
 
+pub mod arg_parser;
 pub mod bar;
 pub mod buffer;
+pub mod color;
+pub mod command_router;
 pub mod completion;
+pub mod completion_template;
 pub mod config;
 pub mod config_options;
+pub mod config_snapshot;
+pub mod connect;
+pub mod debounce;
+pub mod diagnostics;
+#[cfg(feature = "async")]
+pub mod executor;
+pub mod export;
+pub mod focus;
+pub mod free_buffer;
+pub mod gui;
 pub mod hashtable;
 pub mod hdata;
+pub mod history;
+pub mod hook_registry;
 pub mod hooks;
+pub mod hotlist;
+pub mod i18n;
+pub mod idle;
+pub mod info;
 pub mod infolist;
+pub mod irc;
+pub mod key_grab;
+pub mod line;
+pub mod line_hook;
+pub mod mouse;
+pub mod namespace;
+pub mod notify;
+pub mod options_buffer;
+pub mod owned_buffer;
 pub mod plugin;
+pub mod prelude;
+pub mod print_metrics;
+pub mod print_queue;
+pub mod process;
+pub mod profiling;
+pub mod proxy;
+pub mod secure;
+pub mod strings;
+pub mod styled;
+pub mod transfer;
 pub mod weechat;
+pub mod window;
 
 pub use weechat_macro::weechat_plugin;
 
 pub use plugin::{WeechatPlugin, WeechatResult};
-pub use weechat::{ArgsWeechat, OptionChanged, Weechat};
+pub use weechat::{ArgsWeechat, NotSupported, OptionChanged, Weechat};
 
-pub use buffer::{Buffer, Nick, NickArgs};
+pub use arg_parser::{ArgParser, FromArgs, ParsedArgs, UsageError};
+pub use buffer::{
+    Buffer, BufferBuilder, BufferInput, InputKind, Nick, NickArgs,
+};
+pub use color::TextFormat;
+pub use command_router::CommandRouter;
 
-pub use config::{Config, ConfigSection, ConfigSectionInfo};
+#[cfg(feature = "async")]
+pub use config::ConfigReloads;
+pub use config::{Config, ConfigSection, ConfigSectionInfo, ConfigWatcher};
 pub use config_options::{
-    BooleanOption, ColorOption, ConfigOption, IntegerOption, StringOption,
+    inherit_from, BooleanOption, ColorOption, ConfigOption, IntegerOption,
+    StringOption,
 };
+pub use config_snapshot::{ConfigSnapshot, OptionValue};
 
 pub use hooks::{
-    CommandDescription, CommandHook, CommandRunHook, FdHook, FdHookMode,
-    SignalHook, SignalHookValue, TimerHook,
+    ArgSpec, CommandDescription, CommandExists, CommandHook, CommandResult,
+    CommandRunHook, CompletionCallback, ConfigHook, FdHook, FdHookMode,
+    ForeignHook, HookKind, HookSettings, HsignalHook, ModifierHook, PrintHook,
+    PrintedLine, SignalData, SignalHook, SignalHookValue, TimerHook,
+    ToggleHook,
 };
 
-pub use completion::{Completion, CompletionHook, CompletionPosition};
+pub use completion::{
+    Completion, CompletionHook, CompletionOptions, CompletionPosition,
+    LocalvarCompletion, OptionValueCompletion, PluginBufferCompletion,
+};
+pub use completion_template::{CompletionArg, CompletionTemplate};
+pub use connect::{ConnectError, ConnectHook, ConnectOptions, Connection};
+pub use debounce::{Debouncer, Throttle};
+#[cfg(feature = "async")]
+pub use executor::Task;
+pub use export::{StateExport, StateExportHooks};
+pub use focus::{FocusHook, FocusInfo};
+pub use free_buffer::FreeBufferView;
+pub use gui::GuiObject;
 pub use hashtable::{Hashtable, HashtableItemType};
 pub use hdata::HasHData;
-pub use infolist::Infolist;
+pub use history::InputHistory;
+pub use hook_registry::HookRegistry;
+pub use hotlist::{Hotlist, HotlistEntry};
+pub use idle::IdleHook;
+pub use info::{InfoHashtableHook, InfoHook};
+pub use infolist::{Infolist, InfolistBuilder, InfolistHook, InfolistItem};
+pub use irc::IrcMessage;
+pub use key_grab::KeyGrab;
+pub use line::{Line, SearchDirection};
+pub use line_hook::{LineChanges, LineData, LineHook};
+pub use mouse::{
+    DragDirection, MouseArea, MouseButton, MouseEvent, MouseEventKind,
+    MouseHook,
+};
+pub use namespace::{NameKind, NameTaken};
+pub use notify::{Notification, Notifier};
+pub use options_buffer::OptionsBuffer;
+pub use owned_buffer::{BufferRef, OwnedBuffer};
+pub use print_queue::{Overflow, PrintQueue};
+#[cfg(feature = "async")]
+pub use process::ProcessFuture;
+pub use process::{ProcessCommand, ProcessHook, ProcessOutput, ProcessStatus};
+pub use proxy::{Proxy, ProxyType};
+pub use strings::{CaseRange, DynString};
+pub use styled::StyledText;
+pub use transfer::Transfer;
+pub use window::Window;
 
 use std::ffi::CString;
 
@@ -43,6 +133,26 @@ pub enum ReturnCode {
     Error = weechat_sys::WEECHAT_RC_ERROR as isize,
 }
 
+impl ReturnCode {
+    pub(crate) fn from_int(v: i32) -> ReturnCode {
+        match v {
+            weechat_sys::WEECHAT_RC_OK => ReturnCode::Ok,
+            weechat_sys::WEECHAT_RC_OK_EAT => ReturnCode::OkEat,
+            _ => ReturnCode::Error,
+        }
+    }
+}
+
+/// Free the state the library keeps for the plugin, called by the
+/// `weechat_plugin!` macro after the plugin object has been dropped.
+#[doc(hidden)]
+pub fn plugin_end() {
+    diagnostics::shutdown();
+    idle::shutdown();
+    #[cfg(feature = "async")]
+    executor::shutdown();
+}
+
 pub(crate) struct LossyCString;
 
 impl LossyCString {
@@ -0,0 +1,416 @@
+//! Weechat Line module containing the Line type.
+
+use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+use std::os::raw::c_void;
+use weechat_sys::t_weechat_plugin;
+
+use crate::hdata::{HData, HDataPointer, HasHData};
+use crate::{Buffer, ConfigOption, LossyCString, Weechat};
+
+/// A handle to a single line of a buffer.
+///
+/// The line isn't removed if the handle is dropped. A handle can be obtained
+/// by printing with one of the `Buffer::print_*_line` methods or with
+/// `Buffer::last_line`.
+pub struct Line {
+    pub(crate) weechat_ptr: *mut t_weechat_plugin,
+    pub(crate) ptr: *mut c_void,
+}
+
+impl PartialEq for Line {
+    fn eq(&self, other: &Line) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl Line {
+    /// Get the `line_data` hdata of the line, this is where all the line
+    /// fields live.
+    fn data(&self) -> Option<HData> {
+        let line = self.get_hdata("line")?;
+        let data: HDataPointer = line.get_var("data")?;
+
+        if data.ptr.is_null() {
+            None
+        } else {
+            data.get_hdata("line_data")
+        }
+    }
+
+    /// Get the date of the line.
+    pub fn date(&self) -> Option<DateTime<Utc>> {
+        self.data()?.get_var("date")
+    }
+
+    /// Get the prefix of the line, the prefix may contain color codes.
+    pub fn prefix(&self) -> Option<String> {
+        self.data()?.get_var("prefix")
+    }
+
+    /// Get the message of the line, the message may contain color codes.
+    pub fn message(&self) -> Option<String> {
+        self.data()?.get_var("message")
+    }
+
+    /// Get the list of tags of the line.
+    pub fn tags(&self) -> Vec<String> {
+        let data = match self.data() {
+            Some(d) => d,
+            None => return Vec::new(),
+        };
+
+        let count: i32 = data.get_var("tags_count").unwrap_or_default();
+
+        (0..count)
+            .filter_map(|i| unsafe {
+                data.get_string_unchecked(&format!("{}|tags_array", i))
+                    .map(Cow::into_owned)
+            })
+            .collect()
+    }
+
+    /// Set the date of the line.
+    pub fn set_date(&self, date: DateTime<Utc>) {
+        if let Some(data) = self.data() {
+            data.update_var("date", date);
+        }
+    }
+
+    /// Set the prefix of the line.
+    pub fn set_prefix(&self, prefix: &str) {
+        if let Some(data) = self.data() {
+            data.update_var("prefix", prefix.to_owned());
+        }
+    }
+
+    /// Set the message of the line.
+    pub fn set_message(&self, message: &str) {
+        if let Some(data) = self.data() {
+            data.update_var("message", message.to_owned());
+        }
+    }
+
+    /// Delete the line.
+    ///
+    /// Only the lines of buffers with free content can be deleted, WeeChat
+    /// removes them when an empty message is printed on their position.
+    /// WeeChat has no API to remove a single line of a formatted buffer, use
+    /// `set_message` to replace its content instead.
+    ///
+    /// Returns false if the line couldn't be deleted.
+    pub fn delete(self) -> bool {
+        let data = match self.data() {
+            Some(data) => data,
+            None => return false,
+        };
+
+        let buffer = data
+            .get_var::<HDataPointer>("buffer")
+            .and_then(|buffer| Buffer::from_hdata_pointer(&buffer));
+        let y: Option<i32> = data.get_var("y");
+
+        let (buffer, y) = match (buffer, y) {
+            (Some(buffer), Some(y)) if y >= 0 => (buffer, y),
+            _ => return false,
+        };
+
+        if !buffer.has_free_content() {
+            return false;
+        }
+
+        buffer.print_y(y, "");
+        true
+    }
+
+    /// Replace the tags of the line.
+    /// * `tags` - The new tags of the line, an empty slice removes all
+    ///     the tags.
+    pub fn set_tags(&self, tags: &[&str]) {
+        if let Some(data) = self.data() {
+            data.update_var("tags_array", tags.join(","));
+        }
+    }
+}
+
+impl HasHData for Line {
+    fn get_hdata(&self, name: &str) -> Option<HData> {
+        let hdata_get =
+            Weechat::from_ptr(self.weechat_ptr).get().hdata_get.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe {
+            let hdata = hdata_get(self.weechat_ptr, name.as_ptr());
+            if hdata.is_null() {
+                None
+            } else {
+                Some(HData {
+                    weechat_ptr: self.weechat_ptr,
+                    object: self.ptr,
+                    ptr: hdata,
+                })
+            }
+        }
+    }
+}
+
+/// The direction in which the lines of a buffer are searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchDirection {
+    /// Start with the oldest line.
+    Forward,
+    /// Start with the newest line.
+    Backward,
+}
+
+impl Weechat {
+    /// Get the maximum number of lines WeeChat keeps per buffer, older lines
+    /// are removed once a buffer contains more lines.
+    ///
+    /// WeeChat has no per buffer limit, plugins that print a lot of lines
+    /// can apply their own limit with `Buffer::prune_to`.
+    ///
+    /// Returns None if the number of lines isn't limited.
+    pub fn max_buffer_lines(&self) -> Option<usize> {
+        let option =
+            self.get_integer_option("weechat.history.max_buffer_lines_number")?;
+
+        match option.value() {
+            lines if lines > 0 => Some(lines as usize),
+            _ => None,
+        }
+    }
+}
+
+impl Buffer {
+    /// Get the last line of the buffer.
+    ///
+    /// Returns None if the buffer doesn't contain any lines.
+    pub fn last_line(&self) -> Option<Line> {
+        self.edge_line("last_line")
+    }
+
+    /// Get the first line of the buffer.
+    ///
+    /// Returns None if the buffer doesn't contain any lines.
+    pub fn first_line(&self) -> Option<Line> {
+        self.edge_line("first_line")
+    }
+
+    fn edge_line(&self, edge: &str) -> Option<Line> {
+        let buffer = self.get_hdata("buffer")?;
+        let lines: HDataPointer = buffer.get_var("own_lines")?;
+
+        if lines.ptr.is_null() {
+            return None;
+        }
+
+        let line: HDataPointer = lines.get_hdata("lines")?.get_var(edge)?;
+
+        if line.ptr.is_null() {
+            None
+        } else {
+            Some(Line {
+                weechat_ptr: self.weechat,
+                ptr: line.ptr,
+            })
+        }
+    }
+
+    /// Search the lines of the buffer.
+    ///
+    /// The lines are visited one by one and the search stops as soon as
+    /// `limit` lines were found, the lines aren't copied out of WeeChat.
+    /// * `predicate` - A function that decides if a line matches, e.g. by
+    ///     looking at its tags or message.
+    /// * `limit` - The maximum number of lines that should be returned, None
+    ///     to return all the matching lines.
+    /// * `direction` - The direction the lines are searched in.
+    ///
+    /// Returns the matching lines in the order they were found.
+    pub fn find_lines<F>(
+        &self,
+        mut predicate: F,
+        limit: Option<usize>,
+        direction: SearchDirection,
+    ) -> Vec<Line>
+    where
+        F: FnMut(&Line) -> bool,
+    {
+        let mut found = Vec::new();
+
+        if limit == Some(0) {
+            return found;
+        }
+
+        let (mut line, step) = match direction {
+            SearchDirection::Forward => (self.first_line(), 1),
+            SearchDirection::Backward => (self.last_line(), -1),
+        };
+
+        while let Some(current) = line {
+            line = current.get_hdata("line").and_then(|hdata| {
+                let pointer = HDataPointer {
+                    ptr: current.ptr,
+                    weechat: self.weechat,
+                };
+                pointer.advance(&hdata, step).map(|next| Line {
+                    weechat_ptr: self.weechat,
+                    ptr: next.ptr,
+                })
+            });
+
+            if predicate(&current) {
+                found.push(current);
+
+                if Some(found.len()) == limit {
+                    break;
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Does the buffer have free content, the lines of such a buffer are
+    /// addressed by their position instead of being appended.
+    pub fn has_free_content(&self) -> bool {
+        // The type is 0 for formatted buffers and 1 for free ones.
+        self.get_hdata("buffer")
+            .and_then(|buffer| buffer.get_var::<i32>("type"))
+            == Some(1)
+    }
+
+    /// Get the number of lines that were printed to this buffer.
+    ///
+    /// WeeChat removes the oldest lines once the limits of the
+    /// `weechat.history.max_buffer_lines_*` options are reached, see
+    /// `Weechat::max_buffer_lines`.
+    pub fn own_lines_count(&self) -> usize {
+        self.lines_count_of("own_lines")
+    }
+
+    /// Get the number of lines that are displayed in this buffer, for merged
+    /// buffers this includes the lines of all the merged buffers.
+    pub fn lines_count(&self) -> usize {
+        self.lines_count_of("lines")
+    }
+
+    /// Remove lines of the buffer until at most `max_lines` lines are left.
+    ///
+    /// For buffers with free content the lines at a position of `max_lines`
+    /// or greater are removed. For formatted buffers the oldest lines are
+    /// removed: WeeChat can't remove a single line of a formatted buffer, so
+    /// the buffer is cleared and the newest lines are printed again with
+    /// their date, tags, prefix and message. The printed lines are tagged
+    /// with `no_log`, `notify_none` and `no_highlight` since they were
+    /// already logged and notified when they were first printed.
+    /// * `max_lines` - The maximum number of lines the buffer should keep.
+    ///
+    /// Returns the number of removed lines.
+    pub fn prune_to(&self, max_lines: usize) -> usize {
+        if self.has_free_content() {
+            let lines = self.find_lines(
+                |line| {
+                    line.data()
+                        .and_then(|data| data.get_var::<i32>("y"))
+                        .is_some_and(|y| y >= 0 && y as usize >= max_lines)
+                },
+                None,
+                SearchDirection::Backward,
+            );
+
+            return lines
+                .into_iter()
+                .map(Line::delete)
+                .filter(|deleted| *deleted)
+                .count();
+        }
+
+        let count = self.own_lines_count();
+
+        if count <= max_lines {
+            return 0;
+        }
+
+        let mut kept: Vec<(i64, Vec<String>, String)> = self
+            .find_lines(|_| true, Some(max_lines), SearchDirection::Backward)
+            .iter()
+            .map(|line| {
+                let date = line.date().map_or(0, |date| date.timestamp());
+                let message = format!(
+                    "{}\t{}",
+                    line.prefix().unwrap_or_default(),
+                    line.message().unwrap_or_default()
+                );
+                (date, line.tags(), message)
+            })
+            .collect();
+        kept.reverse();
+
+        self.clear();
+
+        for (date, mut tags, message) in kept {
+            tags.extend(
+                ["no_log", "notify_none", "no_highlight"]
+                    .iter()
+                    .map(|tag| (*tag).to_owned()),
+            );
+            self.print_tags_dated(date, &tags.join(","), &message);
+        }
+
+        count - max_lines
+    }
+
+    fn lines_count_of(&self, lines: &str) -> usize {
+        let lines: Option<HDataPointer> = self
+            .get_hdata("buffer")
+            .and_then(|buffer| buffer.get_var(lines));
+
+        match lines {
+            Some(lines) if !lines.ptr.is_null() => lines
+                .get_hdata("lines")
+                .and_then(|lines| lines.get_var::<i32>("lines_count"))
+                .map_or(0, |count| count.max(0) as usize),
+            _ => 0,
+        }
+    }
+
+    /// Display a message on the buffer and return a handle to the newly
+    /// created line.
+    ///
+    /// Returns None if no line was added to the buffer, e.g. if the line was
+    /// dropped by a line hook.
+    pub fn print_line(&self, message: &str) -> Option<Line> {
+        let previous = self.last_line();
+        self.print(message);
+        self.new_last_line(previous)
+    }
+
+    /// Display a message on the buffer with attached date and tags and return
+    /// a handle to the newly created line.
+    ///
+    /// Returns None if no line was added to the buffer, e.g. if the line was
+    /// dropped by a line hook.
+    pub fn print_tags_dated_line(
+        &self,
+        date: i64,
+        tags: &str,
+        message: &str,
+    ) -> Option<Line> {
+        let previous = self.last_line();
+        self.print_tags_dated(date, tags, message);
+        self.new_last_line(previous)
+    }
+
+    fn new_last_line(&self, previous: Option<Line>) -> Option<Line> {
+        let last = self.last_line()?;
+
+        if Some(&last) == previous.as_ref() {
+            None
+        } else {
+            Some(last)
+        }
+    }
+}
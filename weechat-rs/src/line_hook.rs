@@ -0,0 +1,377 @@
+//! Line hooks allow plugins to modify or drop lines before they are added to
+//! a buffer.
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::os::raw::c_void;
+use std::ptr;
+
+use weechat_sys::{t_gui_buffer, t_hashtable, t_weechat_plugin};
+
+use crate::buffer::HotlistPriority;
+use crate::focus::parse_pointer;
+use crate::hashtable::{hashtable_to_map, map_to_hashtable, Hashtable};
+use crate::hooks::{sealed, Hook, HookCallback, HookSettings, ToggleHook};
+use crate::profiling::profile;
+use crate::{Buffer, HookKind, LossyCString, Weechat};
+
+/// A hook that modifies lines, the hook is removed when the object is
+/// dropped.
+///
+/// Lines are left unchanged while the hook is disabled.
+pub struct LineHook {
+    hook: Hook,
+    hook_data: Box<LineHookData>,
+}
+
+struct LineHookData {
+    callback: HookCallback<dyn FnMut(&Weechat, &LineData) -> LineChanges>,
+    name: &'static str,
+    enabled: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for LineHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        Some(&self.hook)
+    }
+}
+
+impl sealed::Switch for LineHook {
+    fn enabled(&self) -> bool {
+        self.hook_data.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.hook_data.enabled.set(enabled);
+    }
+}
+
+impl HookSettings for LineHook {}
+
+impl ToggleHook for LineHook {}
+
+impl LineHook {
+    /// Hook lines before they are added to a buffer, the lines can be
+    /// modified, moved to another buffer or dropped.
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `buffer_type` - The type of buffers that should be hooked,
+    ///     `formatted`, `free` or `*` for all buffers.
+    /// * `buffer_name` - A comma separated list of buffer masks, e.g.
+    ///     `irc.libera.*`. An empty string hooks all buffers.
+    /// * `tags` - Only hook lines with these tags, e.g.
+    ///     `irc_privmsg,notify_message`. An empty string hooks all lines.
+    /// * `callback` - A closure that will be called for every line, the
+    ///     returned changes are applied to the line.
+    pub fn new<C>(
+        weechat: &Weechat,
+        buffer_type: &str,
+        buffer_name: &str,
+        tags: &str,
+        callback: C,
+    ) -> LineHook
+    where
+        C: FnMut(&Weechat, &LineData) -> LineChanges + 'static,
+    {
+        weechat.new_line_hook(
+            buffer_type,
+            buffer_name,
+            tags,
+            Box::new(callback),
+            std::any::type_name::<C>(),
+        )
+    }
+}
+
+/// A line that is about to be added to a buffer, passed to the callback of a
+/// `LineHook`.
+pub struct LineData {
+    /// The buffer the line is added to.
+    pub buffer: Option<Buffer>,
+    /// The full name of the buffer, e.g. `irc.libera.#weechat`.
+    pub buffer_name: String,
+    /// The type of the buffer, `formatted` or `free`.
+    pub buffer_type: String,
+    /// The line number for buffers with free content, -1 for formatted
+    /// buffers.
+    pub y: i32,
+    /// The date of the line.
+    pub date: DateTime<Utc>,
+    /// The date when the line was printed.
+    pub date_printed: DateTime<Utc>,
+    /// The tags of the line.
+    pub tags: Vec<String>,
+    /// Is the line displayed or is it filtered.
+    pub displayed: bool,
+    /// The notify level of the line, -1 if the line doesn't notify.
+    pub notify_level: i32,
+    /// Is the line a highlight.
+    pub highlight: bool,
+    /// The prefix of the line.
+    pub prefix: String,
+    /// The message of the line.
+    pub message: String,
+}
+
+impl LineData {
+    fn from_map(weechat: &Weechat, map: &HashMap<String, String>) -> LineData {
+        let string = |key: &str| map.get(key).cloned().unwrap_or_default();
+        let integer = |key: &str| {
+            map.get(key).and_then(|value| value.parse::<i64>().ok())
+        };
+        let date = |key: &str| {
+            integer(key)
+                .and_then(|date| Utc.timestamp_opt(date, 0).single())
+                .unwrap_or_else(Utc::now)
+        };
+
+        let tags = map
+            .get("tags")
+            .map(|tags| {
+                tags.split(',')
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        LineData {
+            buffer: parse_pointer(map.get("buffer"))
+                .map(|ptr| Buffer::wrap(weechat.ptr, ptr as *mut t_gui_buffer)),
+            buffer_name: string("buffer_name"),
+            buffer_type: string("buffer_type"),
+            y: integer("y").unwrap_or(-1) as i32,
+            date: date("date"),
+            date_printed: date("date_printed"),
+            tags,
+            displayed: integer("displayed").unwrap_or(1) != 0,
+            notify_level: integer("notify_level").unwrap_or(0) as i32,
+            highlight: integer("highlight").unwrap_or(0) != 0,
+            prefix: string("prefix"),
+            message: string("message"),
+        }
+    }
+}
+
+/// The changes a `LineHook` makes to a line, fields that are `None` are left
+/// unchanged.
+#[derive(Default)]
+pub struct LineChanges {
+    /// Move the line to another buffer.
+    pub buffer: Option<Buffer>,
+    /// Change the date of the line.
+    pub date: Option<DateTime<Utc>>,
+    /// Replace the tags of the line.
+    pub tags: Option<Vec<String>>,
+    /// Change the notify level of the line, from -1 for a line that doesn't
+    /// notify up to 3 for a highlight. Lines with a higher level add the
+    /// buffer to the hotlist with a higher priority.
+    pub notify_level: Option<i32>,
+    /// Mark the line as a highlight or remove the highlight.
+    pub highlight: Option<bool>,
+    /// Replace the prefix of the line.
+    pub prefix: Option<String>,
+    /// Replace the message of the line.
+    pub message: Option<String>,
+    /// Drop the line, it won't be added to the buffer.
+    pub drop: bool,
+}
+
+impl LineChanges {
+    /// Keep the line unchanged.
+    pub fn keep() -> LineChanges {
+        LineChanges::default()
+    }
+
+    /// Drop the line, it won't be added to the buffer.
+    pub fn drop_line() -> LineChanges {
+        LineChanges {
+            drop: true,
+            ..Default::default()
+        }
+    }
+
+    /// Keep the line but don't notify about it, the buffer isn't added to the
+    /// hotlist because of the line.
+    pub fn silence() -> LineChanges {
+        LineChanges {
+            notify_level: Some(-1),
+            ..Default::default()
+        }
+    }
+
+    /// Change the notify level of the line, e.g. to escalate a message to a
+    /// highlight.
+    /// * `priority` - The hotlist priority the line should notify with.
+    pub fn notify(priority: HotlistPriority) -> LineChanges {
+        LineChanges {
+            notify_level: Some(priority as i32),
+            ..Default::default()
+        }
+    }
+
+    fn to_map(&self) -> HashMap<&'static str, String> {
+        let mut map = HashMap::new();
+
+        if self.drop {
+            // WeeChat discards lines whose buffer is set to an empty string.
+            map.insert("buffer", String::new());
+            return map;
+        }
+
+        if let Some(buffer) = &self.buffer {
+            map.insert("buffer", format!("{:p}", buffer.ptr));
+        }
+        if let Some(date) = &self.date {
+            map.insert("date", date.timestamp().to_string());
+        }
+        if let Some(tags) = &self.tags {
+            map.insert("tags", tags.join(","));
+        }
+        if let Some(notify_level) = self.notify_level {
+            map.insert("notify_level", notify_level.clamp(-1, 3).to_string());
+        }
+        if let Some(highlight) = self.highlight {
+            map.insert("highlight", (highlight as i32).to_string());
+        }
+        if let Some(prefix) = &self.prefix {
+            map.insert("prefix", prefix.clone());
+        }
+        if let Some(message) = &self.message {
+            map.insert("message", message.clone());
+        }
+
+        map
+    }
+}
+
+impl Weechat {
+    /// Hook lines before they are added to a buffer, the lines can be
+    /// modified, moved to another buffer or dropped.
+    ///
+    /// * `buffer_type` - The type of buffers that should be hooked,
+    ///     `formatted`, `free` or `*` for all buffers.
+    /// * `buffer_name` - A comma separated list of buffer masks, e.g.
+    ///     `irc.libera.*`. An empty string hooks all buffers.
+    /// * `tags` - Only hook lines with these tags, e.g.
+    ///     `irc_privmsg,notify_message`. An empty string hooks all lines.
+    /// * `callback` - A function that will be called for every line, the
+    ///     returned changes are applied to the line.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is
+    ///     unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `LineHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_line<T>(
+        &self,
+        buffer_type: &str,
+        buffer_name: &str,
+        tags: &str,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            line: &LineData,
+        ) -> LineChanges,
+        callback_data: Option<T>,
+    ) -> LineHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_line_hook(
+            buffer_type,
+            buffer_name,
+            tags,
+            Box::new(move |weechat, line| callback(&data, weechat, line)),
+            std::any::type_name::<T>(),
+        )
+    }
+
+    fn new_line_hook(
+        &self,
+        buffer_type: &str,
+        buffer_name: &str,
+        tags: &str,
+        callback: Box<dyn FnMut(&Weechat, &LineData) -> LineChanges>,
+        name: &'static str,
+    ) -> LineHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            line: *mut t_hashtable,
+        ) -> *mut t_hashtable {
+            let hook_data: &LineHookData =
+                { &*(pointer as *const LineHookData) };
+
+            if !hook_data.enabled.get() {
+                return ptr::null_mut();
+            }
+
+            let name = hook_data.name;
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let line =
+                LineData::from_map(&weechat, &hashtable_to_map(&weechat, line));
+
+            // A nested call leaves the line unchanged.
+            let changes = hook_data
+                .callback
+                .call(&weechat, HookKind::Line, |callback| {
+                    profile(
+                        HookKind::Line,
+                        || format!("{} ({:p})", name, pointer),
+                        || callback(&weechat, &line),
+                    )
+                })
+                .map(|changes| changes.to_map())
+                .unwrap_or_default();
+
+            if changes.is_empty() {
+                return ptr::null_mut();
+            }
+
+            // WeeChat applies the changes to the line and frees the hashtable
+            // afterwards.
+            map_to_hashtable(&weechat, &changes)
+                .map_or(ptr::null_mut(), Hashtable::into_raw)
+        }
+
+        let data = Box::new(LineHookData {
+            callback: HookCallback::new(callback),
+            name,
+            enabled: Cell::new(true),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_line = self.get().hook_line.unwrap();
+
+        let buffer_type = LossyCString::new(buffer_type);
+        let buffer_name = LossyCString::new(buffer_name);
+        let tags = LossyCString::new(tags);
+
+        let hook_ptr = unsafe {
+            hook_line(
+                self.ptr,
+                buffer_type.as_ptr(),
+                buffer_name.as_ptr(),
+                tags.as_ptr(),
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        LineHook { hook, hook_data }
+    }
+}
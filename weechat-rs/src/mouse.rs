@@ -0,0 +1,409 @@
+//! Typed mouse events for custom buffers and bar items.
+//!
+//! WeeChat delivers mouse events through key bindings in the `mouse`
+//! context, the key of a binding names the area and the mouse action, e.g.
+//! `@chat(myplugin.list):button1`, and the command of the binding sends a
+//! hsignal with the focus information. A `MouseHook` binds the keys for an
+//! area, hooks the hsignal and turns the focus information into
+//! `MouseEvent`s.
+//!
+//! Clickable regions can be registered on the hook, a focus hook adds the
+//! name of the region under the mouse to the focus information, so the
+//! region is available in the events and to other key bindings.
+//!
+//! ```ignore
+//! let mouse = MouseHook::new(
+//!     &weechat,
+//!     MouseArea::Buffer(&buffer),
+//!     |_, event| {
+//!         if event.kind == MouseEventKind::Click(MouseButton::Left) {
+//!             if let Some(region) = &event.region {
+//!                 select(region);
+//!             }
+//!         }
+//!     },
+//! );
+//!
+//! mouse.add_region("first_entry", 0..1, 0..40);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use weechat_sys::t_weechat_plugin;
+
+use crate::focus::{FocusHook, FocusInfo};
+use crate::hashtable::map_to_hashtable;
+use crate::{Buffer, HsignalHook, LossyCString, ReturnCode, Weechat};
+
+static MOUSE_HOOK_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The mouse actions that are bound for an area.
+const MOUSE_KEYS: [&str; 7] = [
+    "button1",
+    "button2",
+    "button3",
+    "wheelup",
+    "wheeldown",
+    "button1-gesture-*",
+    "button2-gesture-*",
+];
+
+/// The area of the screen mouse events are caught in.
+pub enum MouseArea<'a> {
+    /// The chat area of a buffer.
+    Buffer(&'a Buffer),
+    /// A bar item, in any bar that displays it.
+    BarItem(&'a str),
+}
+
+/// A mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    /// The left mouse button.
+    Left,
+    /// The right mouse button.
+    Right,
+    /// The middle mouse button.
+    Middle,
+}
+
+/// The direction of a drag gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragDirection {
+    /// The mouse was moved up.
+    Up,
+    /// The mouse was moved down.
+    Down,
+    /// The mouse was moved to the left.
+    Left,
+    /// The mouse was moved to the right.
+    Right,
+}
+
+/// The kind of a mouse event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseEventKind {
+    /// A button was clicked.
+    Click(MouseButton),
+    /// The mouse was moved with a button pressed, `long` is set if the
+    /// mouse was moved over a long distance.
+    Drag {
+        /// The button that was held down.
+        button: MouseButton,
+        /// The direction the mouse was moved in.
+        direction: DragDirection,
+        /// Was the mouse moved over a long distance.
+        long: bool,
+    },
+    /// The mouse wheel was scrolled up.
+    WheelUp,
+    /// The mouse wheel was scrolled down.
+    WheelDown,
+}
+
+/// A mouse event that is passed to the callback of a `MouseHook`.
+pub struct MouseEvent {
+    /// The kind of the event.
+    pub kind: MouseEventKind,
+    /// The raw key of the event, e.g. `button1-gesture-left`.
+    pub key: String,
+    /// The column inside of the area, the column of the chat line or of the
+    /// bar item.
+    pub col: i32,
+    /// The line inside of the area, the line of the bar item or, for
+    /// buffers with free content, the line of the buffer. This is -1 for the
+    /// chat area of buffers with formatted content.
+    pub line: i32,
+    /// The position on the screen where a drag ended, as column and row.
+    pub end: Option<(i32, i32)>,
+    /// The name of the region the event happened in, see
+    /// `MouseHook::add_region`.
+    pub region: Option<String>,
+    /// The item under the mouse, e.g. the buffer, the chat line and word and
+    /// the screen coordinates.
+    pub focus: FocusInfo,
+}
+
+#[derive(Debug, Clone)]
+struct MouseRegion {
+    name: String,
+    lines: Range<i32>,
+    cols: Range<i32>,
+}
+
+#[derive(Default)]
+struct RegionState {
+    regions: RefCell<Vec<MouseRegion>>,
+    region_key: String,
+    bar_item: bool,
+}
+
+/// Mouse events of an area, the key bindings and hooks are removed when the
+/// object is dropped. See the module documentation.
+pub struct MouseHook {
+    weechat_ptr: *mut t_weechat_plugin,
+    area: String,
+    regions: Rc<RegionState>,
+    _hsignal: HsignalHook,
+    _focus: FocusHook,
+}
+
+impl MouseButton {
+    fn from_key(key: &str) -> Option<MouseButton> {
+        match key {
+            "button1" => Some(MouseButton::Left),
+            "button2" => Some(MouseButton::Right),
+            "button3" => Some(MouseButton::Middle),
+            _ => None,
+        }
+    }
+}
+
+impl MouseEventKind {
+    /// Parse the key of a mouse event, e.g. `button1-gesture-left-long`.
+    fn from_key(key: &str) -> Option<MouseEventKind> {
+        match key {
+            "wheelup" => return Some(MouseEventKind::WheelUp),
+            "wheeldown" => return Some(MouseEventKind::WheelDown),
+            _ => (),
+        }
+
+        let mut parts = key.splitn(3, '-');
+        let button = MouseButton::from_key(parts.next()?)?;
+
+        match (parts.next(), parts.next()) {
+            (None, _) => Some(MouseEventKind::Click(button)),
+            (Some("gesture"), Some(gesture)) => {
+                let (direction, long) = match gesture.strip_suffix("-long") {
+                    Some(direction) => (direction, true),
+                    None => (gesture, false),
+                };
+
+                let direction = match direction {
+                    "up" => DragDirection::Up,
+                    "down" => DragDirection::Down,
+                    "left" => DragDirection::Left,
+                    "right" => DragDirection::Right,
+                    _ => return None,
+                };
+
+                Some(MouseEventKind::Drag {
+                    button,
+                    direction,
+                    long,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn area_position(state: &RegionState, focus: &FocusInfo) -> (i32, i32) {
+    if state.bar_item {
+        (
+            focus.bar_item_line.unwrap_or(-1),
+            focus.bar_item_col.unwrap_or(-1),
+        )
+    } else {
+        let integer = |key: &str| {
+            focus
+                .raw
+                .get(key)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(-1)
+        };
+        (integer("_chat_line_y"), integer("_chat_line_x"))
+    }
+}
+
+fn region_focus_cb(
+    state: &Rc<RegionState>,
+    focus: &FocusInfo,
+) -> HashMap<String, String> {
+    let (line, col) = area_position(state, focus);
+
+    state
+        .regions
+        .borrow()
+        .iter()
+        .find(|region| {
+            region.lines.contains(&line) && region.cols.contains(&col)
+        })
+        .map(|region| {
+            let mut info = HashMap::new();
+            info.insert(state.region_key.clone(), region.name.clone());
+            info
+        })
+        .unwrap_or_default()
+}
+
+impl MouseHook {
+    /// Catch the mouse events of an area.
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `area` - The area the events are caught in.
+    /// * `callback` - A closure that is called for every click, drag and
+    ///     wheel event in the area.
+    pub fn new(
+        weechat: &Weechat,
+        area: MouseArea,
+        mut callback: impl FnMut(&Weechat, &MouseEvent) + 'static,
+    ) -> MouseHook {
+        let id = MOUSE_HOOK_ID.fetch_add(1, Ordering::SeqCst);
+        let plugin = weechat.plugin_name().into_owned();
+        let signal = format!("{}_mouse_{}", plugin, id);
+
+        let (area_name, bar_item) = match &area {
+            MouseArea::Buffer(buffer) => {
+                (format!("chat({})", buffer.get_full_name()), false)
+            }
+            MouseArea::BarItem(item) => (format!("item({})", item), true),
+        };
+
+        let regions = Rc::new(RegionState {
+            regions: RefCell::new(Vec::new()),
+            region_key: format!("{}_region", signal),
+            bar_item,
+        });
+
+        let state = regions.clone();
+        let hsignal =
+            HsignalHook::new(weechat, &signal, move |weechat, _, map| {
+                let focus = FocusInfo::from_map(weechat, map);
+                let key = focus.key.clone().unwrap_or_default();
+
+                if let Some(kind) = MouseEventKind::from_key(&key) {
+                    let (line, col) = area_position(&state, &focus);
+                    let integer = |key: &str| {
+                        focus.raw.get(key).and_then(|value| value.parse().ok())
+                    };
+
+                    let event = MouseEvent {
+                        kind,
+                        key,
+                        col,
+                        line,
+                        end: integer("_x2").zip(integer("_y2")),
+                        region: focus.raw.get(&state.region_key).cloned(),
+                        focus,
+                    };
+
+                    callback(weechat, &event);
+                }
+
+                ReturnCode::OkEat
+            });
+
+        let focus_regions = regions.clone();
+        let focus_cb = move |_: &Weechat, focus: &FocusInfo| {
+            region_focus_cb(&focus_regions, focus)
+        };
+
+        let focus = match area {
+            MouseArea::Buffer(buffer) => {
+                FocusHook::for_buffer(weechat, buffer, focus_cb)
+            }
+            MouseArea::BarItem(item) => FocusHook::new(weechat, item, focus_cb),
+        };
+
+        let keys: HashMap<String, String> = MOUSE_KEYS
+            .iter()
+            .map(|key| {
+                (
+                    format!("@{}:{}", area_name, key),
+                    format!("hsignal:{}", signal),
+                )
+            })
+            .collect();
+
+        weechat.key_bind("mouse", &keys);
+
+        MouseHook {
+            weechat_ptr: weechat.ptr,
+            area: area_name,
+            regions,
+            _hsignal: hsignal,
+            _focus: focus,
+        }
+    }
+
+    /// Add a clickable region, events inside of the region carry its name.
+    ///
+    /// The coordinates are relative to the area, the lines and columns of a
+    /// bar item or of the chat area of a buffer. Chat lines are only
+    /// numbered for buffers with free content. If regions overlap, the
+    /// region that was added first wins.
+    /// * `name` - The name of the region.
+    /// * `lines` - The lines the region spans.
+    /// * `cols` - The columns the region spans.
+    pub fn add_region(&self, name: &str, lines: Range<i32>, cols: Range<i32>) {
+        self.regions.regions.borrow_mut().push(MouseRegion {
+            name: name.to_owned(),
+            lines,
+            cols,
+        });
+    }
+
+    /// Remove all regions with the given name.
+    pub fn remove_region(&self, name: &str) {
+        self.regions
+            .regions
+            .borrow_mut()
+            .retain(|region| region.name != name);
+    }
+
+    /// Remove all regions, e.g. before the content of the area is redrawn.
+    pub fn clear_regions(&self) {
+        self.regions.regions.borrow_mut().clear();
+    }
+}
+
+impl Drop for MouseHook {
+    fn drop(&mut self) {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        weechat.key_unbind("mouse", &format!("area:{}", self.area));
+    }
+}
+
+impl Weechat {
+    /// Bind keys, the bindings are added to the keys of the user.
+    /// * `context` - The context of the keys, e.g. `default` or `mouse`.
+    /// * `keys` - The keys and the commands they run.
+    ///
+    /// Returns the number of keys that were bound.
+    pub fn key_bind(
+        &self,
+        context: &str,
+        keys: &HashMap<String, String>,
+    ) -> usize {
+        let key_bind = self.get().key_bind.unwrap();
+
+        let context = LossyCString::new(context);
+        let keys = match map_to_hashtable(self, keys) {
+            Some(keys) => keys,
+            None => return 0,
+        };
+
+        let count = unsafe { key_bind(context.as_ptr(), keys.ptr) };
+
+        count.max(0) as usize
+    }
+
+    /// Remove key bindings.
+    /// * `context` - The context of the key, e.g. `default` or `mouse`.
+    /// * `key` - The key that should be unbound, `area:<name>` removes all
+    ///     mouse keys of an area.
+    ///
+    /// Returns the number of keys that were removed.
+    pub fn key_unbind(&self, context: &str, key: &str) -> usize {
+        let key_unbind = self.get().key_unbind.unwrap();
+
+        let context = LossyCString::new(context);
+        let key = LossyCString::new(key);
+
+        unsafe { key_unbind(context.as_ptr(), key.as_ptr()).max(0) as usize }
+    }
+}
@@ -0,0 +1,119 @@
+//! Helpers to scope the names of buffers, completions, bar items and signals
+//! to the plugin that creates them.
+//!
+//! WeeChat shares these names between all plugins and scripts, by convention
+//! they are prefixed with the name of the plugin that owns them, e.g. the
+//! completion `irc_channels` or the signal `xfer_add`.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::{HookKind, LossyCString, Weechat};
+
+/// The kind of object a scoped name is created for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameKind {
+    /// A buffer, the scoped name is the full name of the buffer, e.g.
+    /// `myplugin.name`.
+    Buffer,
+    /// A completion template, e.g. `myplugin_name`.
+    Completion,
+    /// A bar item, e.g. `myplugin_name`.
+    BarItem,
+    /// A signal or hsignal, e.g. `myplugin_name`.
+    Signal,
+}
+
+impl NameKind {
+    fn separator(self) -> char {
+        match self {
+            NameKind::Buffer => '.',
+            _ => '_',
+        }
+    }
+}
+
+/// Error returned when a scoped name is already in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTaken {
+    /// The kind of object the name was requested for.
+    pub kind: NameKind,
+    /// The scoped name that is already in use.
+    pub name: String,
+}
+
+impl fmt::Display for NameTaken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            NameKind::Buffer => "buffer",
+            NameKind::Completion => "completion",
+            NameKind::BarItem => "bar item",
+            NameKind::Signal => "signal",
+        };
+
+        write!(f, "the {} name {} is already in use", kind, self.name)
+    }
+}
+
+impl Error for NameTaken {}
+
+impl Weechat {
+    /// Prefix a name with the name of the plugin.
+    ///
+    /// Buffers are prefixed with `plugin.`, this is the full name WeeChat
+    /// gives to a buffer that the plugin created with the given name. All
+    /// other kinds are prefixed with `plugin_`. Names that already contain
+    /// the prefix are returned unchanged.
+    /// * `kind` - The kind of object the name is for.
+    /// * `name` - The name without the plugin prefix.
+    pub fn scoped_name(&self, kind: NameKind, name: &str) -> String {
+        let prefix = format!("{}{}", self.plugin_name(), kind.separator());
+
+        if name.starts_with(&prefix) {
+            name.to_owned()
+        } else {
+            format!("{}{}", prefix, name)
+        }
+    }
+
+    /// Prefix a name with the name of the plugin and check that the name
+    /// isn't in use yet.
+    ///
+    /// Signals can't collide since every signal can have many senders and
+    /// receivers, the scoped name is always returned for them.
+    /// * `kind` - The kind of object the name is for.
+    /// * `name` - The name without the plugin prefix.
+    ///
+    /// Returns an error if a buffer, completion or bar item with the scoped
+    /// name already exists.
+    pub fn claim_scoped_name(
+        &self,
+        kind: NameKind,
+        name: &str,
+    ) -> Result<String, NameTaken> {
+        let name = self.scoped_name(kind, name);
+
+        let taken = match kind {
+            NameKind::Buffer => self.buffer_search_full_name(&name).is_some(),
+            NameKind::Completion => self
+                .hooks_matching(HookKind::Completion, &name)
+                .is_some_and(|infolist| infolist.next()),
+            NameKind::BarItem => self.bar_item_exists(&name),
+            NameKind::Signal => false,
+        };
+
+        if taken {
+            Err(NameTaken { kind, name })
+        } else {
+            Ok(name)
+        }
+    }
+
+    fn bar_item_exists(&self, name: &str) -> bool {
+        let bar_item_search = self.get().bar_item_search.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe { !bar_item_search(name.as_ptr()).is_null() }
+    }
+}
@@ -0,0 +1,219 @@
+//! Notify the user about events, e.g. new messages.
+//!
+//! A `Notifier` packages what most plugins do when something needs the
+//! attention of the user: the message is printed with the tags that put the
+//! buffer into the hotlist, an optional bar item counts the notifications
+//! and an optional external command, e.g. `notify-send`, is run for every
+//! notification.
+//!
+//! ```ignore
+//! let notifier = weechat
+//!     .notifier()
+//!     .counter("myplugin_notifications")
+//!     .command_option("myplugin.look.notify_command");
+//!
+//! notifier.notify(
+//!     &buffer,
+//!     &Notification::new("alice mentioned you")
+//!         .priority(HotlistPriority::Highlight)
+//!         .prefix("alice"),
+//! );
+//! ```
+
+use std::cell::{Cell, RefCell};
+
+use weechat_sys::t_weechat_plugin;
+
+use crate::bar::StaticBarItem;
+use crate::buffer::HotlistPriority;
+use crate::config_options::ConfigOption;
+use crate::{Buffer, ProcessCommand, ProcessHook, ReturnCode, Weechat};
+
+/// A notification that is sent with a `Notifier`.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    message: String,
+    prefix: Option<String>,
+    priority: HotlistPriority,
+    tags: Vec<String>,
+}
+
+impl Notification {
+    /// Create a notification with the `Message` priority.
+    /// * `message` - The message that is printed and passed to the notifier
+    ///     command.
+    pub fn new(message: &str) -> Notification {
+        Notification {
+            message: message.to_owned(),
+            prefix: None,
+            priority: HotlistPriority::Message,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Set the priority, it decides how the buffer is added to the hotlist.
+    pub fn priority(mut self, priority: HotlistPriority) -> Notification {
+        self.priority = priority;
+        self
+    }
+
+    /// Set the prefix of the printed line, e.g. the nick of the sender.
+    pub fn prefix(mut self, prefix: &str) -> Notification {
+        self.prefix = Some(prefix.to_owned());
+        self
+    }
+
+    /// Add a tag to the printed line, e.g. `nick_alice` so the line is
+    /// matched by highlight and filter rules.
+    pub fn tag(mut self, tag: &str) -> Notification {
+        self.tags.push(tag.to_owned());
+        self
+    }
+
+    /// The tags of the printed line, the `notify_*` tag makes WeeChat add
+    /// the buffer to the hotlist. Lines without such a tag are added with
+    /// the low priority, `notify_none` would keep them out of the hotlist.
+    fn line_tags(&self) -> String {
+        let notify = match self.priority {
+            HotlistPriority::Low => None,
+            HotlistPriority::Message => Some("notify_message"),
+            HotlistPriority::Private => Some("notify_private"),
+            HotlistPriority::Highlight => Some("notify_highlight"),
+        };
+
+        let mut tags: Vec<&str> = notify.into_iter().collect();
+        tags.extend(self.tags.iter().map(String::as_str));
+        tags.join(",")
+    }
+
+    fn line(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}\t{}", prefix, self.message),
+            None => format!("\t{}", self.message),
+        }
+    }
+}
+
+/// Sends notifications to the user, see the module documentation.
+pub struct Notifier {
+    counter: Option<StaticBarItem>,
+    count: Cell<u32>,
+    command_option: Option<String>,
+    processes: RefCell<Vec<ProcessHook>>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl Weechat {
+    /// Create a notifier that only prints the notifications, the counter and
+    /// the notifier command can be configured with the builder methods.
+    pub fn notifier(&self) -> Notifier {
+        Notifier {
+            counter: None,
+            count: Cell::new(0),
+            command_option: None,
+            processes: RefCell::new(Vec::new()),
+            weechat_ptr: self.ptr,
+        }
+    }
+}
+
+impl Notifier {
+    /// Count the notifications in a bar item, the bar item is empty if there
+    /// are no notifications.
+    /// * `bar_item_name` - The name of the bar item that is created.
+    pub fn counter(mut self, bar_item_name: &str) -> Notifier {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        self.counter = Some(weechat.new_static_bar_item(bar_item_name, ""));
+        self
+    }
+
+    /// Run the command configured in an option for every notification.
+    ///
+    /// The command is run by `sh -c`, so it may contain arguments and pipes.
+    /// The name of the buffer and the message are appended to it as
+    /// arguments, e.g. `notify-send` shows them as the summary and the body.
+    /// No command is run if the option is empty or doesn't exist.
+    /// * `option` - The full name of a string option, e.g.
+    ///     `myplugin.look.notify_command`.
+    pub fn command_option(mut self, option: &str) -> Notifier {
+        self.command_option = Some(option.to_owned());
+        self
+    }
+
+    /// The number of notifications since the counter was last reset.
+    pub fn count(&self) -> u32 {
+        self.count.get()
+    }
+
+    /// Reset the number of notifications, e.g. once the user looked at them.
+    pub fn reset(&self) {
+        self.count.set(0);
+        self.update_counter();
+    }
+
+    /// Send a notification.
+    /// * `buffer` - The buffer the notification belongs to, the message is
+    ///     printed in it.
+    /// * `notification` - The notification that should be sent.
+    pub fn notify(&self, buffer: &Buffer, notification: &Notification) {
+        buffer.print_tags_dated(
+            0,
+            &notification.line_tags(),
+            &notification.line(),
+        );
+
+        self.count.set(self.count.get().saturating_add(1));
+        self.update_counter();
+
+        if let Some(command) = self.command() {
+            self.run_command(&command, buffer, notification);
+        }
+    }
+
+    fn update_counter(&self) {
+        if let Some(counter) = &self.counter {
+            match self.count.get() {
+                0 => counter.set_text(""),
+                count => counter.set_text(count.to_string()),
+            }
+        }
+    }
+
+    fn command(&self) -> Option<String> {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let option =
+            weechat.get_string_option(self.command_option.as_ref()?)?;
+        let command = option.value().trim().to_owned();
+
+        if command.is_empty() {
+            None
+        } else {
+            Some(command)
+        }
+    }
+
+    fn run_command(
+        &self,
+        command: &str,
+        buffer: &Buffer,
+        notification: &Notification,
+    ) {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+
+        // The option may contain arguments, so the command is run by the
+        // shell. The buffer name and the message are passed as positional
+        // parameters, the shell doesn't interpret them.
+        let command = ProcessCommand::new("sh")
+            .arg("-c")
+            .arg(&format!("{} \"$@\"", command))
+            .arg("sh")
+            .arg(&buffer.get_full_name())
+            .arg(&notification.message)
+            .detached();
+
+        let mut processes = self.processes.borrow_mut();
+        processes.retain(|process| !process.is_finished());
+        processes
+            .push(ProcessHook::new(&weechat, &command, |_, _| ReturnCode::Ok));
+    }
+}
@@ -0,0 +1,282 @@
+//! A settings UI that displays the options of a configuration file in a buffer
+//! with free content, similar to the fset plugin.
+//!
+//! The options can be navigated with the up and down keys, an option is
+//! edited by typing the new value into the input line of the buffer.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::config_options::{display_value, ConfigOption};
+use crate::{
+    ArgsWeechat, Buffer, BufferBuilder, CommandDescription, CommandExists,
+    CommandHook, FreeBufferView, OwnedBuffer, Weechat,
+};
+
+/// A buffer displaying the options of a configuration file.
+///
+/// The buffer is closed when the object is dropped.
+pub struct OptionsBuffer {
+    state: Rc<RefCell<OptionsBufferState>>,
+    _command: CommandHook,
+    _buffer: OwnedBuffer,
+}
+
+#[derive(Default)]
+struct OptionsBufferState {
+    config_name: String,
+    view: Option<FreeBufferView>,
+    selected: usize,
+    options: Vec<OptionEntry>,
+}
+
+struct OptionEntry {
+    full_name: String,
+    option_type: String,
+    value: Option<String>,
+}
+
+impl OptionsBufferState {
+    fn load_options(&mut self, weechat: &Weechat) {
+        self.options.clear();
+
+        let infolist = match weechat
+            .infolist_get("option", &format!("{}.*", self.config_name))
+        {
+            Some(i) => i,
+            None => return,
+        };
+
+        while infolist.next() {
+            let full_name = match infolist.get_string("full_name") {
+                Some(n) => n.into_owned(),
+                None => continue,
+            };
+
+            self.options.push(OptionEntry {
+                full_name,
+                option_type: infolist
+                    .get_string("type")
+                    .map(Cow::into_owned)
+                    .unwrap_or_default(),
+                value: infolist.get_string("value").map(Cow::into_owned),
+            });
+        }
+    }
+
+    fn selected_option(&self) -> Option<&OptionEntry> {
+        self.options.get(self.selected)
+    }
+
+    fn render(&mut self) {
+        let weechat = match &self.view {
+            Some(view) => view.buffer().get_weechat(),
+            None => return,
+        };
+
+        self.load_options(&weechat);
+
+        if self.selected >= self.options.len() {
+            self.selected = self.options.len().saturating_sub(1);
+        }
+
+        let width = self
+            .options
+            .iter()
+            .map(|o| o.full_name.len())
+            .max()
+            .unwrap_or_default();
+
+        let mut lines = Vec::with_capacity(self.options.len());
+
+        for (y, option) in self.options.iter().enumerate() {
+            let value =
+                display_value(&option.option_type, option.value.as_deref());
+
+            let line = format!(
+                "{:width$}  {}",
+                option.full_name,
+                value,
+                width = width
+            );
+
+            let line = if y == self.selected {
+                format!(
+                    "{}{}{}",
+                    weechat.color("reverse"),
+                    line,
+                    weechat.color("-reverse")
+                )
+            } else {
+                line
+            };
+
+            lines.push(line);
+        }
+
+        if let Some(view) = &mut self.view {
+            view.set_lines(lines);
+            view.refresh();
+        }
+    }
+
+    fn set_selected(&self, value: &str) {
+        let weechat = match &self.view {
+            Some(view) => view.buffer().get_weechat(),
+            None => return,
+        };
+
+        if let Some(option) = self
+            .selected_option()
+            .and_then(|o| weechat.get_string_option(&o.full_name))
+        {
+            option.set(value);
+        }
+    }
+
+    fn reset_selected(&self) {
+        let weechat = match &self.view {
+            Some(view) => view.buffer().get_weechat(),
+            None => return,
+        };
+
+        if let Some(option) = self
+            .selected_option()
+            .and_then(|o| weechat.get_string_option(&o.full_name))
+        {
+            option.reset(true);
+        }
+    }
+}
+
+impl OptionsBuffer {
+    /// Get the buffer that displays the options.
+    ///
+    /// Returns None if the buffer has been closed by the user.
+    pub fn buffer(&self) -> Option<Buffer> {
+        self.state
+            .borrow()
+            .view
+            .as_ref()
+            .map(|view| Buffer::wrap(view.buffer().weechat, view.buffer().ptr))
+    }
+
+    /// Redraw the buffer, this needs to be called if options are changed
+    /// outside of the buffer.
+    pub fn refresh(&self) {
+        self.state.borrow_mut().render();
+    }
+}
+
+impl Weechat {
+    /// Create a buffer displaying the options of a configuration file.
+    ///
+    /// The selected option can be changed with the up and down keys, `alt-t`
+    /// toggles a boolean option and `alt-r` resets the option to its default
+    /// value. Text entered into the input line of the buffer is set as the
+    /// new value of the selected option.
+    ///
+    /// The same actions are available with the `/<config_name>_options`
+    /// command which is hooked together with the buffer.
+    /// * `config_name` - The name of the configuration file whose options
+    ///     should be displayed.
+    ///
+    /// Returns an error if the command for the buffer can't be hooked.
+    pub fn new_options_buffer(
+        &self,
+        config_name: &str,
+    ) -> Result<OptionsBuffer, CommandExists> {
+        fn command_cb(
+            state: &Rc<RefCell<OptionsBufferState>>,
+            _buffer: Buffer,
+            mut args: ArgsWeechat,
+        ) {
+            let mut state = state.borrow_mut();
+
+            match args.nth(1).as_deref() {
+                Some("up") => {
+                    state.selected = state.selected.saturating_sub(1);
+                }
+                Some("down") => state.selected += 1,
+                Some("toggle") => {
+                    let is_boolean = state
+                        .selected_option()
+                        .is_some_and(|o| o.option_type == "boolean");
+
+                    if is_boolean {
+                        state.set_selected("toggle");
+                    }
+                }
+                Some("reset") => state.reset_selected(),
+                _ => (),
+            }
+
+            state.render();
+        }
+
+        let state = Rc::new(RefCell::new(OptionsBufferState {
+            config_name: config_name.to_owned(),
+            ..Default::default()
+        }));
+
+        let command_name = format!("{}_options", config_name);
+        let command_state = state.clone();
+        let command = CommandHook::new(
+            self,
+            CommandDescription {
+                name: &command_name,
+                description: "navigate and edit the options displayed in \
+                              the options buffer",
+                args: "up|down|toggle|reset",
+                args_description: concat!(
+                    "    up: select the previous option\n",
+                    "  down: select the next option\n",
+                    "toggle: toggle the value of a boolean option\n",
+                    " reset: reset the option to its default value"
+                ),
+                completion: "up|down|toggle|reset",
+                ..Default::default()
+            },
+            move |_, buffer, args| command_cb(&command_state, buffer, args),
+        )?;
+
+        let input_state = state.clone();
+        let close_state = state.clone();
+        let buffer = BufferBuilder::new(&command_name)
+            .input_callback(move |_, _, input| {
+                let mut state = input_state.borrow_mut();
+                state.set_selected(&input);
+                state.render();
+            })
+            .close_callback(move |_, _| {
+                close_state.borrow_mut().view = None;
+            })
+            .build(self);
+
+        buffer.set_title(format!("Options of {}", config_name));
+
+        for (key, action) in &[
+            ("up", "up"),
+            ("down", "down"),
+            ("meta-t", "toggle"),
+            ("meta-r", "reset"),
+        ] {
+            buffer.set(
+                &format!("key_bind_{}", key),
+                &format!("/{} {}", command_name, action),
+            );
+        }
+
+        let view =
+            FreeBufferView::new(Buffer::wrap(buffer.weechat, buffer.ptr));
+        state.borrow_mut().view = Some(view);
+        state.borrow_mut().render();
+
+        Ok(OptionsBuffer {
+            state,
+            _command: command,
+            _buffer: buffer,
+        })
+    }
+}
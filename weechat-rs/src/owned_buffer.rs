@@ -0,0 +1,139 @@
+//! Buffer handles with a clear ownership.
+//!
+//! A `Buffer` is a plain handle, it neither closes the buffer when it's
+//! dropped nor guarantees that the buffer still exists. The types in this
+//! module make the intent explicit:
+//!
+//! * `OwnedBuffer` - A buffer that was created by the plugin with a
+//!     `BufferBuilder`, it's closed when the handle is dropped unless it was
+//!     released.
+//! * `BufferRef` - A borrowed handle that can't outlive the handle it was
+//!     borrowed from. The callbacks of a `BufferBuilder` receive one, so the
+//!     buffer can't be stored or closed from inside of its own callbacks.
+//!
+//! ```ignore
+//! let buffer: OwnedBuffer = BufferBuilder::new("myplugin")
+//!     .input_callback(|_, buffer, input| render(buffer, &input))
+//!     .build(&weechat);
+//!
+//! render(buffer.as_buffer_ref(), "");
+//! // The buffer is closed once `buffer` goes out of scope.
+//! ```
+//!
+//! Neither handle gives access to `Buffer::close`, an owned buffer is closed
+//! with `OwnedBuffer::close` or by dropping it.
+
+use std::cell::Cell;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::Buffer;
+
+/// A buffer that is closed when the handle is dropped, see the module
+/// documentation.
+///
+/// Buffers that were already closed, e.g. by the user, aren't closed again.
+pub struct OwnedBuffer {
+    buffer: Option<Buffer>,
+    closed: Rc<Cell<bool>>,
+}
+
+/// A borrowed buffer handle, see the module documentation.
+#[derive(Clone, Copy)]
+pub struct BufferRef<'a> {
+    buffer: &'a Buffer,
+}
+
+impl Buffer {
+    /// Does the buffer still exist, the pointer of the buffer is checked
+    /// against the buffer list of WeeChat.
+    pub fn exists(&self) -> bool {
+        Buffer::from_hdata_pointer(&self.to_hdata_pointer()).is_some()
+    }
+
+    /// Borrow the buffer handle.
+    pub fn as_buffer_ref(&self) -> BufferRef<'_> {
+        BufferRef::new(self)
+    }
+}
+
+impl OwnedBuffer {
+    /// Take the ownership of a buffer created by the plugin.
+    /// * `buffer` - The new buffer.
+    /// * `closed` - Set by the close callback of the buffer once WeeChat
+    ///     closed it.
+    pub(crate) fn new(buffer: Buffer, closed: Rc<Cell<bool>>) -> OwnedBuffer {
+        OwnedBuffer {
+            buffer: Some(buffer),
+            closed,
+        }
+    }
+
+    /// Give up the ownership, the returned handle doesn't close the buffer
+    /// when it's dropped.
+    pub fn release(mut self) -> Buffer {
+        self.buffer
+            .take()
+            .expect("Owned buffer was already released")
+    }
+
+    /// Close the buffer now instead of when the handle is dropped.
+    pub fn close(self) {
+        drop(self)
+    }
+
+    /// Was the buffer closed, e.g. by the user.
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+}
+
+impl Deref for OwnedBuffer {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Buffer {
+        self.buffer
+            .as_ref()
+            .expect("Owned buffer was already released")
+    }
+}
+
+impl Drop for OwnedBuffer {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            // The user might have closed the buffer already, closing it again
+            // would free it twice.
+            if !self.closed.get() {
+                buffer.close();
+            }
+        }
+    }
+}
+
+impl<'a> BufferRef<'a> {
+    /// Borrow a buffer handle.
+    /// * `buffer` - The handle that is borrowed.
+    pub fn new(buffer: &'a Buffer) -> BufferRef<'a> {
+        BufferRef { buffer }
+    }
+}
+
+impl<'a> Deref for BufferRef<'a> {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Buffer {
+        self.buffer
+    }
+}
+
+impl<'a> PartialEq for BufferRef<'a> {
+    fn eq(&self, other: &BufferRef<'a>) -> bool {
+        self.buffer == other.buffer
+    }
+}
+
+impl<'a> PartialEq<Buffer> for BufferRef<'a> {
+    fn eq(&self, other: &Buffer) -> bool {
+        self.buffer == other
+    }
+}
@@ -1,9 +1,49 @@
-use crate::{ArgsWeechat, Weechat};
+use crate::{ArgsWeechat, CommandExists, NameTaken, Weechat};
 use libc::c_int;
 
+/// The main trait of a plugin, the `weechat_plugin!` macro creates the
+/// plugin object with `init` when WeeChat loads the plugin and drops it when
+/// the plugin is unloaded.
 pub trait WeechatPlugin: Sized {
+    /// Initialize the plugin.
+    ///
+    /// The arguments are the ones given to `/plugin load`, e.g. `debug` for
+    /// `/plugin load myplugin.so debug`. If the plugin is loaded on startup
+    /// WeeChat passes its command line arguments instead, e.g. `-a` or
+    /// `--no-connect`, so plugins should ignore arguments they don't know.
+    ///
+    /// ```ignore
+    /// fn init(weechat: Weechat, args: ArgsWeechat) -> WeechatResult<Self> {
+    ///     let mut debug = false;
+    ///     let mut config_dir = None;
+    ///     let mut args = args;
+    ///
+    ///     while let Some(arg) = args.next() {
+    ///         match arg.as_str() {
+    ///             "debug" => debug = true,
+    ///             "--myplugin-dir" => config_dir = args.next(),
+    ///             _ => (),
+    ///         }
+    ///     }
+    ///     ...
+    /// }
+    /// ```
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `args` - The arguments the plugin was loaded with.
     fn init(weechat: Weechat, args: ArgsWeechat) -> WeechatResult<Self>;
 }
 
 pub struct Error(c_int);
 pub type WeechatResult<T> = Result<T, Error>;
+
+impl From<CommandExists> for Error {
+    fn from(_: CommandExists) -> Self {
+        Error(weechat_sys::WEECHAT_RC_ERROR)
+    }
+}
+
+impl From<NameTaken> for Error {
+    fn from(_: NameTaken) -> Self {
+        Error(weechat_sys::WEECHAT_RC_ERROR)
+    }
+}
@@ -0,0 +1,19 @@
+//! The weechat prelude.
+//!
+//! The prelude re-exports the most commonly used traits, macros and types of
+//! the crate, it can be imported with:
+//!
+//! ```ignore
+//! use weechat::prelude::*;
+//! ```
+
+pub use crate::config_options::ConfigOption;
+pub use crate::hdata::{HDataType, HasHData};
+pub use crate::hooks::{HookSettings, ToggleHook};
+
+pub use crate::weechat_plugin;
+
+pub use crate::{
+    ArgsWeechat, Buffer, CommandDescription, Config, ConfigSectionInfo, Line,
+    ReturnCode, Weechat, WeechatPlugin, WeechatResult,
+};
@@ -0,0 +1,191 @@
+//! Counters for the output of the plugin.
+//!
+//! With the `print-metrics` feature enabled every line the plugin prints is
+//! counted per buffer, together with the number of bytes and the time of
+//! the last print. This helps finding the buffer that floods the screen.
+//! The counters can be read with `Weechat::print_stats` or, by users and
+//! scripts, through the info and infolist that `Weechat::hook_print_stats`
+//! provides. Without the feature nothing is counted.
+//!
+//! ```ignore
+//! // `/eval -n ${info:myplugin_print_lines,irc.libera.#rust}`
+//! let _print_stats = weechat.hook_print_stats();
+//! ```
+
+#[cfg(feature = "print-metrics")]
+pub use self::stats::{PrintStats, PrintStatsHooks};
+
+/// Count a printed message, if the `print-metrics` feature is enabled.
+/// * `buffer` - A function returning the full name of the buffer the
+///     message was printed on. Only called if the message is counted.
+/// * `message` - The message that was printed.
+#[cfg(feature = "print-metrics")]
+pub(crate) fn record<N>(buffer: N, message: &str)
+where
+    N: FnOnce() -> String,
+{
+    stats::record(buffer(), message)
+}
+
+#[cfg(not(feature = "print-metrics"))]
+#[inline(always)]
+pub(crate) fn record<N>(_buffer: N, _message: &str)
+where
+    N: FnOnce() -> String,
+{
+}
+
+#[cfg(feature = "print-metrics")]
+mod stats {
+    use std::cell::RefCell;
+    use std::cmp::Reverse;
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use std::os::raw::c_void;
+
+    use chrono::{DateTime, Utc};
+
+    use crate::{InfoHook, InfolistBuilder, InfolistHook, Weechat};
+
+    thread_local! {
+        static STATS: RefCell<HashMap<String, PrintStats>> =
+            RefCell::new(HashMap::new());
+    }
+
+    /// The accumulated output of the plugin on a buffer.
+    #[derive(Debug, Clone)]
+    pub struct PrintStats {
+        /// The full name of the buffer, e.g. `irc.libera.#rust`.
+        pub buffer: String,
+        /// How many lines were printed, messages containing newlines count
+        /// as multiple lines.
+        pub lines: u64,
+        /// How many bytes were printed.
+        pub bytes: u64,
+        /// When the last message was printed.
+        pub last_print: DateTime<Utc>,
+    }
+
+    /// The info and infolist that expose the print counters, they are
+    /// removed when the object is dropped.
+    pub struct PrintStatsHooks {
+        _info: InfoHook,
+        _infolist: InfolistHook,
+    }
+
+    pub(super) fn record(buffer: String, message: &str) {
+        let lines = message.split('\n').count() as u64;
+        let bytes = message.len() as u64;
+        let now = Utc::now();
+
+        STATS.with(|stats| {
+            let mut stats = stats.borrow_mut();
+            let entry =
+                stats.entry(buffer.clone()).or_insert_with(|| PrintStats {
+                    buffer,
+                    lines: 0,
+                    bytes: 0,
+                    last_print: now,
+                });
+
+            entry.lines += lines;
+            entry.bytes += bytes;
+            entry.last_print = now;
+        })
+    }
+
+    fn clamp(value: u64) -> i32 {
+        i32::try_from(value).unwrap_or(i32::MAX)
+    }
+
+    fn info_cb(
+        weechat: &Weechat,
+        _name: &str,
+        arguments: &str,
+    ) -> Option<String> {
+        let stats = weechat.print_stats();
+
+        let lines: u64 = if arguments.is_empty() {
+            stats.iter().map(|stat| stat.lines).sum()
+        } else {
+            stats
+                .iter()
+                .find(|stat| stat.buffer == arguments)
+                .map_or(0, |stat| stat.lines)
+        };
+
+        Some(lines.to_string())
+    }
+
+    fn infolist_cb(
+        weechat: &Weechat,
+        _pointer: *mut c_void,
+        arguments: &str,
+        infolist: &InfolistBuilder,
+    ) -> bool {
+        for stat in weechat.print_stats() {
+            if !arguments.is_empty() && stat.buffer != arguments {
+                continue;
+            }
+
+            if let Some(item) = infolist.new_item() {
+                item.string("buffer", &stat.buffer)
+                    .integer("lines", clamp(stat.lines))
+                    .integer("bytes", clamp(stat.bytes))
+                    .time("last_print", stat.last_print);
+            }
+        }
+
+        true
+    }
+
+    impl Weechat {
+        /// Get the output the plugin printed on every buffer, sorted by the
+        /// number of printed lines.
+        pub fn print_stats(&self) -> Vec<PrintStats> {
+            let mut stats: Vec<PrintStats> =
+                STATS.with(|stats| stats.borrow().values().cloned().collect());
+            stats.sort_by_key(|stat| Reverse(stat.lines));
+            stats
+        }
+
+        /// Reset the print counters of all buffers.
+        pub fn reset_print_stats(&self) {
+            STATS.with(|stats| stats.borrow_mut().clear());
+        }
+
+        /// Provide the print counters to users and scripts.
+        ///
+        /// The info `<plugin>_print_lines` returns the number of lines that
+        /// were printed on the buffer with the full name given as argument,
+        /// or on all buffers if no argument is given. The infolist
+        /// `<plugin>_print_stats` has an item with the variables `buffer`,
+        /// `lines`, `bytes` and `last_print` for every buffer, the counts
+        /// are capped at the maximum of an integer.
+        pub fn hook_print_stats(&self) -> PrintStatsHooks {
+            let plugin = self.plugin_name();
+
+            let info = InfoHook::new(
+                self,
+                &format!("{}_print_lines", plugin),
+                "number of lines the plugin printed",
+                "buffer full name (optional)",
+                info_cb,
+            );
+
+            let infolist = InfolistHook::new(
+                self,
+                &format!("{}_print_stats", plugin),
+                "lines and bytes the plugin printed per buffer",
+                "",
+                "buffer full name (optional)",
+                infolist_cb,
+            );
+
+            PrintStatsHooks {
+                _info: info,
+                _infolist: infolist,
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Batch prints to a buffer so bursts of lines can't freeze the UI.
+//!
+//! A `PrintQueue` collects the lines for a buffer and prints them on the
+//! next iterations of the main loop, a limited number of lines per
+//! iteration. If more lines are queued than the queue can hold, the
+//! `Overflow` strategy decides which lines are kept.
+//!
+//! ```ignore
+//! let queue = PrintQueue::new(&buffer, 1000)
+//!     .batch_size(200)
+//!     .overflow(Overflow::Summary);
+//!
+//! for message in burst {
+//!     queue.push(&message);
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use chrono::Utc;
+use weechat_sys::t_weechat_plugin;
+
+use crate::{Buffer, Weechat};
+
+/// What a `PrintQueue` does with lines that don't fit into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Drop the oldest queued lines to make room for the new ones.
+    DropOldest,
+    /// Merge a line into the last queued line if they are identical, the
+    /// merged line is printed with a repeat count. Lines that can't be merged
+    /// are handled like with `DropOldest`.
+    Coalesce,
+    /// Drop the new lines and print a line with the number of dropped lines
+    /// once the queue is flushed.
+    Summary,
+}
+
+struct QueuedLine {
+    date: i64,
+    tags: String,
+    message: String,
+    count: usize,
+}
+
+struct QueueState {
+    weechat_ptr: *mut t_weechat_plugin,
+    buffer_name: String,
+    lines: VecDeque<QueuedLine>,
+    capacity: usize,
+    batch_size: usize,
+    overflow: Overflow,
+    dropped: usize,
+    scheduled: bool,
+}
+
+/// A queue of lines that are printed to a buffer in batches, see the module
+/// documentation.
+///
+/// Lines that are still queued when the queue is dropped aren't printed.
+pub struct PrintQueue {
+    state: Rc<RefCell<QueueState>>,
+}
+
+impl QueueState {
+    /// Take up to `count` lines out of the queue, together with the number
+    /// of dropped lines if the summary line is due.
+    fn take_batch(&mut self, count: usize) -> (Vec<QueuedLine>, usize) {
+        let count = count.min(self.lines.len());
+        let lines = self.lines.drain(..count).collect();
+
+        let dropped =
+            if self.overflow == Overflow::Summary && self.lines.is_empty() {
+                std::mem::take(&mut self.dropped)
+            } else {
+                0
+            };
+
+        (lines, dropped)
+    }
+}
+
+/// Print up to `count` lines. The queue isn't borrowed while printing, the
+/// callbacks of print hooks may push more lines.
+fn print(state: &RefCell<QueueState>, count: usize) {
+    let (weechat_ptr, buffer_name, (lines, dropped)) = {
+        let mut queue = state.borrow_mut();
+        (
+            queue.weechat_ptr,
+            queue.buffer_name.clone(),
+            queue.take_batch(count),
+        )
+    };
+
+    // The buffer might have been closed since the lines were queued.
+    let buffer = match Weechat::from_ptr(weechat_ptr)
+        .buffer_search_full_name(&buffer_name)
+    {
+        Some(buffer) => buffer,
+        None => return,
+    };
+
+    for line in lines {
+        if line.count > 1 {
+            buffer.print_tags_dated(
+                line.date,
+                &line.tags,
+                &format!("{} (x{})", line.message, line.count),
+            );
+        } else {
+            buffer.print_tags_dated(line.date, &line.tags, &line.message);
+        }
+    }
+
+    if dropped > 0 {
+        buffer.print_tags_dated(
+            0,
+            "no_highlight",
+            &format!("{} lines were dropped", dropped),
+        );
+    }
+}
+
+/// Print the next batch of lines and schedule the following one.
+fn print_batch(state: &Rc<RefCell<QueueState>>) {
+    let batch_size = {
+        let mut queue = state.borrow_mut();
+        queue.scheduled = false;
+        queue.batch_size
+    };
+
+    print(state, batch_size);
+
+    if !state.borrow().lines.is_empty() {
+        schedule(state);
+    }
+}
+
+fn schedule(state: &Rc<RefCell<QueueState>>) {
+    let mut queue = state.borrow_mut();
+    if queue.scheduled {
+        return;
+    }
+    queue.scheduled = true;
+
+    let weechat = Weechat::from_ptr(queue.weechat_ptr);
+    let state: Weak<RefCell<QueueState>> = Rc::downgrade(state);
+
+    weechat.call_later(Duration::from_millis(0), move |_| {
+        if let Some(state) = state.upgrade() {
+            print_batch(&state);
+        }
+    });
+}
+
+impl PrintQueue {
+    /// Create a print queue for a buffer.
+    ///
+    /// By default the queue prints 100 lines per iteration of the main loop
+    /// and drops the oldest lines if it overflows.
+    /// * `buffer` - The buffer the lines are printed to.
+    /// * `capacity` - The maximum number of lines that are queued.
+    pub fn new(buffer: &Buffer, capacity: usize) -> PrintQueue {
+        let state = QueueState {
+            weechat_ptr: buffer.weechat,
+            buffer_name: buffer.get_full_name().into_owned(),
+            lines: VecDeque::new(),
+            capacity: capacity.max(1),
+            batch_size: 100,
+            overflow: Overflow::DropOldest,
+            dropped: 0,
+            scheduled: false,
+        };
+
+        PrintQueue {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// Set the number of lines that are printed per iteration of the main
+    /// loop.
+    pub fn batch_size(self, batch_size: usize) -> PrintQueue {
+        self.state.borrow_mut().batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set the strategy that is used if the queue overflows.
+    pub fn overflow(self, overflow: Overflow) -> PrintQueue {
+        self.state.borrow_mut().overflow = overflow;
+        self
+    }
+
+    /// Queue a message.
+    /// * `message` - The message that should be printed.
+    pub fn push(&self, message: &str) {
+        self.push_tags("", message);
+    }
+
+    /// Queue a message with tags.
+    /// * `tags` - A comma separated list of tags for the line.
+    /// * `message` - The message that should be printed.
+    pub fn push_tags(&self, tags: &str, message: &str) {
+        {
+            let mut queue = self.state.borrow_mut();
+
+            if queue.overflow == Overflow::Coalesce {
+                if let Some(last) = queue.lines.back_mut() {
+                    if last.tags == tags && last.message == message {
+                        last.count += 1;
+                        return;
+                    }
+                }
+            }
+
+            if queue.lines.len() >= queue.capacity {
+                queue.dropped += 1;
+
+                match queue.overflow {
+                    Overflow::DropOldest | Overflow::Coalesce => {
+                        queue.lines.pop_front();
+                    }
+                    Overflow::Summary => return,
+                }
+            }
+
+            queue.lines.push_back(QueuedLine {
+                date: Utc::now().timestamp(),
+                tags: tags.to_owned(),
+                message: message.to_owned(),
+                count: 1,
+            });
+        }
+
+        schedule(&self.state);
+    }
+
+    /// Print all queued lines right away.
+    pub fn flush(&self) {
+        print(&self.state, usize::MAX);
+    }
+
+    /// Remove all queued lines without printing them.
+    pub fn clear(&self) {
+        let mut queue = self.state.borrow_mut();
+        queue.lines.clear();
+        queue.dropped = 0;
+    }
+
+    /// Get the number of queued lines.
+    pub fn len(&self) -> usize {
+        self.state.borrow().lines.len()
+    }
+
+    /// Is the queue empty.
+    pub fn is_empty(&self) -> bool {
+        self.state.borrow().lines.is_empty()
+    }
+
+    /// Get the number of lines that were dropped because the queue was full.
+    /// With the `Summary` strategy the count is reset once the summary line
+    /// is printed.
+    pub fn dropped(&self) -> usize {
+        self.state.borrow().dropped
+    }
+}
@@ -0,0 +1,659 @@
+//! Run external commands in the background, the output of the command is
+//! passed to a callback.
+
+use libc::{c_char, c_int};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+use std::time::Duration;
+
+use weechat_sys::t_weechat_plugin;
+
+use crate::hashtable::map_to_hashtable;
+use crate::hooks::{sealed, Hook, HookCallback, HookSettings};
+use crate::profiling::profile;
+use crate::{HookKind, LossyCString, ReturnCode, Weechat};
+
+const HOOK_PROCESS_RUNNING: c_int = -1;
+
+/// A command that should be run by WeeChat in the background.
+///
+/// ```ignore
+/// let command = ProcessCommand::new("git pull")
+///     .env("GIT_TERMINAL_PROMPT", "0")
+///     .timeout(Duration::from_secs(30));
+///
+/// let hook = ProcessHook::new(&weechat, &command, |_, output| {
+///     // ...
+///     ReturnCode::Ok
+/// });
+/// ```
+///
+/// Arguments added with `arg()` are passed to the program as they are, they
+/// aren't split into words or interpreted by a shell:
+///
+/// ```ignore
+/// let command = ProcessCommand::new("git")
+///     .arg("log")
+///     .arg(user_input)
+///     .current_dir("/home/user/src/weechat");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProcessCommand {
+    command: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    current_dir: Option<String>,
+    stdin: Option<String>,
+    detached: bool,
+    buffer_flush: Option<u32>,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+}
+
+impl ProcessCommand {
+    /// Create a new command.
+    /// * `command` - The command that should be run. WeeChat splits the
+    ///     command into words and runs the program directly, it's not run by
+    ///     a shell so pipes, redirections and variables don't work, use
+    ///     `ProcessCommand::new("sh").arg("-c").arg(script)` for those.
+    ///     Commands starting with `url:` download an URL, e.g.
+    ///     `url:https://weechat.org/`. If arguments are added with `arg()`
+    ///     the command is the name of the program that should be run.
+    pub fn new(command: &str) -> ProcessCommand {
+        ProcessCommand {
+            command: command.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Add an argument that is passed to the program.
+    ///
+    /// The argument is passed to the program as it is, it isn't split into
+    /// words or interpreted by a shell, it's safe to use arguments that
+    /// contain user input.
+    pub fn arg(mut self, arg: &str) -> ProcessCommand {
+        self.args.push(arg.to_owned());
+        self
+    }
+
+    /// Add multiple arguments that are passed to the program, see `arg()`.
+    pub fn args<I, S>(mut self, args: I) -> ProcessCommand
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self
+    }
+
+    /// Set the working directory of the command.
+    ///
+    /// The directory is ignored for `url:` commands.
+    pub fn current_dir(mut self, dir: &str) -> ProcessCommand {
+        self.current_dir = Some(dir.to_owned());
+        self
+    }
+
+    /// Set an environment variable for the command.
+    ///
+    /// Variables with a name that isn't a valid shell variable name are
+    /// ignored.
+    pub fn env(mut self, key: &str, value: &str) -> ProcessCommand {
+        self.env.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Data that should be written to the standard input of the command, the
+    /// standard input is closed afterwards.
+    pub fn stdin(mut self, data: &str) -> ProcessCommand {
+        self.stdin = Some(data.to_owned());
+        self
+    }
+
+    /// Run the command detached, the callback is only called when the command
+    /// finishes and the output of the command is discarded.
+    pub fn detached(mut self) -> ProcessCommand {
+        self.detached = true;
+        self
+    }
+
+    /// Set the number of bytes of output after which the callback is called,
+    /// by default the callback is called whenever output is available.
+    pub fn buffer_flush(mut self, bytes: u32) -> ProcessCommand {
+        self.buffer_flush = Some(bytes);
+        self
+    }
+
+    /// Kill the command if it runs longer than the timeout.
+    pub fn timeout(mut self, timeout: Duration) -> ProcessCommand {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Download the URL of an `url:` command through a proxy that is
+    /// configured in WeeChat. The download fails if no proxy with the name
+    /// exists.
+    ///
+    /// The proxy is ignored for other commands.
+    /// * `proxy` - The name of the proxy, see the `/proxy` command.
+    pub fn proxy(mut self, proxy: &str) -> ProcessCommand {
+        self.proxy = Some(proxy.to_owned());
+        self
+    }
+
+    fn needs_shell(&self) -> bool {
+        !self.command.starts_with("url:")
+            && (!self.env.is_empty() || self.current_dir.is_some())
+    }
+
+    /// The program and the arguments that are passed to WeeChat, the
+    /// arguments are passed with the `argN` options and WeeChat runs the
+    /// program directly. Without arguments WeeChat splits the command into
+    /// words itself.
+    ///
+    /// WeeChat doesn't run commands in a shell, if the directory or the
+    /// environment needs to be changed the command is run by `sh -c` with a
+    /// script that changes them first.
+    fn command_line(&self) -> (String, Vec<String>) {
+        if !self.needs_shell() {
+            return (self.command.clone(), self.args.clone());
+        }
+
+        let cd = self
+            .current_dir
+            .as_ref()
+            .map(|dir| format!("cd {} || exit 1; ", shell_quote(dir)))
+            .unwrap_or_default();
+
+        let exports: String = self
+            .env
+            .iter()
+            .filter(|(key, _)| is_variable_name(key))
+            .map(|(key, value)| {
+                format!("export {}={}; ", key, shell_quote(value))
+            })
+            .collect();
+
+        let script = if self.args.is_empty() {
+            format!("{}{}{}", cd, exports, self.command)
+        } else {
+            let args: Vec<String> =
+                self.args.iter().map(|arg| shell_quote(arg)).collect();
+
+            format!(
+                "{}{}exec {} {}",
+                cd,
+                exports,
+                shell_quote(&self.command),
+                args.join(" ")
+            )
+        };
+
+        ("sh".to_owned(), vec!["-c".to_owned(), script])
+    }
+}
+
+/// Is the string a valid name for a shell variable.
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Quote a string so that the shell treats it as a single word.
+pub(crate) fn shell_quote(string: &str) -> String {
+    format!("'{}'", string.replace('\'', r"'\''"))
+}
+
+/// The state of a process that is passed to the callback of a
+/// `ProcessHook`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    /// The process is still running, more output will follow.
+    Running,
+    /// The process finished with the given exit code.
+    Finished(i32),
+    /// The process couldn't be started or was killed, e.g. because of a
+    /// timeout.
+    Error,
+}
+
+/// Output of a process that is passed to the callback of a `ProcessHook`.
+#[derive(Debug, Clone)]
+pub struct ProcessOutput<'a> {
+    /// The command that is running.
+    pub command: Cow<'a, str>,
+    /// The state of the process.
+    pub status: ProcessStatus,
+    /// The standard output of the process since the last call of the
+    /// callback.
+    pub stdout: Cow<'a, str>,
+    /// The standard error of the process since the last call of the
+    /// callback.
+    pub stderr: Cow<'a, str>,
+}
+
+/// Hook for a process, the process is killed and the hook removed when the
+/// object is dropped before the process finished.
+pub struct ProcessHook {
+    hook: Option<Hook>,
+    hook_data: Box<ProcessHookData>,
+}
+
+struct ProcessHookData {
+    callback: HookCallback<dyn FnMut(&Weechat, ProcessOutput) -> ReturnCode>,
+    finished: Cell<bool>,
+    weechat_ptr: *mut t_weechat_plugin,
+}
+
+impl sealed::RawHook for ProcessHook {
+    fn raw_hook(&self) -> Option<&Hook> {
+        self.hook
+            .as_ref()
+            .filter(|_| !self.hook_data.finished.get())
+    }
+}
+
+impl HookSettings for ProcessHook {}
+
+impl ProcessHook {
+    /// Run a command in the background.
+    ///
+    /// * `weechat` - The Weechat object of the plugin.
+    /// * `command` - The command that should be run.
+    /// * `callback` - A closure that will be called when the process
+    ///     produces output and when it finishes.
+    pub fn new<C>(
+        weechat: &Weechat,
+        command: &ProcessCommand,
+        callback: C,
+    ) -> ProcessHook
+    where
+        C: FnMut(&Weechat, ProcessOutput) -> ReturnCode + 'static,
+    {
+        weechat.new_process_hook(command, Box::new(callback))
+    }
+
+    /// Has the process finished, the callback won't be called anymore.
+    pub fn is_finished(&self) -> bool {
+        self.hook_data.finished.get()
+    }
+}
+
+impl Drop for ProcessHook {
+    fn drop(&mut self) {
+        // WeeChat removes the hook itself after the process finished.
+        if self.hook_data.finished.get() {
+            if let Some(hook) = self.hook.take() {
+                mem::forget(hook);
+            }
+        }
+    }
+}
+
+impl Weechat {
+    /// Run a command in the background.
+    ///
+    /// * `command` - The command that should be run.
+    /// * `callback` - A function that will be called when the process
+    ///     produces output and when it finishes.
+    /// * `callback_data` - Data that will be passed to the callback every time
+    ///     the callback runs. This data will be freed when the hook is unhooked.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `ProcessHook::new` with a closure instead, see the migration guide in the `hooks` module"
+    )]
+    pub fn hook_process<T>(
+        &self,
+        command: &ProcessCommand,
+        callback: fn(
+            data: &T,
+            weechat: &Weechat,
+            output: ProcessOutput,
+        ) -> ReturnCode,
+        callback_data: Option<T>,
+    ) -> ProcessHook
+    where
+        T: Default + 'static,
+    {
+        let data = callback_data.unwrap_or_default();
+
+        self.new_process_hook(
+            command,
+            Box::new(move |weechat, output| callback(&data, weechat, output)),
+        )
+    }
+
+    fn new_process_hook(
+        &self,
+        command: &ProcessCommand,
+        callback: Box<dyn FnMut(&Weechat, ProcessOutput) -> ReturnCode>,
+    ) -> ProcessHook {
+        unsafe extern "C" fn c_hook_cb(
+            pointer: *const c_void,
+            _data: *mut c_void,
+            command: *const c_char,
+            return_code: c_int,
+            out: *const c_char,
+            err: *const c_char,
+        ) -> c_int {
+            let hook_data: &ProcessHookData =
+                { &*(pointer as *const ProcessHookData) };
+            let weechat = Weechat::from_ptr(hook_data.weechat_ptr);
+
+            let to_str = |string: *const c_char| {
+                if string.is_null() {
+                    Cow::Borrowed("")
+                } else {
+                    CStr::from_ptr(string).to_string_lossy()
+                }
+            };
+
+            let status = match return_code {
+                HOOK_PROCESS_RUNNING => ProcessStatus::Running,
+                code if code >= 0 => ProcessStatus::Finished(code),
+                _ => ProcessStatus::Error,
+            };
+
+            let output = ProcessOutput {
+                command: to_str(command),
+                status,
+                stdout: to_str(out),
+                stderr: to_str(err),
+            };
+
+            if status != ProcessStatus::Running {
+                hook_data.finished.set(true);
+            }
+
+            hook_data
+                .callback
+                .call(&weechat, HookKind::Process, |callback| {
+                    profile(
+                        HookKind::Process,
+                        || output.command.clone().into_owned(),
+                        || callback(&weechat, output.clone()),
+                    )
+                })
+                .unwrap_or(ReturnCode::Error) as i32
+        }
+
+        let data = Box::new(ProcessHookData {
+            callback: HookCallback::new(callback),
+            finished: Cell::new(false),
+            weechat_ptr: self.ptr,
+        });
+
+        let data_ref = Box::leak(data);
+        let hook_process_hashtable = self.get().hook_process_hashtable.unwrap();
+
+        let mut options = HashMap::new();
+
+        if command.stdin.is_some() {
+            options.insert("stdin", "1".to_owned());
+        }
+        if command.detached {
+            options.insert("detached", "1".to_owned());
+        }
+        if let Some(bytes) = command.buffer_flush {
+            options.insert("buffer_flush", bytes.to_string());
+        }
+
+        if let Some(name) = &command.proxy {
+            if command.command.starts_with("url:") {
+                match self.proxy(name) {
+                    Some(proxy) => options.extend(proxy.url_options()),
+                    // The `.invalid` domain never resolves, the download
+                    // fails instead of bypassing the proxy.
+                    None => {
+                        options.insert("proxy", format!("{}.invalid", name));
+                    }
+                }
+            }
+        }
+
+        let (program, args) = command.command_line();
+        let arg_names: Vec<String> =
+            (1..=args.len()).map(|i| format!("arg{}", i)).collect();
+
+        for (name, arg) in arg_names.iter().zip(args) {
+            options.insert(name, arg);
+        }
+
+        let options = map_to_hashtable(self, &options);
+        let options_ptr = options.as_ref().map_or(ptr::null_mut(), |o| o.ptr);
+
+        let command_string = LossyCString::new(program);
+        let timeout = command
+            .timeout
+            .map_or(0, |t| t.as_millis().min(i32::MAX as u128) as i32);
+
+        let hook_ptr = unsafe {
+            hook_process_hashtable(
+                self.ptr,
+                command_string.as_ptr(),
+                options_ptr,
+                timeout,
+                Some(c_hook_cb),
+                data_ref as *const _ as *const c_void,
+                ptr::null_mut(),
+            )
+        };
+        let hook_data = unsafe { Box::from_raw(data_ref) };
+        let hook = Hook {
+            ptr: hook_ptr,
+            weechat_ptr: self.ptr,
+        };
+
+        if let Some(stdin) = &command.stdin {
+            hook.set("stdin", stdin);
+            hook.set("stdin_close", "1");
+        }
+
+        ProcessHook {
+            hook: Some(hook),
+            hook_data,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub use self::future::ProcessFuture;
+
+#[cfg(feature = "async")]
+mod future {
+    use std::borrow::Cow;
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::task::{Context, Poll, Waker};
+
+    use super::{ProcessCommand, ProcessHook, ProcessOutput, ProcessStatus};
+    use crate::{ReturnCode, Weechat};
+
+    #[derive(Default)]
+    struct ProcessState {
+        command: String,
+        status: Option<ProcessStatus>,
+        stdout: String,
+        stderr: String,
+        waker: Option<Waker>,
+    }
+
+    /// A future that resolves to the output of a command once it finished,
+    /// created with `Weechat::exec`.
+    ///
+    /// The process is killed if the future is dropped before the process
+    /// finished.
+    pub struct ProcessFuture {
+        _hook: ProcessHook,
+        state: Rc<RefCell<ProcessState>>,
+    }
+
+    impl Future for ProcessFuture {
+        type Output = ProcessOutput<'static>;
+
+        fn poll(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<ProcessOutput<'static>> {
+            let mut state = self.state.borrow_mut();
+
+            match state.status {
+                Some(status) => Poll::Ready(ProcessOutput {
+                    command: Cow::Owned(state.command.clone()),
+                    status,
+                    stdout: Cow::Owned(std::mem::take(&mut state.stdout)),
+                    stderr: Cow::Owned(std::mem::take(&mut state.stderr)),
+                }),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    impl Weechat {
+        /// Run a command in the background and wait for it to finish.
+        ///
+        /// The future resolves to the whole output of the command, its status
+        /// is either `ProcessStatus::Finished` or `ProcessStatus::Error`.
+        ///
+        /// ```ignore
+        /// let command = ProcessCommand::new("git").arg("pull");
+        /// let output = weechat.exec(&command).await;
+        /// ```
+        /// * `command` - The command that should be run.
+        pub fn exec(&self, command: &ProcessCommand) -> ProcessFuture {
+            let state = Rc::new(RefCell::new(ProcessState::default()));
+            let process_state = state.clone();
+
+            let hook = ProcessHook::new(self, command, move |_, output| {
+                let mut state = process_state.borrow_mut();
+
+                state.command = output.command.into_owned();
+                state.stdout.push_str(&output.stdout);
+                state.stderr.push_str(&output.stderr);
+
+                if output.status != ProcessStatus::Running {
+                    state.status = Some(output.status);
+
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+                }
+
+                ReturnCode::Ok
+            });
+
+            // WeeChat doesn't call the callback if the process couldn't be
+            // hooked, e.g. because of an invalid command.
+            if hook.hook.as_ref().is_none_or(|hook| hook.ptr.is_null()) {
+                let mut state = state.borrow_mut();
+                state.command = command.command.clone();
+                state.status = Some(ProcessStatus::Error);
+            }
+
+            ProcessFuture { _hook: hook, state }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_quote_wraps_in_single_quotes() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b; $HOME"), "'a b; $HOME'");
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("'"), r"''\'''");
+    }
+
+    #[test]
+    fn variable_names() {
+        assert!(is_variable_name("GIT_TERMINAL_PROMPT"));
+        assert!(is_variable_name("_x1"));
+        assert!(!is_variable_name(""));
+        assert!(!is_variable_name("1X"));
+        assert!(!is_variable_name("A B"));
+        assert!(!is_variable_name("A=B"));
+    }
+
+    #[test]
+    fn plain_command_is_passed_as_is() {
+        let command = ProcessCommand::new("git pull");
+
+        assert_eq!(command.command_line(), ("git pull".to_owned(), vec![]));
+    }
+
+    #[test]
+    fn arguments_are_passed_as_options() {
+        let command = ProcessCommand::new("git").arg("log").arg("a b");
+
+        assert_eq!(
+            command.command_line(),
+            ("git".to_owned(), vec!["log".to_owned(), "a b".to_owned()])
+        );
+    }
+
+    #[test]
+    fn environment_and_directory_use_a_shell() {
+        let command = ProcessCommand::new("git")
+            .arg("log")
+            .arg("it's")
+            .env("GIT_PAGER", "cat")
+            .env("NOT VALID", "ignored")
+            .current_dir("/tmp/my dir");
+
+        let script = concat!(
+            "cd '/tmp/my dir' || exit 1; export GIT_PAGER='cat'; ",
+            r"exec 'git' 'log' 'it'\''s'"
+        );
+
+        assert_eq!(
+            command.command_line(),
+            ("sh".to_owned(), vec!["-c".to_owned(), script.to_owned()])
+        );
+    }
+
+    #[test]
+    fn shell_command_without_arguments() {
+        let command = ProcessCommand::new("make all").current_dir("/src");
+        let script = "cd '/src' || exit 1; make all";
+
+        assert_eq!(
+            command.command_line(),
+            ("sh".to_owned(), vec!["-c".to_owned(), script.to_owned()])
+        );
+    }
+
+    #[test]
+    fn url_commands_ignore_the_shell_options() {
+        let command = ProcessCommand::new("url:https://weechat.org/")
+            .env("A", "b")
+            .current_dir("/tmp");
+
+        assert_eq!(
+            command.command_line(),
+            ("url:https://weechat.org/".to_owned(), vec![])
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Timing of hook callbacks.
+//!
+//! With the `profiling` feature enabled every hook callback is timed, the
+//! number of calls and the time spent in the callbacks is accumulated per
+//! hook. This helps finding callbacks that make WeeChat sluggish. Without
+//! the feature the callbacks are called directly.
+//!
+//! Hooks with a name, e.g. commands and signals, are listed by that name,
+//! hooks with the same name share a row. Hooks without a name, e.g. timers
+//! and file descriptors, are listed by the type of their callback and the
+//! address of the hook, e.g. `myplugin::timer_cb (0x55d0c8a4b2c0)`, so every
+//! hook gets its own row. Callbacks that only run once, e.g. the ones of
+//! `IdleHook` and `Weechat::call_later`, are listed by the type of the
+//! callback alone, otherwise every call would add a row.
+
+use crate::HookKind;
+
+#[cfg(feature = "profiling")]
+pub use self::stats::HookStats;
+
+/// Run a hook callback, timing it if the `profiling` feature is enabled.
+/// * `kind` - The kind of the hook the callback belongs to.
+/// * `name` - A function returning the name of the hook, e.g. the name of
+///     the command or signal, see the module documentation for hooks without
+///     a name. Only called if the callback is timed.
+/// * `callback` - The callback that should be run.
+#[cfg(feature = "profiling")]
+pub(crate) fn profile<N, F, R>(kind: HookKind, name: N, callback: F) -> R
+where
+    N: FnOnce() -> String,
+    F: FnOnce() -> R,
+{
+    let start = std::time::Instant::now();
+    let ret = callback();
+    stats::record(kind, name(), start.elapsed());
+    ret
+}
+
+#[cfg(not(feature = "profiling"))]
+#[inline(always)]
+pub(crate) fn profile<N, F, R>(_kind: HookKind, _name: N, callback: F) -> R
+where
+    N: FnOnce() -> String,
+    F: FnOnce() -> R,
+{
+    callback()
+}
+
+#[cfg(feature = "profiling")]
+mod stats {
+    use std::cell::RefCell;
+    use std::cmp::Reverse;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use crate::{
+        ArgsWeechat, Buffer, CommandDescription, CommandExists, CommandHook,
+        HookKind, Weechat,
+    };
+
+    thread_local! {
+        static STATS: RefCell<HashMap<(HookKind, String), HookStats>> =
+            RefCell::new(HashMap::new());
+    }
+
+    /// Accumulated timings of the callback of a hook.
+    #[derive(Debug, Clone)]
+    pub struct HookStats {
+        /// The kind of the hook.
+        pub kind: HookKind,
+        /// The name of the hook, e.g. the name of the command or signal. Hooks
+        /// without a name are identified by the type of their callback and
+        /// their address, see the module documentation.
+        pub name: String,
+        /// How many times the callback was called.
+        pub calls: u64,
+        /// The total time spent in the callback.
+        pub total: Duration,
+        /// The longest time a single call of the callback took.
+        pub max: Duration,
+    }
+
+    pub(super) fn record(kind: HookKind, name: String, elapsed: Duration) {
+        STATS.with(|stats| {
+            let mut stats = stats.borrow_mut();
+            let entry =
+                stats.entry((kind, name.clone())).or_insert_with(|| {
+                    HookStats {
+                        kind,
+                        name,
+                        calls: 0,
+                        total: Duration::default(),
+                        max: Duration::default(),
+                    }
+                });
+
+            entry.calls += 1;
+            entry.total += elapsed;
+            entry.max = entry.max.max(elapsed);
+        })
+    }
+
+    impl Weechat {
+        /// Get the accumulated timings of all the hook callbacks of the
+        /// plugin, sorted by the total time spent in the callbacks.
+        pub fn hook_stats(&self) -> Vec<HookStats> {
+            let mut stats: Vec<HookStats> =
+                STATS.with(|stats| stats.borrow().values().cloned().collect());
+            stats.sort_by_key(|stat| Reverse(stat.total));
+            stats
+        }
+
+        /// Reset the accumulated timings of all the hook callbacks.
+        pub fn reset_hook_stats(&self) {
+            STATS.with(|stats| stats.borrow_mut().clear());
+        }
+
+        /// Create a `/<plugin>_stats` command that prints the accumulated
+        /// timings of the hook callbacks, running it with the `reset`
+        /// argument resets the timings.
+        ///
+        /// Returns an error if the command can't be hooked.
+        pub fn hook_stats_command(&self) -> Result<CommandHook, CommandExists> {
+            fn command_cb(
+                weechat: &Weechat,
+                buffer: Buffer,
+                mut args: ArgsWeechat,
+            ) {
+                if args.nth(1).as_deref() == Some("reset") {
+                    weechat.reset_hook_stats();
+                    buffer.print("Hook statistics have been reset");
+                    return;
+                }
+
+                let stats = weechat.hook_stats();
+
+                if stats.is_empty() {
+                    buffer.print("No hook callbacks have been called");
+                    return;
+                }
+
+                buffer.print("Hook statistics (calls, total, average, max):");
+
+                for stat in stats {
+                    buffer.print(&format!(
+                        "  {} {}: {}, {:?}, {:?}, {:?}",
+                        stat.kind.as_str(),
+                        stat.name,
+                        stat.calls,
+                        stat.total,
+                        stat.total / stat.calls.max(1) as u32,
+                        stat.max,
+                    ));
+                }
+            }
+
+            let name = format!("{}_stats", self.plugin_name());
+
+            CommandHook::new(
+                self,
+                CommandDescription {
+                    name: &name,
+                    description: "show the timings of the hook callbacks",
+                    args: "[reset]",
+                    args_description: "reset: reset the timings",
+                    completion: "reset",
+                    ..Default::default()
+                },
+                command_cb,
+            )
+        }
+    }
+}
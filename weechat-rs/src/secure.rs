@@ -0,0 +1,208 @@
+//! Helpers to store credentials in the secured data of WeeChat (sec.conf).
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::{BufferBuilder, BufferRef, ModifierHook, OwnedBuffer, Weechat};
+
+/// Error returned when a name or a value can't be stored in the secured
+/// data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecuredDataError {
+    /// The name is empty or contains characters other than ASCII letters,
+    /// digits, `_` and `.`.
+    InvalidName(String),
+    /// The value is empty, starts or ends with whitespace or contains a
+    /// newline or a nul byte. The `/secure` command would store such a value
+    /// differently than it was given.
+    InvalidValue,
+    /// The core buffer of WeeChat, which runs the `/secure` command, wasn't
+    /// found.
+    CoreBufferNotFound,
+}
+
+impl fmt::Display for SecuredDataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecuredDataError::InvalidName(name) => {
+                write!(f, "invalid secured data name \"{}\"", name)
+            }
+            SecuredDataError::InvalidValue => write!(
+                f,
+                "the value can't be empty, start or end with whitespace or \
+                 contain a newline"
+            ),
+            SecuredDataError::CoreBufferNotFound => {
+                write!(f, "the core buffer of WeeChat wasn't found")
+            }
+        }
+    }
+}
+
+impl Error for SecuredDataError {}
+
+fn check_name(name: &str) -> Result<(), SecuredDataError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(SecuredDataError::InvalidName(name.to_owned()))
+    }
+}
+
+fn check_value(value: &str) -> Result<(), SecuredDataError> {
+    // The command line is split at whitespace, leading and trailing
+    // whitespace of the value would be lost.
+    let valid = !value.is_empty()
+        && value.trim() == value
+        && !value.contains(['\n', '\r', '\0']);
+
+    if valid {
+        Ok(())
+    } else {
+        Err(SecuredDataError::InvalidValue)
+    }
+}
+
+impl Weechat {
+    /// Get the value of a secured data.
+    /// * `name` - The name of the secured data.
+    ///
+    /// Returns None if the secured data isn't set or if the name isn't
+    /// valid, see `SecuredDataError::InvalidName`.
+    pub fn secured_data(&self, name: &str) -> Option<String> {
+        check_name(name).ok()?;
+
+        self.eval_string_expression(&format!("${{sec.data.{}}}", name))
+            .filter(|value| !value.is_empty())
+            .map(Cow::into_owned)
+    }
+
+    /// Store a value in the secured data.
+    /// * `name` - The name of the secured data, it may only contain ASCII
+    ///     letters, digits, `_` and `.`.
+    /// * `value` - The value that should be stored.
+    ///
+    /// Returns an error if the name or the value is invalid or if the core
+    /// buffer, which runs the `/secure` command, wasn't found.
+    pub fn set_secured_data(
+        &self,
+        name: &str,
+        value: &str,
+    ) -> Result<(), SecuredDataError> {
+        check_name(name)?;
+        check_value(value)?;
+
+        let buffer = self
+            .buffer_search("core", "weechat")
+            .ok_or(SecuredDataError::CoreBufferNotFound)?;
+
+        buffer.run_command(&format!("/mute /secure set {} {}", name, value));
+
+        Ok(())
+    }
+
+    /// Ask the user for a secret, e.g. a password, and store it in the
+    /// secured data.
+    ///
+    /// A temporary buffer is opened that asks the user to enter the secret,
+    /// the buffer is closed once the secret was entered. The input line
+    /// isn't masked, the same as for the `/secure set` command, the secret
+    /// isn't printed to the buffer and it isn't added to the input history
+    /// though.
+    ///
+    /// Returns the buffer that was opened, the prompt is cancelled and the
+    /// buffer closed if the handle is dropped before the secret was
+    /// entered.
+    /// * `name` - The name of the secured data the secret is stored in, it
+    ///     may only contain ASCII letters, digits, `_` and `.`.
+    /// * `description` - A description of the secret that is shown to the
+    ///     user, e.g. "the password for the foo service".
+    /// * `callback` - A function that will be called with the secret once the
+    ///     user entered it.
+    /// * `callback_data` - Data that will be passed to the callback.
+    pub fn prompt_secret<T: Default + 'static>(
+        &self,
+        name: &str,
+        description: &str,
+        callback: fn(data: &T, weechat: &Weechat, secret: &str),
+        callback_data: Option<T>,
+    ) -> Result<OwnedBuffer, SecuredDataError> {
+        check_name(name)?;
+
+        let secret_name = name.to_owned();
+        let callback_data = callback_data.unwrap_or_default();
+        let mut done = false;
+
+        let input_cb =
+            move |weechat: &Weechat, buffer: BufferRef, input: Cow<str>| {
+                if done {
+                    return;
+                }
+
+                let stored = weechat.set_secured_data(&secret_name, &input);
+                if let Err(e) = stored {
+                    buffer.print(&format!("Error: {}, please try again.", e));
+                    return;
+                }
+                done = true;
+
+                callback(&callback_data, weechat, &input);
+
+                // The buffer can't be closed while its input callback is
+                // running, let WeeChat close it once the callback returns.
+                buffer.run_command(&format!(
+                    "/wait 1ms /buffer close {}",
+                    buffer.get_full_name()
+                ));
+            };
+
+        // The hook that keeps the secret out of the input history, it's
+        // removed when the buffer is closed.
+        let history_hook: Rc<RefCell<Option<ModifierHook>>> =
+            Rc::new(RefCell::new(None));
+        let close_hook = Rc::clone(&history_hook);
+
+        let buffer = BufferBuilder::new(&format!("secret_{}", name))
+            .input_callback(input_cb)
+            .close_callback(move |_, _| {
+                close_hook.borrow_mut().take();
+            })
+            .build(self);
+
+        let buffer_ptr = format!("{:p}", buffer.ptr);
+        *history_hook.borrow_mut() = Some(ModifierHook::new(
+            self,
+            "history_add",
+            move |_, _, modifier_data, _| {
+                if modifier_data == buffer_ptr {
+                    Some(String::new())
+                } else {
+                    None
+                }
+            },
+        ));
+
+        buffer.set_title(format!("Enter {}", description));
+        buffer.disable_log();
+        buffer.print(&format!(
+            "Please enter {} and press enter, it will be stored in the \
+             secured data as \"{}\".",
+            description, name
+        ));
+        buffer.print(&format!(
+            "Alternatively close this buffer and run: /secure set {} <value>",
+            name
+        ));
+        buffer.switch_to();
+
+        Ok(buffer)
+    }
+}
@@ -0,0 +1,394 @@
+//! String helpers that follow the charset and casing rules of WeeChat.
+
+use libc::c_char;
+use std::cmp::Ordering;
+use std::ffi::{CStr, CString};
+use std::fmt;
+
+use weechat_sys::t_weechat_plugin;
+
+use crate::{LossyCString, Weechat};
+
+/// The range of chars that are considered for case insensitive comparisons,
+/// this corresponds to the casemappings of IRC servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseRange {
+    /// Only the ASCII letters `A-Z` are case folded, `ascii` casemapping.
+    Ascii,
+    /// `A-Z` and `[\]` are case folded, `strict-rfc1459` casemapping.
+    StrictRfc1459,
+    /// `A-Z` and `[\]^` are case folded, `rfc1459` casemapping.
+    Rfc1459,
+}
+
+impl CaseRange {
+    fn as_int(self) -> i32 {
+        match self {
+            CaseRange::Ascii => 26,
+            CaseRange::StrictRfc1459 => 29,
+            CaseRange::Rfc1459 => 30,
+        }
+    }
+}
+
+impl Weechat {
+    /// Compare two strings ignoring the case, using the locale and charset
+    /// rules of WeeChat.
+    pub fn strcasecmp(&self, string1: &str, string2: &str) -> Ordering {
+        let strcasecmp = self.get().strcasecmp.unwrap();
+
+        let string1 = LossyCString::new(string1);
+        let string2 = LossyCString::new(string2);
+
+        let ret = unsafe { strcasecmp(string1.as_ptr(), string2.as_ptr()) };
+
+        ret.cmp(&0)
+    }
+
+    /// Compare two strings ignoring the case for a range of chars, e.g. to
+    /// compare nicks or channel names according to the casemapping of an IRC
+    /// server.
+    pub fn strcasecmp_range(
+        &self,
+        string1: &str,
+        string2: &str,
+        range: CaseRange,
+    ) -> Ordering {
+        let strcasecmp_range = self.get().strcasecmp_range.unwrap();
+
+        let string1 = LossyCString::new(string1);
+        let string2 = LossyCString::new(string2);
+
+        let ret = unsafe {
+            strcasecmp_range(string1.as_ptr(), string2.as_ptr(), range.as_int())
+        };
+
+        ret.cmp(&0)
+    }
+
+    /// Compare the first chars of two strings ignoring the case.
+    /// * `max` - The number of chars that should be compared.
+    pub fn strncasecmp(
+        &self,
+        string1: &str,
+        string2: &str,
+        max: usize,
+    ) -> Ordering {
+        let strncasecmp = self.get().strncasecmp.unwrap();
+
+        let string1 = LossyCString::new(string1);
+        let string2 = LossyCString::new(string2);
+
+        let ret = unsafe {
+            strncasecmp(string1.as_ptr(), string2.as_ptr(), max as i32)
+        };
+
+        ret.cmp(&0)
+    }
+
+    /// Compare the first chars of two strings ignoring the case for a range
+    /// of chars.
+    /// * `max` - The number of chars that should be compared.
+    pub fn strncasecmp_range(
+        &self,
+        string1: &str,
+        string2: &str,
+        max: usize,
+        range: CaseRange,
+    ) -> Ordering {
+        let strncasecmp_range = self.get().strncasecmp_range.unwrap();
+
+        let string1 = LossyCString::new(string1);
+        let string2 = LossyCString::new(string2);
+
+        let ret = unsafe {
+            strncasecmp_range(
+                string1.as_ptr(),
+                string2.as_ptr(),
+                max as i32,
+                range.as_int(),
+            )
+        };
+
+        ret.cmp(&0)
+    }
+
+    /// Compare two strings while ignoring some chars.
+    /// * `chars_ignored` - The chars that should be ignored.
+    /// * `case_sensitive` - Should the comparison be case sensitive.
+    pub fn strcmp_ignore_chars(
+        &self,
+        string1: &str,
+        string2: &str,
+        chars_ignored: &str,
+        case_sensitive: bool,
+    ) -> Ordering {
+        let strcmp_ignore_chars = self.get().strcmp_ignore_chars.unwrap();
+
+        let string1 = LossyCString::new(string1);
+        let string2 = LossyCString::new(string2);
+        let chars_ignored = LossyCString::new(chars_ignored);
+
+        let ret = unsafe {
+            strcmp_ignore_chars(
+                string1.as_ptr(),
+                string2.as_ptr(),
+                chars_ignored.as_ptr(),
+                case_sensitive as i32,
+            )
+        };
+
+        ret.cmp(&0)
+    }
+
+    /// Search for a string in another string ignoring the case.
+    ///
+    /// Returns the byte offset of the first match.
+    pub fn strcasestr(&self, string: &str, search: &str) -> Option<usize> {
+        let strcasestr = self.get().strcasestr.unwrap();
+
+        let c_string = LossyCString::new(string);
+        let search = LossyCString::new(search);
+
+        let ret = unsafe { strcasestr(c_string.as_ptr(), search.as_ptr()) };
+
+        if ret.is_null() {
+            None
+        } else {
+            Some(unsafe { ret.offset_from(c_string.as_ptr()) } as usize)
+        }
+    }
+
+    /// Convert a string to lower case the same way WeeChat does.
+    pub fn string_tolower(&self, string: &str) -> String {
+        let string_tolower = self.get().string_tolower.unwrap();
+
+        let string = LossyCString::new(string).into_raw();
+
+        unsafe {
+            string_tolower(string);
+            CString::from_raw(string).to_string_lossy().into_owned()
+        }
+    }
+
+    /// Convert a string to upper case the same way WeeChat does.
+    pub fn string_toupper(&self, string: &str) -> String {
+        let string_toupper = self.get().string_toupper.unwrap();
+
+        let string = LossyCString::new(string).into_raw();
+
+        unsafe {
+            string_toupper(string);
+            CString::from_raw(string).to_string_lossy().into_owned()
+        }
+    }
+
+    /// Get the number of chars in a UTF-8 string.
+    pub fn utf8_strlen(&self, string: &str) -> usize {
+        let utf8_strlen = self.get().utf8_strlen.unwrap();
+
+        let string = LossyCString::new(string);
+
+        unsafe { utf8_strlen(string.as_ptr()).max(0) as usize }
+    }
+
+    /// Get the number of columns that are needed to display a UTF-8 string
+    /// on screen, wide chars take up two columns.
+    ///
+    /// Color codes aren't handled by this method, use `strlen_screen` for
+    /// strings that contain color codes.
+    pub fn utf8_strlen_screen(&self, string: &str) -> usize {
+        let utf8_strlen_screen = self.get().utf8_strlen_screen.unwrap();
+
+        let string = LossyCString::new(string);
+
+        unsafe { utf8_strlen_screen(string.as_ptr()).max(0) as usize }
+    }
+
+    /// Get the number of columns that are needed to display a single char on
+    /// screen.
+    pub fn utf8_char_size_screen(&self, c: char) -> usize {
+        let utf8_char_size_screen = self.get().utf8_char_size_screen.unwrap();
+
+        let mut buf = [0; 4];
+        let string = LossyCString::new(c.encode_utf8(&mut buf));
+
+        unsafe { utf8_char_size_screen(string.as_ptr()).max(0) as usize }
+    }
+
+    /// Get the number of columns that are needed to display a string on
+    /// screen, color codes in the string are skipped.
+    pub fn strlen_screen(&self, string: &str) -> usize {
+        let strlen_screen = self.get().strlen_screen.unwrap();
+
+        let string = LossyCString::new(string);
+
+        unsafe { strlen_screen(string.as_ptr()).max(0) as usize }
+    }
+
+    /// Pad a string with spaces so that it takes up a number of columns on
+    /// screen, this can be used to align columns in buffers with free
+    /// content.
+    ///
+    /// The string is returned unchanged if it's already wider than `width`.
+    /// * `string` - The string that should be padded, may contain color
+    ///     codes.
+    /// * `width` - The number of columns the string should take up.
+    pub fn pad_screen(&self, string: &str, width: usize) -> String {
+        let padding = width.saturating_sub(self.strlen_screen(string));
+        format!("{}{}", string, " ".repeat(padding))
+    }
+}
+
+/// A string that grows in place, backed by a dynamic string of WeeChat.
+///
+/// Appending to the string only reallocates when the allocated size is
+/// exceeded, this makes it cheap to build up large output before printing it
+/// with a single call. The string implements `fmt::Write`, so `write!` can be
+/// used to append formatted text.
+///
+/// ```ignore
+/// fn print_entries(weechat: &Weechat, buffer: &Buffer) -> fmt::Result {
+///     let mut output = weechat.dyn_string(4096).ok_or(fmt::Error)?;
+///
+///     for entry in entries() {
+///         writeln!(output, "{}: {}", entry.name, entry.value)?;
+///     }
+///
+///     buffer.print(output.as_str());
+///     Ok(())
+/// }
+/// ```
+pub struct DynString {
+    weechat_ptr: *mut t_weechat_plugin,
+    ptr: *mut *mut c_char,
+}
+
+impl DynString {
+    /// Append a string, nul bytes in the string are removed.
+    ///
+    /// Returns an error if WeeChat couldn't grow the string, a part of the
+    /// appended string might have been added in that case.
+    pub fn push_str(
+        &mut self,
+        string: &str,
+    ) -> Result<&mut DynString, fmt::Error> {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let string_dyn_concat = weechat.get().string_dyn_concat.unwrap();
+
+        // WeeChat expects a nul terminated string, the string is copied to
+        // it in chunks through a buffer on the stack so that appending
+        // doesn't need an allocation. The chunks end at char boundaries, so
+        // the content stays valid UTF-8 even if appending fails.
+        let mut chunk = [0 as c_char; 256];
+
+        for mut rest in string.split('\0') {
+            while !rest.is_empty() {
+                let mut end = rest.len().min(chunk.len() - 1);
+                while !rest.is_char_boundary(end) {
+                    end -= 1;
+                }
+                let (part, tail) = rest.split_at(end);
+                rest = tail;
+
+                for (c, byte) in chunk.iter_mut().zip(part.bytes()) {
+                    *c = byte as c_char;
+                }
+                chunk[part.len()] = 0;
+
+                let ret =
+                    unsafe { string_dyn_concat(self.ptr, chunk.as_ptr(), -1) };
+
+                if ret == 0 {
+                    return Err(fmt::Error);
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Replace the content of the string.
+    ///
+    /// Returns an error if WeeChat couldn't grow the string, see
+    /// `push_str()`.
+    pub fn set(&mut self, string: &str) -> fmt::Result {
+        self.clear();
+        self.push_str(string).map(|_| ())
+    }
+
+    /// Remove the content of the string, the allocated memory is kept.
+    pub fn clear(&mut self) {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let string_dyn_copy = weechat.get().string_dyn_copy.unwrap();
+
+        // Copying an empty string never needs to grow the string.
+        unsafe {
+            string_dyn_copy(self.ptr, b"\0".as_ptr() as *const c_char);
+        }
+    }
+
+    /// Get the content of the string.
+    pub fn as_str(&self) -> &str {
+        let bytes = unsafe { CStr::from_ptr(*self.ptr).to_bytes() };
+
+        // The content is only ever built from whole chars of Rust strings,
+        // so it's valid UTF-8.
+        unsafe { std::str::from_utf8_unchecked(bytes) }
+    }
+
+    /// Get the length of the string in bytes.
+    pub fn len(&self) -> usize {
+        self.as_str().len()
+    }
+
+    /// Is the string empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Free the dynamic string and return its content.
+    pub fn into_string(self) -> String {
+        self.as_str().to_owned()
+    }
+}
+
+impl fmt::Write for DynString {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.push_str(string).map(|_| ())
+    }
+}
+
+impl Drop for DynString {
+    fn drop(&mut self) {
+        let weechat = Weechat::from_ptr(self.weechat_ptr);
+        let string_dyn_free = weechat.get().string_dyn_free.unwrap();
+
+        unsafe {
+            string_dyn_free(self.ptr, 1);
+        }
+    }
+}
+
+impl Weechat {
+    /// Create a new empty dynamic string.
+    /// * `size` - The initial number of bytes that should be allocated.
+    ///
+    /// Returns None if the string couldn't be allocated.
+    pub fn dyn_string(&self, size: usize) -> Option<DynString> {
+        let string_dyn_alloc = self.get().string_dyn_alloc.unwrap();
+
+        let ptr = unsafe {
+            string_dyn_alloc(size.clamp(1, i32::MAX as usize) as i32)
+        };
+
+        if ptr.is_null() {
+            None
+        } else {
+            Some(DynString {
+                weechat_ptr: self.ptr,
+                ptr,
+            })
+        }
+    }
+}
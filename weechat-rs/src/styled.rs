@@ -0,0 +1,115 @@
+//! Builder for text containing WeeChat color codes.
+
+use crate::Weechat;
+
+/// Text that is made up of differently colored or styled parts.
+///
+/// The color codes are resolved when the text is displayed, this allows the
+/// text to be built up without a `Weechat` object.
+///
+/// ```ignore
+/// let title = StyledText::new()
+///     .color("bold")
+///     .text("Server")
+///     .reset()
+///     .text(": irc.libera.chat");
+///
+/// buffer.set_title(title);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyledText {
+    parts: Vec<StyledPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StyledPart {
+    Text(String),
+    Color(String),
+}
+
+impl StyledText {
+    /// Create a new empty styled text.
+    pub fn new() -> StyledText {
+        StyledText::default()
+    }
+
+    /// Append plain text.
+    /// * `text` - The text that should be appended.
+    pub fn text(mut self, text: &str) -> StyledText {
+        self.parts.push(StyledPart::Text(text.to_owned()));
+        self
+    }
+
+    /// Switch to a different color or attribute for the text that follows.
+    /// * `color` - The name of a WeeChat color or attribute, e.g. `red`,
+    ///     `bold`, `*yellow,blue` or the name of a color option.
+    pub fn color(mut self, color: &str) -> StyledText {
+        self.parts.push(StyledPart::Color(color.to_owned()));
+        self
+    }
+
+    /// Append text in a color, the color is reset after the text.
+    /// * `color` - The name of the color the text should be displayed with.
+    /// * `text` - The text that should be appended.
+    pub fn colored(self, color: &str, text: &str) -> StyledText {
+        self.color(color).text(text).reset()
+    }
+
+    /// Reset the color and all attributes for the text that follows.
+    pub fn reset(self) -> StyledText {
+        self.color("reset")
+    }
+
+    /// Is the styled text empty.
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Get the text without any color codes.
+    pub fn plain(&self) -> String {
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                StyledPart::Text(text) => Some(text.as_str()),
+                StyledPart::Color(_) => None,
+            })
+            .collect()
+    }
+
+    /// Convert the styled text into a string containing WeeChat color codes.
+    /// * `weechat` - The Weechat object used to resolve the colors.
+    pub fn render(&self, weechat: &Weechat) -> String {
+        let mut rendered = String::new();
+
+        for part in &self.parts {
+            match part {
+                StyledPart::Text(text) => rendered.push_str(text),
+                StyledPart::Color(color) => {
+                    rendered.push_str(&weechat.color(color))
+                }
+            }
+        }
+
+        rendered
+    }
+}
+
+impl From<&str> for StyledText {
+    fn from(text: &str) -> StyledText {
+        StyledText::new().text(text)
+    }
+}
+
+impl From<String> for StyledText {
+    fn from(text: String) -> StyledText {
+        StyledText {
+            parts: vec![StyledPart::Text(text)],
+        }
+    }
+}
+
+impl From<&String> for StyledText {
+    fn from(text: &String) -> StyledText {
+        StyledText::new().text(text)
+    }
+}
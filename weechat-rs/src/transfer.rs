@@ -0,0 +1,224 @@
+//! Progress reporting for long running transfers, e.g. file transfers or
+//! uploads.
+//!
+//! A transfer periodically prints its progress to a buffer, updates are
+//! throttled so that a transfer that makes progress very often doesn't flood
+//! the buffer. Every transfer gets its own command that allows the user to
+//! cancel the transfer.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::{
+    ArgsWeechat, Buffer, CommandDescription, CommandExists, CommandHook,
+    TimerHook, Weechat,
+};
+
+static TRANSFER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A long running transfer that reports its progress to a buffer.
+///
+/// The progress report and the cancel command are removed when the object is
+/// dropped.
+pub struct Transfer {
+    state: Rc<RefCell<TransferState>>,
+    _timer: TimerHook,
+    _command: CommandHook,
+}
+
+#[derive(Default)]
+struct TransferState {
+    name: String,
+    command: String,
+    buffer: Option<Buffer>,
+    total: Option<u64>,
+    transferred: u64,
+    reported: Option<u64>,
+    cancelled: bool,
+    finished: bool,
+}
+
+impl TransferState {
+    fn progress(&self, weechat: &Weechat) -> String {
+        let transferred = weechat.string_format_size(self.transferred);
+
+        match self.total {
+            Some(total) if total > 0 => format!(
+                "{} / {} ({}%)",
+                transferred,
+                weechat.string_format_size(total),
+                self.transferred.min(total) * 100 / total
+            ),
+            _ => transferred,
+        }
+    }
+
+    fn print(&self, message: &str) {
+        if let Some(buffer) = &self.buffer {
+            buffer.print(&format!("{}: {}", self.name, message));
+        }
+    }
+
+    fn report(&mut self) {
+        if self.cancelled
+            || self.finished
+            || self.reported == Some(self.transferred)
+        {
+            return;
+        }
+
+        let weechat = match &self.buffer {
+            Some(b) => b.get_weechat(),
+            None => return,
+        };
+
+        self.print(&format!(
+            "{} (/{} cancel to abort)",
+            self.progress(&weechat),
+            self.command
+        ));
+        self.reported = Some(self.transferred);
+    }
+}
+
+impl Transfer {
+    /// Get the name of the command that cancels the transfer.
+    pub fn command_name(&self) -> String {
+        self.state.borrow().command.clone()
+    }
+
+    /// Update the number of bytes that have been transferred so far.
+    ///
+    /// The progress isn't printed immediately, it will be printed the next
+    /// time the update interval expires.
+    /// * `transferred` - The number of bytes that have been transferred.
+    pub fn update(&self, transferred: u64) {
+        self.state.borrow_mut().transferred = transferred;
+    }
+
+    /// Set the total size of the transfer, if it wasn't known when the
+    /// transfer was created.
+    /// * `total` - The total size of the transfer in bytes.
+    pub fn set_total(&self, total: u64) {
+        self.state.borrow_mut().total = Some(total);
+    }
+
+    /// Has the transfer been cancelled by the user.
+    ///
+    /// The code driving the transfer should check this and stop the transfer
+    /// if it returns true.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.borrow().cancelled
+    }
+
+    /// Mark the transfer as finished and print a final message to the
+    /// buffer.
+    pub fn finish(&self) {
+        let mut state = self.state.borrow_mut();
+
+        if state.cancelled || state.finished {
+            return;
+        }
+
+        let weechat = match &state.buffer {
+            Some(b) => b.get_weechat(),
+            None => return,
+        };
+
+        state.print(&format!(
+            "finished, {}",
+            weechat.string_format_size(state.transferred)
+        ));
+        state.finished = true;
+    }
+}
+
+impl Weechat {
+    /// Start reporting the progress of a long running transfer.
+    ///
+    /// A command named `/<plugin>_transfer_<number>` is created for every
+    /// transfer, running it with the `cancel` argument cancels the transfer.
+    /// * `buffer` - The buffer where the progress will be printed.
+    /// * `name` - A name for the transfer that will be used as a prefix for
+    ///     all the messages of the transfer, e.g. a file name.
+    /// * `total` - The total size of the transfer in bytes, if known.
+    /// * `interval` - The minimal delay between two progress updates.
+    ///
+    /// Returns an error if the cancel command of the transfer can't be hooked.
+    pub fn new_transfer(
+        &self,
+        buffer: &Buffer,
+        name: &str,
+        total: Option<u64>,
+        interval: Duration,
+    ) -> Result<Transfer, CommandExists> {
+        fn timer_cb(
+            state: &Rc<RefCell<TransferState>>,
+            _weechat: &Weechat,
+            _remaining: i32,
+        ) {
+            state.borrow_mut().report();
+        }
+
+        fn command_cb(
+            state: &Rc<RefCell<TransferState>>,
+            buffer: Buffer,
+            mut args: ArgsWeechat,
+        ) {
+            let mut state = state.borrow_mut();
+
+            match args.nth(1).as_deref() {
+                Some("cancel") if !state.cancelled && !state.finished => {
+                    state.print("cancelled");
+                    state.cancelled = true;
+                }
+                Some("cancel") => {
+                    buffer.print(&format!(
+                        "{}: the transfer isn't running anymore",
+                        state.name
+                    ));
+                }
+                _ => (),
+            }
+        }
+
+        let id = TRANSFER_ID.fetch_add(1, Ordering::Relaxed);
+        let command_name = format!("{}_transfer_{}", self.plugin_name(), id);
+
+        let state = Rc::new(RefCell::new(TransferState {
+            name: name.to_owned(),
+            command: command_name.clone(),
+            buffer: Some(Buffer::wrap(buffer.weechat, buffer.ptr)),
+            total,
+            ..Default::default()
+        }));
+
+        let command_state = state.clone();
+        let command = CommandHook::new(
+            self,
+            CommandDescription {
+                name: &command_name,
+                description: "control a running transfer",
+                args: "cancel",
+                args_description: "cancel: abort the transfer",
+                completion: "cancel",
+                ..Default::default()
+            },
+            move |_, buffer, args| command_cb(&command_state, buffer, args),
+        )?;
+
+        let timer_state = state.clone();
+        let timer =
+            TimerHook::new(self, interval, 0, 0, move |weechat, remaining| {
+                timer_cb(&timer_state, weechat, remaining)
+            });
+
+        Ok(Transfer {
+            state,
+            _timer: timer,
+            _command: command,
+        })
+    }
+}
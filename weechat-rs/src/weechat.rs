@@ -2,16 +2,45 @@
 
 use weechat_sys::t_weechat_plugin;
 
-use crate::{ConfigOption, LossyCString, StringOption};
+use crate::{ConfigOption, IntegerOption, LossyCString, StringOption};
 use libc::{c_char, c_int};
 use std::borrow::Cow;
+use std::error::Error;
 use std::ffi::CStr;
-use std::{ptr, vec};
-
-/// An iterator over the arguments of a command, yielding a String value for
-/// each argument.
+use std::fmt;
+use std::ptr;
+
+/// An iterator over the arguments of a command or of the plugin, yielding a
+/// String value for each argument.
+///
+/// The arguments of the plugin are the arguments of `/plugin load`, they
+/// are passed to `WeechatPlugin::init`.
+///
+/// The arguments of commands also keep their original spacing, see `eol()`.
+#[derive(Debug, Clone)]
 pub struct ArgsWeechat {
-    iter: vec::IntoIter<String>,
+    args: Vec<String>,
+    eol: Vec<String>,
+    front: usize,
+    back: usize,
+}
+
+/// Convert an argv array of WeeChat into strings, a null array results in an
+/// empty vector.
+fn argv_to_vec(argc: c_int, argv: *mut *mut c_char) -> Vec<String> {
+    if argv.is_null() {
+        return Vec::new();
+    }
+
+    (0..argc.max(0) as isize)
+        .map(|i| {
+            let cstr = unsafe {
+                CStr::from_ptr(*argv.offset(i) as *const libc::c_char)
+            };
+
+            String::from_utf8_lossy(cstr.to_bytes()).into_owned()
+        })
+        .collect()
 }
 
 impl ArgsWeechat {
@@ -19,41 +48,116 @@ impl ArgsWeechat {
     /// Expects the strings in argv to be valid utf8, if not invalid UTF-8
     /// sequences are replaced with the replacement character.
     pub fn new(argc: c_int, argv: *mut *mut c_char) -> ArgsWeechat {
-        let argc = argc as isize;
-        let args: Vec<String> = (0..argc)
-            .map(|i| {
-                let cstr = unsafe {
-                    CStr::from_ptr(*argv.offset(i) as *const libc::c_char)
-                };
-
-                String::from_utf8_lossy(&cstr.to_bytes().to_vec()).to_string()
-            })
-            .collect();
+        ArgsWeechat::with_eol(argc, argv, ptr::null_mut())
+    }
+
+    /// Create an ArgsWeechat object from the `argv` and `argv_eol` arrays
+    /// that WeeChat passes to command callbacks.
+    /// * `argc` - The number of arguments.
+    /// * `argv` - The arguments split at spaces.
+    /// * `argv_eol` - The arguments from the Nth argument to the end of the
+    ///     line, may be null.
+    pub(crate) fn with_eol(
+        argc: c_int,
+        argv: *mut *mut c_char,
+        argv_eol: *mut *mut c_char,
+    ) -> ArgsWeechat {
+        let args = argv_to_vec(argc, argv);
+        let back = args.len();
+
         ArgsWeechat {
-            iter: args.clone().into_iter(),
+            args,
+            eol: argv_to_vec(argc, argv_eol),
+            front: 0,
+            back,
+        }
+    }
+
+    /// Get the text of the command line starting at the argument with the
+    /// given index up to the end of the line, with the original spacing.
+    ///
+    /// The index counts from the command itself, e.g. `eol(2)` of
+    /// `/msg alice hello   there` is `hello   there`. The index doesn't
+    /// change while the arguments are iterated over.
+    ///
+    /// Returns None if the index is out of range or if the arguments weren't
+    /// created from a command line.
+    /// * `index` - The index of the first argument.
+    pub fn eol(&self, index: usize) -> Option<&str> {
+        self.eol.get(index).map(String::as_str)
+    }
+
+    /// Get the text of the command line starting at the next argument the
+    /// iterator yields, with the original spacing.
+    ///
+    /// Returns None if all arguments were consumed or if the arguments
+    /// weren't created from a command line.
+    pub fn remaining_eol(&self) -> Option<&str> {
+        if self.front < self.back {
+            self.eol(self.front)
+        } else {
+            None
         }
     }
+
+    /// Get the arguments the iterator hasn't yielded yet.
+    pub fn as_slice(&self) -> &[String] {
+        &self.args[self.front..self.back]
+    }
+
+    /// Copy the arguments the iterator hasn't yielded yet into a vector.
+    pub fn to_vec(&self) -> Vec<String> {
+        self.as_slice().to_vec()
+    }
+
+    /// Consume the remaining arguments and join them into a single string,
+    /// e.g. the text of a message.
+    ///
+    /// The original spacing is kept if the arguments were created from a
+    /// command line and no arguments were consumed from the back, otherwise
+    /// the arguments are joined with single spaces.
+    pub fn rest(&mut self) -> String {
+        let rest = match self.remaining_eol() {
+            Some(eol) if self.back == self.args.len() => eol.to_owned(),
+            _ => self.as_slice().join(" "),
+        };
+
+        self.front = self.back;
+
+        rest
+    }
 }
 
 impl Iterator for ArgsWeechat {
     type Item = String;
     fn next(&mut self) -> Option<String> {
-        self.iter.next()
+        if self.front < self.back {
+            self.front += 1;
+            Some(std::mem::take(&mut self.args[self.front - 1]))
+        } else {
+            None
+        }
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.iter.size_hint()
+        let len = self.back - self.front;
+        (len, Some(len))
     }
 }
 
 impl ExactSizeIterator for ArgsWeechat {
     fn len(&self) -> usize {
-        self.iter.len()
+        self.back - self.front
     }
 }
 
 impl DoubleEndedIterator for ArgsWeechat {
     fn next_back(&mut self) -> Option<String> {
-        self.iter.next_back()
+        if self.front < self.back {
+            self.back -= 1;
+            Some(std::mem::take(&mut self.args[self.back]))
+        } else {
+            None
+        }
     }
 }
 
@@ -82,6 +186,33 @@ impl OptionChanged {
     }
 }
 
+/// Error returned if the running WeeChat doesn't support a feature, because
+/// it is older than the WeeChat version that added the feature.
+///
+/// The API functions themselves are always available, WeeChat refuses to
+/// load a plugin that was built for another plugin API version. Features
+/// that are selected by a name, e.g. buffer properties such as
+/// `input_prompt`, can still be missing from an older WeeChat with the same
+/// plugin API. Methods that depend on such a feature check the version with
+/// `Weechat::require_version` and return this error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotSupported {
+    /// The name of the feature or API function that isn't supported.
+    pub feature: &'static str,
+}
+
+impl fmt::Display for NotSupported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} isn't supported by this WeeChat version",
+            self.feature
+        )
+    }
+}
+
+impl Error for NotSupported {}
+
 /// Main Weechat struct that encapsulates common weechat API functions.
 /// It has a similar API as the weechat script API.
 pub struct Weechat {
@@ -102,11 +233,53 @@ impl Weechat {
         self.ptr
     }
 
+    /// Get the plugin struct holding the API functions.
+    ///
+    /// WeeChat only loads plugins that were built for its plugin API version,
+    /// see `weechat_plugin_api_version` in the `weechat_plugin!` macro, so
+    /// every function of the struct is set and unwrapping them can't fail.
     #[inline]
     pub(crate) fn get(&self) -> &t_weechat_plugin {
         unsafe { &*self.ptr }
     }
 
+    /// Get the version of the running WeeChat as a number, e.g. `0x04020100`
+    /// for WeeChat 4.2.1.
+    pub fn version_number(&self) -> u32 {
+        self.info_get("version_number", "")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Check that the running WeeChat is at least a certain version.
+    /// * `major` - The major version that is required.
+    /// * `minor` - The minor version that is required.
+    /// * `patch` - The patch version that is required.
+    /// * `feature` - The name of the feature that requires the version, used
+    ///     for the error.
+    pub fn require_version(
+        &self,
+        major: u8,
+        minor: u8,
+        patch: u8,
+        feature: &'static str,
+    ) -> Result<(), NotSupported> {
+        let required = (u32::from(major) << 24)
+            | (u32::from(minor) << 16)
+            | (u32::from(patch) << 8);
+
+        if self.version_number() >= required {
+            Ok(())
+        } else {
+            Err(NotSupported { feature })
+        }
+    }
+
+    /// Get the name of the plugin.
+    pub fn plugin_name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.get().name).to_string_lossy() }
+    }
+
     /// Write a message in WeeChat log file (weechat.log).
     pub fn log(&self, msg: &str) {
         let log_printf = self.get().log_printf.unwrap();
@@ -124,7 +297,7 @@ impl Weechat {
         let printf_datetime_tags = self.get().printf_datetime_tags.unwrap();
 
         let fmt = LossyCString::new("%s");
-        let msg = LossyCString::new(msg);
+        let c_msg = LossyCString::new(msg);
 
         unsafe {
             printf_datetime_tags(
@@ -132,9 +305,11 @@ impl Weechat {
                 0,
                 0,
                 ptr::null(),
-                msg.as_ptr(),
+                c_msg.as_ptr(),
             );
         }
+
+        crate::print_metrics::record(|| "core.weechat".to_owned(), msg);
     }
 
     /// Return a string color code for display.
@@ -218,7 +393,23 @@ impl Weechat {
             if option.is_null() {
                 None
             } else {
-                Some(StringOption::from_ptrs(option, self.ptr))
+                Some(StringOption::from_raw(option, self.ptr))
+            }
+        }
+    }
+
+    /// Get an integer option of WeeChat or a plugin by its full name.
+    pub fn get_integer_option(&self, option: &str) -> Option<IntegerOption> {
+        let config_get = self.get().config_get.unwrap();
+
+        let option_name = LossyCString::new(option);
+
+        unsafe {
+            let option = config_get(option_name.as_ptr());
+            if option.is_null() {
+                None
+            } else {
+                Some(IntegerOption::from_raw(option, self.ptr))
             }
         }
     }
@@ -268,4 +459,23 @@ impl Weechat {
             }
         }
     }
+
+    /// Format a size in bytes as a human readable string, e.g. "15.2 MB".
+    /// * `size` - The size in bytes.
+    pub fn string_format_size(&self, size: u64) -> String {
+        let string_format_size = self.get().string_format_size.unwrap();
+
+        unsafe {
+            let result = string_format_size(size);
+
+            if result.is_null() {
+                size.to_string()
+            } else {
+                let formatted =
+                    CStr::from_ptr(result).to_string_lossy().into_owned();
+                libc::free(result as *mut libc::c_void);
+                formatted
+            }
+        }
+    }
 }
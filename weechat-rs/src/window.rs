@@ -0,0 +1,129 @@
+//! Weechat Window module containing the Window type.
+
+use std::os::raw::c_void;
+use std::ptr;
+
+use weechat_sys::{t_gui_buffer, t_gui_window, t_weechat_plugin};
+
+use crate::hdata::{HData, HasHData};
+use crate::{Buffer, LossyCString, Weechat};
+
+/// A high level Window type encapsulating weechats C window pointer.
+pub struct Window {
+    pub(crate) weechat: *mut t_weechat_plugin,
+    pub(crate) ptr: *mut t_gui_window,
+}
+
+impl PartialEq for Window {
+    fn eq(&self, other: &Window) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl Window {
+    pub(crate) fn from_ptr(
+        weechat_ptr: *mut t_weechat_plugin,
+        window_ptr: *mut t_gui_window,
+    ) -> Window {
+        Window {
+            weechat: weechat_ptr,
+            ptr: window_ptr,
+        }
+    }
+
+    fn get_integer(&self, property: &str) -> i32 {
+        let weechat = Weechat::from_ptr(self.weechat);
+        let window_get_integer = weechat.get().window_get_integer.unwrap();
+
+        let property = LossyCString::new(property);
+
+        unsafe { window_get_integer(self.ptr, property.as_ptr()) }
+    }
+
+    /// Get the number of the window.
+    pub fn number(&self) -> i32 {
+        self.get_integer("number")
+    }
+
+    /// Get the width of the window in chars.
+    pub fn width(&self) -> i32 {
+        self.get_integer("win_width")
+    }
+
+    /// Get the height of the window in chars.
+    pub fn height(&self) -> i32 {
+        self.get_integer("win_height")
+    }
+
+    /// Get the buffer that is displayed in the window.
+    pub fn buffer(&self) -> Buffer {
+        let weechat = Weechat::from_ptr(self.weechat);
+        let window_get_pointer = weechat.get().window_get_pointer.unwrap();
+
+        let property = LossyCString::new("buffer");
+
+        let buffer_ptr =
+            unsafe { window_get_pointer(self.ptr, property.as_ptr()) };
+
+        Buffer::wrap(self.weechat, buffer_ptr as *mut t_gui_buffer)
+    }
+}
+
+impl HasHData for Window {
+    fn get_hdata(&self, name: &str) -> Option<HData> {
+        let hdata_get =
+            Weechat::from_ptr(self.weechat).get().hdata_get.unwrap();
+
+        let name = LossyCString::new(name);
+
+        unsafe {
+            let hdata = hdata_get(self.weechat, name.as_ptr());
+            if hdata.is_null() {
+                None
+            } else {
+                Some(HData {
+                    weechat_ptr: self.weechat,
+                    object: self.ptr as *mut c_void,
+                    ptr: hdata,
+                })
+            }
+        }
+    }
+}
+
+impl Weechat {
+    /// Get the current window.
+    pub fn current_window(&self) -> Option<Window> {
+        let window_get_pointer = self.get().window_get_pointer.unwrap();
+
+        let property = LossyCString::new("current");
+
+        let window_ptr =
+            unsafe { window_get_pointer(ptr::null_mut(), property.as_ptr()) };
+
+        if window_ptr.is_null() {
+            None
+        } else {
+            Some(Window::from_ptr(self.ptr, window_ptr as *mut t_gui_window))
+        }
+    }
+}
+
+impl Buffer {
+    /// Get the window that displays the buffer.
+    ///
+    /// Returns None if the buffer isn't displayed in any window.
+    pub fn window(&self) -> Option<Window> {
+        let weechat = Weechat::from_ptr(self.weechat);
+        let window_search_with_buffer =
+            weechat.get().window_search_with_buffer.unwrap();
+
+        let window_ptr = unsafe { window_search_with_buffer(self.ptr) };
+
+        if window_ptr.is_null() {
+            None
+        } else {
+            Some(Window::from_ptr(self.weechat, window_ptr))
+        }
+    }
+}
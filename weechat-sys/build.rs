@@ -1,9 +1,95 @@
 use bindgen::Bindings;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 const WEECHAT_BUNDLED_ENV: &str = "WEECHAT_BUNDLED";
 const WEECHAT_PLUGIN_FILE_ENV: &str = "WEECHAT_PLUGIN_FILE";
+const WEECHAT_PLUGIN_INCLUDE_DIR_ENV: &str = "WEECHAT_PLUGIN_INCLUDE_DIR";
+
+/// Include directories WeeChat is commonly installed into, checked if
+/// pkg-config doesn't know about WeeChat.
+const INCLUDE_PREFIXES: &[&str] = &[
+    "/usr/include",
+    "/usr/local/include",
+    "/opt/homebrew/include",
+    "/opt/local/include",
+];
+
+/// Look for `weechat-plugin.h` inside of an include directory, either in a
+/// `weechat` subdirectory or directly in the directory itself.
+fn header_in(dir: &Path) -> Option<PathBuf> {
+    let candidates = [
+        dir.join("weechat").join("weechat-plugin.h"),
+        dir.join("weechat-plugin.h"),
+    ];
+
+    candidates.iter().find(|path| path.is_file()).cloned()
+}
+
+/// Ask pkg-config for the include directories of WeeChat.
+fn pkg_config_include_dirs() -> Vec<PathBuf> {
+    let output = Command::new("pkg-config")
+        .args(["--cflags-only-I", "weechat"])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .filter_map(|flag| flag.strip_prefix("-I"))
+                .map(PathBuf::from)
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Convert the path of a header into a string for bindgen.
+fn header_path_str(header: PathBuf) -> Result<String, PathBuf> {
+    header.into_os_string().into_string().map_err(PathBuf::from)
+}
+
+/// Find the plugin header of the installed WeeChat.
+///
+/// The include directory from the environment wins, then the directories
+/// pkg-config reports and last the common install prefixes. Headers whose
+/// path isn't valid UTF-8 can't be passed to bindgen and are skipped.
+///
+/// Panics if the include directory from the environment doesn't contain the
+/// header, falling back to another WeeChat would hide the mistake.
+fn find_plugin_header() -> Option<String> {
+    if let Some(dir) = env::var_os(WEECHAT_PLUGIN_INCLUDE_DIR_ENV) {
+        let dir = PathBuf::from(dir);
+        let header = header_in(&dir).unwrap_or_else(|| {
+            panic!(
+                "No weechat-plugin.h found in {}={}",
+                WEECHAT_PLUGIN_INCLUDE_DIR_ENV,
+                dir.display()
+            )
+        });
+
+        return Some(header_path_str(header).unwrap_or_else(|header| {
+            panic!("The path {} isn't valid UTF-8", header.display())
+        }));
+    }
+
+    pkg_config_include_dirs()
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(INCLUDE_PREFIXES.iter().map(Path::new))
+        .filter_map(header_in)
+        .find_map(|header| match header_path_str(header) {
+            Ok(header) => Some(header),
+            Err(header) => {
+                println!(
+                    "cargo:warning=Skipping {}, the path isn't valid UTF-8",
+                    header.display()
+                );
+                None
+            }
+        })
+}
 
 fn build(file: &str) -> Result<Bindings, ()> {
     const INCLUDED_TYPES: &[&str] = &[
@@ -51,7 +137,6 @@ fn main() {
 
     let plugin_file = env::var(WEECHAT_PLUGIN_FILE_ENV);
 
-
     let bindings = if bundled {
         build("src/weechat-plugin.h").expect("Unable to generate bindings")
     } else {
@@ -66,7 +151,13 @@ fn main() {
                 ))
             }
             Err(_) => {
-                let bindings = build("src/wrapper.h");
+                let bindings = match find_plugin_header() {
+                    Some(header) => {
+                        println!("cargo:rerun-if-changed={}", header);
+                        build(&header)
+                    }
+                    None => build("src/wrapper.h"),
+                };
 
                 match bindings {
                     Ok(b) => b,
@@ -78,6 +169,8 @@ fn main() {
 
     println!("cargo:rerun-if-env-changed={}", WEECHAT_BUNDLED_ENV);
     println!("cargo:rerun-if-env-changed={}", WEECHAT_PLUGIN_FILE_ENV);
+    println!("cargo:rerun-if-env-changed={}", WEECHAT_PLUGIN_INCLUDE_DIR_ENV);
+    println!("cargo:rerun-if-env-changed=PKG_CONFIG_PATH");
 
     let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
     bindings